@@ -0,0 +1,269 @@
+#![allow(dead_code)]
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::gates::{self, Bit, Bit16};
+use crate::netlist::{Eval, Logic, Netlist, Sliced};
+
+/*
+ 16-BIT ADDERS
+
+ adder           | carries
+ ----------------+-----------------------------------------------------------
+ ripple          | chain of 16 full adders (same circuit as gates::add_16)
+ carry-lookahead | 4-bit blocks, carries expanded from generate / propagate,
+                 | a second lookahead level computes the block carries
+ kogge-stone     | parallel prefix of (generate, propagate) in log2(16) steps
+ carry-select    | 4-bit ripple blocks computed for carry 0 and carry 1,
+                 | the incoming carry picks one with a mux
+
+ per bit:  g = a & b  (generate)   p = a ^ b  (propagate)
+           c(i+1) = g(i) | p(i) & c(i)      sum(i) = p(i) ^ c(i)
+*/
+
+const BLOCK: usize = 4;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Adder {
+  #[default]
+  Ripple,
+  CarryLookahead,
+  KoggeStone,
+  CarrySelect,
+}
+
+impl Adder {
+  pub const ALL: [Adder; 4] = [Adder::Ripple, Adder::CarryLookahead, Adder::KoggeStone, Adder::CarrySelect];
+
+  pub fn name(self) -> &'static str {
+    match self {
+      Adder::Ripple => "ripple",
+      Adder::CarryLookahead => "carry-lookahead",
+      Adder::KoggeStone => "kogge-stone",
+      Adder::CarrySelect => "carry-select",
+    }
+  }
+}
+
+impl fmt::Display for Adder {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str(self.name())
+  }
+}
+
+impl FromStr for Adder {
+  type Err = String;
+
+  fn from_str(name: &str) -> Result<Self, String> {
+    Adder::ALL.into_iter().find(|adder| adder.name() == name).ok_or_else(|| format!("unknown adder '{}'", name))
+  }
+}
+
+// a + b + carry, returns the sum (MSB first) and the carry out of bit 15
+pub fn add<L: Logic>(
+  logic: &mut L,
+  adder: Adder,
+  a: [L::Signal; 16],
+  b: [L::Signal; 16],
+  carry: L::Signal,
+) -> ([L::Signal; 16], L::Signal) {
+  // the circuits below work LSB first
+  let a: Vec<L::Signal> = a.into_iter().rev().collect();
+  let b: Vec<L::Signal> = b.into_iter().rev().collect();
+  let (sum, carry) = match adder {
+    Adder::Ripple => ripple(logic, &a, &b, carry),
+    Adder::CarryLookahead => carry_lookahead(logic, &a, &b, carry),
+    Adder::KoggeStone => kogge_stone(logic, &a, &b, carry),
+    Adder::CarrySelect => carry_select(logic, &a, &b, carry),
+  };
+  let mut out = [sum[0]; 16];
+  for (pos, bit) in sum.into_iter().rev().enumerate() {
+    out[pos] = bit;
+  }
+  (out, carry)
+}
+
+pub fn add_16(adder: Adder, a: Bit16, b: Bit16) -> Bit16 {
+  add(&mut Eval, adder, a, b, 0).0
+}
+
+// 64 sums at once on sliced buses, see `netlist::pack_16`
+pub fn batch_add_16(adder: Adder, a: [u64; 16], b: [u64; 16]) -> [u64; 16] {
+  add(&mut Sliced, adder, a, b, 0).0
+}
+
+// sum, carry out and signed overflow, like gates::add_16_flags
+pub fn add_16_flags(adder: Adder, a: Bit16, b: Bit16) -> (Bit16, Bit, Bit) {
+  add_16_carry(adder, a, b, 0)
+}
+
+// a + b + carry with the flags, like gates::add_16_carry
+pub fn add_16_carry(adder: Adder, a: Bit16, b: Bit16, carry: Bit) -> (Bit16, Bit, Bit) {
+  let (sum, carry) = add(&mut Eval, adder, a, b, carry);
+  (sum, carry, gates::overflow(a[0], b[0], sum[0]))
+}
+
+// inputs a[0..16], b[0..16], outputs sum[0..16]
+pub fn netlist(adder: Adder) -> Netlist {
+  let mut netlist = Netlist::new();
+  let a = netlist.input_bus::<16>("a");
+  let b = netlist.input_bus::<16>("b");
+  let carry = netlist.constant(0);
+  let (sum, _) = add(&mut netlist, adder, a, b, carry);
+  netlist.output_bus("sum", &sum);
+  netlist
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Report {
+  pub adder: Adder,
+  pub nands: usize,
+  pub depth: usize,
+}
+
+pub fn report(adder: Adder) -> Report {
+  let netlist = netlist(adder);
+  Report { adder, nands: netlist.nand_count(), depth: netlist.depth() }
+}
+
+// NAND count and depth of every adder, as a table
+pub fn report_table() -> String {
+  let mut out = format!("{:<16} | {:>5} | {:>5}\n", "adder", "nands", "depth");
+  out.push_str(&format!("{:-<16}-+-{:->5}-+-{:->5}\n", "", "", ""));
+  for report in Adder::ALL.map(report) {
+    out.push_str(&format!("{:<16} | {:>5} | {:>5}\n", report.adder.name(), report.nands, report.depth));
+  }
+  out
+}
+
+// same structure as gates::full_adder (two half adders and an or)
+fn full_adder<L: Logic>(logic: &mut L, a: L::Signal, b: L::Signal, c: L::Signal) -> (L::Signal, L::Signal) {
+  let (sum_one, carry_one) = (logic.xor(a, b), logic.and(a, b));
+  let (sum_two, carry_two) = (logic.xor(sum_one, c), logic.and(sum_one, c));
+  (sum_two, logic.or(carry_one, carry_two))
+}
+
+fn ripple<L: Logic>(
+  logic: &mut L,
+  a: &[L::Signal],
+  b: &[L::Signal],
+  mut carry: L::Signal,
+) -> (Vec<L::Signal>, L::Signal) {
+  let mut sum = Vec::with_capacity(a.len());
+  for (&bit_a, &bit_b) in a.iter().zip(b) {
+    let (bit, next) = full_adder(logic, bit_a, bit_b, carry);
+    sum.push(bit);
+    carry = next;
+  }
+  (sum, carry)
+}
+
+fn generate_propagate<L: Logic>(logic: &mut L, a: &[L::Signal], b: &[L::Signal]) -> (Vec<L::Signal>, Vec<L::Signal>) {
+  a.iter().zip(b).map(|(&bit_a, &bit_b)| (logic.and(bit_a, bit_b), logic.xor(bit_a, bit_b))).unzip()
+}
+
+// c(i+1) = g(i) | p(i) g(i-1) | ... | p(i) .. p(0) c(0), every term in parallel
+fn lookahead<L: Logic>(logic: &mut L, g: &[L::Signal], p: &[L::Signal], carry: L::Signal) -> Vec<L::Signal> {
+  (0..g.len())
+    .map(|i| {
+      let mut terms = Vec::with_capacity(i + 2);
+      for j in 0..=i {
+        let mut factors = p[j + 1..=i].to_vec();
+        factors.push(g[j]);
+        terms.push(logic.and_n(&factors));
+      }
+      let mut factors = p[..=i].to_vec();
+      factors.push(carry);
+      terms.push(logic.and_n(&factors));
+      logic.or_n(&terms)
+    })
+    .collect()
+}
+
+// block generate / propagate: the block makes a carry / passes the incoming one
+fn group<L: Logic>(logic: &mut L, g: &[L::Signal], p: &[L::Signal]) -> (L::Signal, L::Signal) {
+  let terms: Vec<L::Signal> = (0..g.len())
+    .map(|j| {
+      let mut factors = p[j + 1..].to_vec();
+      factors.push(g[j]);
+      logic.and_n(&factors)
+    })
+    .collect();
+  (logic.or_n(&terms), logic.and_n(p))
+}
+
+fn carry_lookahead<L: Logic>(
+  logic: &mut L,
+  a: &[L::Signal],
+  b: &[L::Signal],
+  carry: L::Signal,
+) -> (Vec<L::Signal>, L::Signal) {
+  let (g, p) = generate_propagate(logic, a, b);
+  let (block_g, block_p): (Vec<_>, Vec<_>) =
+    g.chunks(BLOCK).zip(p.chunks(BLOCK)).map(|(g, p)| group(logic, g, p)).unzip();
+  let block_carries = lookahead(logic, &block_g, &block_p, carry);
+
+  let mut sum = Vec::with_capacity(a.len());
+  let mut block_carry = carry;
+  for (block, (g, p)) in g.chunks(BLOCK).zip(p.chunks(BLOCK)).enumerate() {
+    let carries = lookahead(logic, g, p, block_carry);
+    // carry into each bit: the block carry, then the lookahead carries
+    for (pos, &bit) in p.iter().enumerate() {
+      let incoming = if pos == 0 { block_carry } else { carries[pos - 1] };
+      sum.push(logic.xor(bit, incoming));
+    }
+    block_carry = block_carries[block];
+  }
+  (sum, block_carry)
+}
+
+fn kogge_stone<L: Logic>(
+  logic: &mut L,
+  a: &[L::Signal],
+  b: &[L::Signal],
+  carry: L::Signal,
+) -> (Vec<L::Signal>, L::Signal) {
+  let (mut g, mut p) = generate_propagate(logic, a, b);
+  let half_sums = p.clone();
+
+  // fold the carry in, then (g, p)(i) covers bits i ..= 0 after the last step
+  let carried = logic.and(p[0], carry);
+  g[0] = logic.or(g[0], carried);
+  let mut distance = 1;
+  while distance < a.len() {
+    let (prev_g, prev_p) = (g.clone(), p.clone());
+    for i in distance..a.len() {
+      let passed = logic.and(prev_p[i], prev_g[i - distance]);
+      g[i] = logic.or(prev_g[i], passed);
+      p[i] = logic.and(prev_p[i], prev_p[i - distance]);
+    }
+    distance *= 2;
+  }
+
+  let mut sum = Vec::with_capacity(a.len());
+  for (pos, &bit) in half_sums.iter().enumerate() {
+    let incoming = if pos == 0 { carry } else { g[pos - 1] };
+    sum.push(logic.xor(bit, incoming));
+  }
+  (sum, g[a.len() - 1])
+}
+
+fn carry_select<L: Logic>(
+  logic: &mut L,
+  a: &[L::Signal],
+  b: &[L::Signal],
+  carry: L::Signal,
+) -> (Vec<L::Signal>, L::Signal) {
+  let (mut sum, mut carry) = ripple(logic, &a[..BLOCK], &b[..BLOCK], carry);
+  let (zero, one) = (logic.constant(0), logic.constant(1));
+  for (a, b) in a[BLOCK..].chunks(BLOCK).zip(b[BLOCK..].chunks(BLOCK)) {
+    let (sum_zero, carry_zero) = ripple(logic, a, b, zero);
+    let (sum_one, carry_one) = ripple(logic, a, b, one);
+    for (bit_zero, bit_one) in sum_zero.into_iter().zip(sum_one) {
+      sum.push(logic.mux(bit_zero, bit_one, carry));
+    }
+    carry = logic.mux(carry_zero, carry_one, carry);
+  }
+  (sum, carry)
+}
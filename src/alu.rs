@@ -1,6 +1,10 @@
 #![allow(dead_code)]
 
+use crate::adders::{self, Adder};
+use crate::arith;
 use crate::gates::{self, Bit, Bit16};
+use crate::netlist::{Logic, Netlist, Sliced};
+use crate::shifter::{self, Shift};
 
 /*
 + ---------------------------------- +
@@ -35,12 +39,49 @@ Key:
 
   zr: if out == 0 then zr = 1 else zr = 0
   ng: if out < 0 then ng = 1 else ng = 0
+  c:  carry out of the adder (0 when f == 0)
+  v:  signed overflow of the adder (0 when f == 0), `no` keeps it valid for
+      x-y, y-x, -x, ... since !r == -r-1 maps the 16-bit range onto itself
+
+extended mode (`set_ext`): the f stage computes `ext` instead of x+y / x&y,
+zx nx zy ny and no still apply around it, c and v are 0
+
+  ext   | out
+  ------+------------------------------------
+  mul   | x * y  (low 16 bits)
+  div   | x / y  (unsigned)
+  rem   | x % y  (unsigned)
+  shift | x shifted / rotated by y & 15
+  carry | carry out of x + y, or borrow of x - y (0 / 1)
+  ovf   | signed overflow of x + y or x - y (0 / 1)
 
 papers:
   - [Introduction to “The First Draft Report on the EDVAC” by John von Neumann]
   (https://people.csail.mit.edu/brooks/idocs/VonNeumann_EDVAC.pdf)
 */
 
+// every ALU flag, see `ALU::execute_flags`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Flags {
+  pub zr: Bit,
+  pub ng: Bit,
+  pub c: Bit,
+  pub v: Bit,
+}
+
+// operations of the extended ALU mode, see `ALU::set_ext`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExtOp {
+  Mul,
+  Div,
+  Rem,
+  Shift(Shift),
+  AddCarry,
+  SubBorrow,
+  AddOverflow,
+  SubOverflow,
+}
+
 pub struct ALU {
   // input
   x: Bit16,
@@ -49,6 +90,8 @@ pub struct ALU {
   out: Bit16,
   zr: Bit, // if out == 0, zr = 1 else zr = 0 (zero flag)
   ng: Bit, // if out < 0, ng = 1 else ng = 0 (negative flag)
+  c: Bit,  // carry out of the adder (carry flag)
+  v: Bit,  // signed overflow of the adder (overflow flag)
 
   // control bits
   zx: Bit, // if zx ==1 than x = 0
@@ -57,6 +100,11 @@ pub struct ALU {
   ny: Bit, // negate the y input
   f: Bit,  // f == 1, out=add(x,y), else out=and(x,y)
   no: Bit, // negate the out
+
+  // circuit used for f == 1
+  adder: Adder,
+  // replaces the f stage when set
+  ext: Option<ExtOp>,
 }
 
 impl Default for ALU {
@@ -69,7 +117,23 @@ impl Default for ALU {
 
 impl ALU {
   pub fn new(x: Bit16, y: Bit16) -> Self {
-    Self { x, y, out: [0; 16], zr: 0, ng: 0, zx: 0, nx: 0, zy: 0, ny: 0, f: 0, no: 0 }
+    Self {
+      x,
+      y,
+      out: [0; 16],
+      zr: 0,
+      ng: 0,
+      c: 0,
+      v: 0,
+      zx: 0,
+      nx: 0,
+      zy: 0,
+      ny: 0,
+      f: 0,
+      no: 0,
+      adder: Adder::Ripple,
+      ext: None,
+    }
   }
 
   // setters control bits
@@ -93,6 +157,18 @@ impl ALU {
     self.no = bit;
   }
 
+  pub fn set_adder(&mut self, adder: Adder) {
+    self.adder = adder;
+  }
+
+  pub fn adder(&self) -> Adder {
+    self.adder
+  }
+
+  pub fn set_ext(&mut self, ext: Option<ExtOp>) {
+    self.ext = ext;
+  }
+
   pub fn set_controls(&mut self, bits: [Bit; 6]) {
     self.set_zx(bits[0]);
     self.set_nx(bits[1]);
@@ -132,7 +208,25 @@ impl ALU {
   }
 
   fn fn_out(&mut self) {
-    self.out = if self.f == 1 { gates::add_16(self.x, self.y) } else { gates::and_16(self.x, self.y) };
+    if let Some(ext) = self.ext {
+      self.out = match ext {
+        ExtOp::Mul => arith::mul_16(self.x, self.y),
+        ExtOp::Div => arith::div_16(self.x, self.y).0,
+        ExtOp::Rem => arith::div_16(self.x, self.y).1,
+        ExtOp::Shift(op) => shifter::shift(self.x, self.y[12..16].try_into().unwrap(), op),
+        ExtOp::AddCarry => flag(adders::add_16_flags(self.adder, self.x, self.y).1),
+        ExtOp::SubBorrow => flag(gates::not(subtract(self.adder, self.x, self.y).1)),
+        ExtOp::AddOverflow => flag(adders::add_16_flags(self.adder, self.x, self.y).2),
+        ExtOp::SubOverflow => flag(subtract(self.adder, self.x, self.y).2),
+      };
+      (self.c, self.v) = (0, 0);
+      return;
+    }
+    (self.out, self.c, self.v) = if self.f == 1 {
+      adders::add_16_flags(self.adder, self.x, self.y)
+    } else {
+      (gates::and_16(self.x, self.y), 0, 0)
+    };
   }
 
   fn negate_out(&mut self) {
@@ -165,7 +259,13 @@ impl ALU {
     // 5.
     self.zero_flag();
     self.negate_flag();
-    return (self.out, self.zr, self.ng);
+    (self.out, self.zr, self.ng)
+  }
+
+  // like `execute`, with the carry and overflow flags as well
+  pub fn execute_flags(&mut self) -> (Bit16, Flags) {
+    let (out, zr, ng) = self.execute();
+    (out, Flags { zr, ng, c: self.c, v: self.v })
   }
 
   // debug
@@ -188,3 +288,64 @@ impl ALU {
     println!("+ -------------------------------------------------- +");
   }
 }
+
+// x - y = x + !y + 1, the carry out is 1 when nothing was borrowed
+fn subtract(adder: Adder, x: Bit16, y: Bit16) -> (Bit16, Bit, Bit) {
+  adders::add_16_carry(adder, x, gates::not_16(y), 1)
+}
+
+// a flag as a 16-bit value
+fn flag(bit: Bit) -> Bit16 {
+  let mut out = [0; 16];
+  out[15] = bit;
+  out
+}
+
+// the same ALU (without the extended ops) as a circuit over `Logic`:
+// controls are zx nx zy ny f no, returns (out, zr, ng)
+pub fn circuit<L: Logic>(
+  logic: &mut L,
+  adder: Adder,
+  x: [L::Signal; 16],
+  y: [L::Signal; 16],
+  controls: [L::Signal; 6],
+) -> ([L::Signal; 16], L::Signal, L::Signal) {
+  let [zx, nx, zy, ny, f, no] = controls;
+  // zeroing is an and with the inverted control, negating an xor
+  let input = |logic: &mut L, bus: [L::Signal; 16], zero: L::Signal, negate: L::Signal| {
+    let keep = logic.not(zero);
+    bus.map(|bit| {
+      let kept = logic.and(bit, keep);
+      logic.xor(kept, negate)
+    })
+  };
+  let (x, y) = (input(logic, x, zx, nx), input(logic, y, zy, ny));
+  let zero = logic.constant(0);
+  let (sum, _) = adders::add(logic, adder, x, y, zero);
+  let mut out = x;
+  for pos in 0..16 {
+    let and = logic.and(x[pos], y[pos]);
+    let selected = logic.mux(and, sum[pos], f);
+    out[pos] = logic.xor(selected, no);
+  }
+  let any = logic.or_n(&out);
+  (out, logic.not(any), out[0])
+}
+
+// `circuit` on 64 sliced operand pairs and controls at once, see `netlist::pack_16`
+pub fn batch_eval(adder: Adder, x: [u64; 16], y: [u64; 16], controls: [u64; 6]) -> ([u64; 16], u64, u64) {
+  circuit(&mut Sliced, adder, x, y, controls)
+}
+
+// inputs x[0..16], y[0..16], zx nx zy ny f no, outputs out[0..16], zr, ng
+pub fn netlist(adder: Adder) -> Netlist {
+  let mut netlist = Netlist::new();
+  let x = netlist.input_bus::<16>("x");
+  let y = netlist.input_bus::<16>("y");
+  let controls = ["zx", "nx", "zy", "ny", "f", "no"].map(|name| netlist.input(name));
+  let (out, zr, ng) = circuit(&mut netlist, adder, x, y, controls);
+  netlist.output_bus("out", &out);
+  netlist.output("zr", zr);
+  netlist.output("ng", ng);
+  netlist
+}
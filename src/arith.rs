@@ -0,0 +1,69 @@
+#![allow(dead_code)]
+
+use crate::gates::{self, Bit, Bit16};
+
+/*
+ MULTIPLIER (shift-and-add array)
+
+ one row per bit of b: the row is `a << i` masked by b(i), the rows are summed
+ by a column of add_16. only the low 16 bits are kept, so the product is the
+ same for signed and unsigned operands (wrapping, like u16 / i16 `*`).
+
+   a       =          a15 .. a1 a0
+   row 0   = (a << 0) & b0
+   row 1   = (a << 1) & b1
+   ...
+   row 15  = (a << 15) & b15
+   out     = row 0 + row 1 + ... + row 15
+
+ RESTORING DIVIDER (unsigned)
+
+ one stage per bit of a, MSB first:
+
+   r = (r << 1) | a(i)
+   if r >= b { r = r - b; q(i) = 1 } else { q(i) = 0 }
+
+ r - b is add_16(r, !b) with a carry in, its carry out is r >= b. the bit
+ shifted out of r counts as well (r is 17 bits wide between stages).
+ dividing by zero gives q = 0xffff and r = a, like most hardware dividers.
+*/
+
+pub fn mul_16(a: Bit16, b: Bit16) -> Bit16 {
+  let mut out = [0; 16];
+  for shift in 0..16 {
+    // b is MSB first: bit `shift` of the value is b[15 - shift]
+    let row = gates::and_16(shift_left(a, shift), [b[15 - shift]; 16]);
+    out = gates::add_16(out, row);
+  }
+  out
+}
+
+// (quotient, remainder) of a / b, unsigned
+pub fn div_16(a: Bit16, b: Bit16) -> (Bit16, Bit16) {
+  let not_b = gates::not_16(b);
+  let mut quotient = [0; 16];
+  let mut remainder = [0; 16];
+  for pos in 0..16 {
+    let high = remainder[0];
+    let shifted = shift_in(remainder, a[pos]);
+    let (difference, no_borrow, _) = gates::add_16_carry(shifted, not_b, 1);
+    let fits = gates::or(high, no_borrow);
+    remainder = gates::mux16(shifted, difference, fits);
+    quotient[pos] = fits;
+  }
+  (quotient, remainder)
+}
+
+// wiring only: drop the top `shift` bits, fill with zeros
+fn shift_left(a: Bit16, shift: usize) -> Bit16 {
+  let mut out = [0; 16];
+  out[..16 - shift].copy_from_slice(&a[shift..]);
+  out
+}
+
+// (r << 1) | bit
+fn shift_in(a: Bit16, bit: Bit) -> Bit16 {
+  let mut out = shift_left(a, 1);
+  out[15] = bit;
+  out
+}
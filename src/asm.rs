@@ -0,0 +1,236 @@
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+
+use crate::gates::{self, Bit, Bit16};
+use crate::instruction::{Group, Isa, COMP_TABLE, JUMP_TABLE};
+use crate::mem::rom32k::ROM32K;
+
+/*
+ HACK ASSEMBLER
+
+ two passes over the source:
+   1. record the ROM address of every `(LABEL)`
+   2. translate instructions, allocating `@variables` from RAM[16] upwards
+
+ predefined symbols:
+   SP LCL ARG THIS THAT -> 0 .. 4
+   R0 .. R15            -> 0 .. 15
+   SCREEN               -> 16384
+   KBD                  -> 24576
+
+ the hack-ext instruction set adds shifts (`D=D<<A`, `M=M>>`, `A=ROL(D)`),
+ multiply / divide (`D=D*A`, `D=M%D`) and flags (`D=C(D+A)`), see
+ `instruction.rs`. `assemble` only accepts stock Hack, `assemble_isa` picks.
+*/
+
+const VARIABLE_BASE: u16 = 16;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SymbolTable {
+  // ROM addresses of `(LABEL)` declarations
+  labels: HashMap<String, u16>,
+  // RAM addresses of predefined symbols and `@variables`
+  variables: HashMap<String, u16>,
+}
+
+impl Default for SymbolTable {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl SymbolTable {
+  pub fn new() -> Self {
+    let mut variables = HashMap::new();
+    for (name, address) in
+      [("SP", 0), ("LCL", 1), ("ARG", 2), ("THIS", 3), ("THAT", 4), ("SCREEN", 16384), ("KBD", 24576)]
+    {
+      variables.insert(name.to_string(), address);
+    }
+    for register in 0..16 {
+      variables.insert(format!("R{}", register), register);
+    }
+    Self { labels: HashMap::new(), variables }
+  }
+
+  pub fn label(&self, name: &str) -> Option<u16> {
+    self.labels.get(name).copied()
+  }
+
+  pub fn variable(&self, name: &str) -> Option<u16> {
+    self.variables.get(name).copied()
+  }
+
+  // first label (alphabetically) declared at a ROM address
+  pub fn label_at(&self, address: u16) -> Option<&str> {
+    self.labels.iter().filter(|(_, &at)| at == address).map(|(name, _)| name.as_str()).min()
+  }
+
+  pub fn labels(&self) -> &HashMap<String, u16> {
+    &self.labels
+  }
+
+  pub fn variables(&self) -> &HashMap<String, u16> {
+    &self.variables
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Program {
+  pub words: Vec<Bit16>,
+  pub symbols: SymbolTable,
+}
+
+pub fn assemble(source: &str) -> Result<Program, String> {
+  assemble_isa(source, Isa::Hack)
+}
+
+pub fn assemble_isa(source: &str, isa: Isa) -> Result<Program, String> {
+  let lines: Vec<(usize, &str)> = source
+    .lines()
+    .enumerate()
+    .map(|(number, line)| (number + 1, line.split("//").next().unwrap().trim()))
+    .filter(|(_, line)| !line.is_empty())
+    .collect();
+
+  // 1. labels
+  let mut symbols = SymbolTable::new();
+  let mut address = 0u16;
+  for &(number, line) in &lines {
+    if let Some(label) = line.strip_prefix('(') {
+      let label = label.strip_suffix(')').ok_or_else(|| format!("line {}: unclosed label '{}'", number, line))?;
+      check_symbol(number, label)?;
+      if symbols.labels.insert(label.to_string(), address).is_some() {
+        return Err(format!("line {}: duplicated label '{}'", number, label));
+      }
+    } else if address as usize == ROM32K::SIZE {
+      return Err(format!("line {}: program does not fit in ROM32K ({} words)", number, ROM32K::SIZE));
+    } else {
+      address += 1;
+    }
+  }
+
+  // 2. instructions
+  let mut next_variable = VARIABLE_BASE;
+  let mut words = Vec::new();
+  for &(number, line) in &lines {
+    if line.starts_with('(') {
+      continue;
+    }
+    let word = match line.strip_prefix('@') {
+      Some(value) => a_instruction(number, value, &mut symbols, &mut next_variable)?,
+      None => c_instruction(number, line, isa)?,
+    };
+    words.push(word);
+  }
+  Ok(Program { words, symbols })
+}
+
+fn check_symbol(number: usize, symbol: &str) -> Result<(), String> {
+  let valid_char = |c: char| c.is_ascii_alphanumeric() || "_.$:".contains(c);
+  if symbol.is_empty() || symbol.starts_with(|c: char| c.is_ascii_digit()) || !symbol.chars().all(valid_char) {
+    return Err(format!("line {}: invalid symbol '{}'", number, symbol));
+  }
+  Ok(())
+}
+
+fn a_instruction(
+  number: usize,
+  value: &str,
+  symbols: &mut SymbolTable,
+  next_variable: &mut u16,
+) -> Result<Bit16, String> {
+  let value = if value.starts_with(|c: char| c.is_ascii_digit()) {
+    match value.parse::<u16>() {
+      Ok(value) if value < 0x8000 => value,
+      _ => return Err(format!("line {}: constant '{}' does not fit in 15 bits", number, value)),
+    }
+  } else if let Some(address) = symbols.label(value).or_else(|| symbols.variable(value)) {
+    address
+  } else {
+    check_symbol(number, value)?;
+    let address = *next_variable;
+    symbols.variables.insert(value.to_string(), address);
+    *next_variable += 1;
+    address
+  };
+  Ok(gates::from_u16(value))
+}
+
+fn c_instruction(number: usize, line: &str, isa: Isa) -> Result<Bit16, String> {
+  let (dest, rest) = match line.split_once('=') {
+    Some((dest, rest)) => (dest.trim(), rest.trim()),
+    None => ("", line),
+  };
+  let (comp, jump) = match rest.split_once(';') {
+    Some((comp, jump)) => (comp.trim(), jump.trim()),
+    None => (rest, ""),
+  };
+
+  let (group, a, comp_bits) = match encode_comp(comp) {
+    Some((a, bits)) => (Group::Alu, a, bits),
+    None => encode_ext(comp).ok_or_else(|| format!("line {}: unknown computation '{}'", number, comp))?,
+  };
+  if group != Group::Alu && isa != Isa::HackExt {
+    return Err(format!("line {}: '{}' needs the {} instruction set", number, comp, Isa::HackExt));
+  }
+  let dest_bits = encode_dest(dest).ok_or_else(|| format!("line {}: unknown destination '{}'", number, dest))?;
+  let jump_bits = encode_table(&JUMP_TABLE, jump).ok_or_else(|| format!("line {}: unknown jump '{}'", number, jump))?;
+
+  let [x, y] = group.prefix();
+  let mut word = [1, x, y, a, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+  word[4..10].copy_from_slice(&comp_bits);
+  word[10..13].copy_from_slice(&dest_bits);
+  word[13..16].copy_from_slice(&jump_bits);
+  Ok(word)
+}
+
+pub fn encode_comp(comp: &str) -> Option<(Bit, [Bit; 6])> {
+  let comp: String = comp.chars().filter(|c| !c.is_whitespace()).collect();
+  let a = if comp.contains('M') { 1 } else { 0 };
+  if a == 1 && comp.contains('A') {
+    return None;
+  }
+  let comp = comp.replace('M', "A");
+  // commutative forms: A+D, A&D, A|D
+  let swapped = match comp.as_bytes() {
+    [x, op @ (b'+' | b'&' | b'|'), y] => format!("{}{}{}", *y as char, *op as char, *x as char),
+    _ => comp.clone(),
+  };
+  COMP_TABLE.iter().find(|(_, mnemonic)| *mnemonic == comp || *mnemonic == swapped).map(|(bits, _)| (a, *bits))
+}
+
+// group and control bits of a hack-ext computation (`D<<A`, `M*D`, `C(D+M)`, ...)
+pub fn encode_ext(comp: &str) -> Option<(Group, Bit, [Bit; 6])> {
+  let comp: String = comp.chars().filter(|c| !c.is_whitespace()).collect();
+  let a = if comp.contains('M') { 1 } else { 0 };
+  if a == 1 && comp.contains('A') {
+    return None;
+  }
+  let comp = comp.replace('M', "A");
+  [Group::Shift, Group::MulDiv, Group::Flag].into_iter().find_map(|group| {
+    let (bits, _) = group.table().iter().find(|(_, mnemonic)| *mnemonic == comp)?;
+    Some((group, a, *bits))
+  })
+}
+
+fn encode_dest(dest: &str) -> Option<[Bit; 3]> {
+  if dest.is_empty() {
+    return Some([0, 0, 0]);
+  }
+  let mut bits = [0; 3];
+  for c in dest.chars() {
+    let pos = "ADM".find(c)?;
+    if bits[pos] == 1 {
+      return None;
+    }
+    bits[pos] = 1;
+  }
+  Some(bits)
+}
+
+fn encode_table(table: &[&str; 8], mnemonic: &str) -> Option<[Bit; 3]> {
+  let index = table.iter().position(|entry| *entry == mnemonic)?;
+  Some([(index >> 2) as Bit & 1, (index >> 1) as Bit & 1, index as Bit & 1])
+}
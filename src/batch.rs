@@ -0,0 +1,128 @@
+#![allow(dead_code)]
+
+use std::ops::Range;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::{fmt, thread};
+
+use crate::computer::Computer;
+use crate::gates::Bit16;
+use crate::instruction::Isa;
+
+/*
+ PARALLEL RUNS
+
+ the simulator is CPU-bound, so independent work is spread over threads:
+
+ level    | threads
+ ---------+--------------------------------------------------------------
+ batch    | one per `Job`, up to `threads` at a time
+ machine  | the cores a batch leaves idle (`threads / jobs`, at most 4)
+          | tick the RAM4Ks of each RAM16K, see `RAM16K::set_threads`
+
+ a machine only gets RAM threads when the batch is smaller than the thread
+ count: a RAM4K tick is a few microseconds of work, so the handoff only pays
+ off with a core per worker. spawning threads on every tick instead made
+ runs about 3x slower.
+
+ workers take the next job off a shared counter but every result is stored
+ at its job's index, and machines share no state, so the outcomes are the
+ same, in the same order, for any number of threads.
+*/
+
+// one worker per core, at least one
+pub fn threads() -> usize {
+  thread::available_parallelism().map_or(1, |threads| threads.get())
+}
+
+// `job` of every item on up to `threads` threads, results in item order
+pub fn map<T: Sync, R: Send>(items: &[T], threads: usize, job: impl Fn(&T) -> R + Sync) -> Vec<R> {
+  let threads = threads.clamp(1, items.len().max(1));
+  if threads == 1 {
+    return items.iter().map(job).collect();
+  }
+
+  let next = AtomicUsize::new(0);
+  let mut results: Vec<Option<R>> = thread::scope(|scope| {
+    let workers: Vec<_> = (0..threads)
+      .map(|_| {
+        scope.spawn(|| {
+          let mut done = Vec::new();
+          loop {
+            let pos = next.fetch_add(1, Ordering::Relaxed);
+            let Some(item) = items.get(pos) else { break done };
+            done.push((pos, job(item)));
+          }
+        })
+      })
+      .collect();
+    let mut results: Vec<Option<R>> = items.iter().map(|_| None).collect();
+    for worker in workers {
+      for (pos, result) in worker.join().expect("error: batch worker panicked") {
+        results[pos] = Some(result);
+      }
+    }
+    results
+  });
+  results.iter_mut().map(|result| result.take().unwrap()).collect()
+}
+
+#[derive(Clone, Debug)]
+pub struct Job {
+  pub program: Vec<Bit16>,
+  pub isa: Isa,
+  pub cycles: u64,
+  // RAM words copied into the outcome
+  pub watch: Range<u16>,
+}
+
+impl Job {
+  // a hack program, watching R0..R15
+  pub fn new(program: Vec<Bit16>, cycles: u64) -> Self {
+    Self { program, isa: Isa::Hack, cycles, watch: 0..16 }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Outcome {
+  pub cycle: u64,
+  pub pc: u16,
+  pub a: u16,
+  pub d: u16,
+  pub memory: Vec<u16>,
+}
+
+impl Outcome {
+  pub fn of(computer: &mut Computer, watch: Range<u16>) -> Self {
+    Self {
+      cycle: computer.cycle(),
+      pc: computer.pc(),
+      a: computer.a(),
+      d: computer.d(),
+      memory: watch.map(|address| computer.peek(address)).collect(),
+    }
+  }
+}
+
+impl fmt::Display for Outcome {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "cycle {} pc {} a {} d {} ram", self.cycle, self.pc, self.a, self.d)?;
+    for word in &self.memory {
+      write!(f, " {}", word)?;
+    }
+    Ok(())
+  }
+}
+
+// a fresh computer per job, its RAM16K ticking on `ram_threads` threads
+pub fn run_job(job: &Job, ram_threads: usize) -> Outcome {
+  let mut computer = Computer::new(job.program.clone());
+  computer.set_isa(job.isa);
+  computer.set_ram_threads(ram_threads);
+  computer.run(job.cycles);
+  Outcome::of(&mut computer, job.watch.clone())
+}
+
+pub fn run(jobs: &[Job], threads: usize) -> Vec<Outcome> {
+  let ram_threads = threads / jobs.len().max(1);
+  map(jobs, threads, |job| run_job(job, ram_threads))
+}
@@ -0,0 +1,290 @@
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::gates::Bit;
+use crate::netlist::{Logic, Netlist, Node};
+
+/*
+ BINARY DECISION DIAGRAMS
+
+ reduced ordered BDDs: every node tests one variable and points to the
+ diagram for var = 0 (low) and var = 1 (high). nodes are shared (unique
+ table), so two functions are equal exactly when their `Ref`s are equal.
+
+   Ref(0) = false, Ref(1) = true, variables are tested in index order
+
+ `Bdd` implements `Logic`, so circuits written against it (adders, ALU) and
+ recorded netlists can both be turned into diagrams. the variable order
+ decides the size: datapaths stay small when the bits of the operands are
+ interleaved from the LSB up (a15 b15 a14 b14 ... for MSB-first buses),
+ which is what `default_order` does.
+*/
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Ref(u32);
+
+pub const FALSE: Ref = Ref(0);
+pub const TRUE: Ref = Ref(1);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct BddNode {
+  var: u32,
+  low: Ref,
+  high: Ref,
+}
+
+#[derive(Default)]
+pub struct Bdd {
+  nodes: Vec<BddNode>,
+  unique: HashMap<BddNode, Ref>,
+  and_cache: HashMap<(Ref, Ref), Ref>,
+  not_cache: HashMap<Ref, Ref>,
+}
+
+impl Logic for Bdd {
+  type Signal = Ref;
+
+  fn nand(&mut self, a: Ref, b: Ref) -> Ref {
+    let and = self.apply_and(a, b);
+    self.apply_not(and)
+  }
+
+  fn constant(&mut self, bit: Bit) -> Ref {
+    if bit == 0 {
+      FALSE
+    } else {
+      TRUE
+    }
+  }
+
+  // shortcuts: the derived gates give the same diagrams, these just skip
+  // building the intermediate ones
+  fn not(&mut self, a: Ref) -> Ref {
+    self.apply_not(a)
+  }
+
+  fn and(&mut self, a: Ref, b: Ref) -> Ref {
+    self.apply_and(a, b)
+  }
+}
+
+impl Bdd {
+  pub fn new() -> Self {
+    let terminal = BddNode { var: u32::MAX, low: FALSE, high: FALSE };
+    // index 0 and 1 are the terminals, never looked up in `unique`
+    Self { nodes: vec![terminal, terminal], ..Self::default() }
+  }
+
+  // the function that is true when variable `index` is 1
+  pub fn var(&mut self, index: u32) -> Ref {
+    self.node(index, FALSE, TRUE)
+  }
+
+  // number of diagram nodes allocated so far (terminals included)
+  pub fn size(&self) -> usize {
+    self.nodes.len()
+  }
+
+  fn node(&mut self, var: u32, low: Ref, high: Ref) -> Ref {
+    if low == high {
+      return low;
+    }
+    let node = BddNode { var, low, high };
+    if let Some(&existing) = self.unique.get(&node) {
+      return existing;
+    }
+    let id = Ref(self.nodes.len() as u32);
+    self.nodes.push(node);
+    self.unique.insert(node, id);
+    id
+  }
+
+  fn var_of(&self, r: Ref) -> u32 {
+    self.nodes[r.0 as usize].var
+  }
+
+  // low / high children of `r` with respect to `var`
+  fn cofactors(&self, r: Ref, var: u32) -> (Ref, Ref) {
+    let node = self.nodes[r.0 as usize];
+    if r.0 > 1 && node.var == var {
+      (node.low, node.high)
+    } else {
+      (r, r)
+    }
+  }
+
+  fn apply_not(&mut self, a: Ref) -> Ref {
+    match a {
+      FALSE => return TRUE,
+      TRUE => return FALSE,
+      _ => {}
+    }
+    if let Some(&cached) = self.not_cache.get(&a) {
+      return cached;
+    }
+    let node = self.nodes[a.0 as usize];
+    let (low, high) = (self.apply_not(node.low), self.apply_not(node.high));
+    let out = self.node(node.var, low, high);
+    self.not_cache.insert(a, out);
+    out
+  }
+
+  fn apply_and(&mut self, a: Ref, b: Ref) -> Ref {
+    match (a, b) {
+      (FALSE, _) | (_, FALSE) => return FALSE,
+      (TRUE, other) | (other, TRUE) => return other,
+      _ if a == b => return a,
+      _ => {}
+    }
+    let key = if a < b { (a, b) } else { (b, a) };
+    if let Some(&cached) = self.and_cache.get(&key) {
+      return cached;
+    }
+    let var = self.var_of(a).min(self.var_of(b));
+    let (a_low, a_high) = self.cofactors(a, var);
+    let (b_low, b_high) = self.cofactors(b, var);
+    let (low, high) = (self.apply_and(a_low, b_low), self.apply_and(a_high, b_high));
+    let out = self.node(var, low, high);
+    self.and_cache.insert(key, out);
+    out
+  }
+
+  // one assignment (by variable index) that makes `r` true, None when `r` is
+  // false; variables the path does not test are set to 0
+  pub fn satisfy(&self, r: Ref, vars: usize) -> Option<Vec<Bit>> {
+    if r == FALSE {
+      return None;
+    }
+    let mut assignment = vec![0; vars];
+    let mut at = r;
+    while at != TRUE {
+      let node = self.nodes[at.0 as usize];
+      // every node below a non-false node reaches true on one side
+      if node.high != FALSE {
+        assignment[node.var as usize] = 1;
+        at = node.high;
+      } else {
+        at = node.low;
+      }
+    }
+    Some(assignment)
+  }
+
+  pub fn eval(&self, r: Ref, assignment: &[Bit]) -> Bit {
+    let mut at = r;
+    while at.0 > 1 {
+      let node = self.nodes[at.0 as usize];
+      at = if assignment[node.var as usize] == 1 { node.high } else { node.low };
+    }
+    if at == TRUE {
+      1
+    } else {
+      0
+    }
+  }
+
+  // diagrams of every netlist output, `inputs` are the functions fed to the
+  // netlist inputs (in `Netlist::inputs` order)
+  pub fn netlist(&mut self, netlist: &Netlist, inputs: &[Ref]) -> Vec<Ref> {
+    let mut refs = vec![FALSE; netlist.nodes().len()];
+    for (id, &input) in netlist.inputs().iter().zip(inputs) {
+      refs[id.0] = input;
+    }
+    for (pos, node) in netlist.nodes().iter().enumerate() {
+      match node {
+        Node::Input(_) => {}
+        Node::Const(bit) => refs[pos] = self.constant(*bit),
+        Node::Nand(a, b) => refs[pos] = self.nand(refs[a.0], refs[b.0]),
+      }
+    }
+    netlist.outputs().iter().map(|(_, id)| refs[id.0]).collect()
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Equivalence {
+  Equivalent,
+  // an input vector (in `Netlist::inputs` order) where output `output` differs
+  Different { inputs: Vec<Bit>, output: String, left: Bit, right: Bit },
+}
+
+impl fmt::Display for Equivalence {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Equivalence::Equivalent => write!(f, "equivalent"),
+      Equivalence::Different { inputs, output, left, right } => write!(
+        f,
+        "different: {} is {} / {} for inputs {}",
+        output,
+        left,
+        right,
+        inputs.iter().map(|bit| bit.to_string()).collect::<String>()
+      ),
+    }
+  }
+}
+
+// single-bit inputs first, then bus bits interleaved from the LSB up (by the
+// `[i]` suffix of the input names, MSB first like Bit16)
+pub fn default_order(netlist: &Netlist) -> Vec<usize> {
+  let index = |pos: usize| {
+    let Node::Input(name) = netlist.node(netlist.inputs()[pos]) else { unreachable!() };
+    let bit = name.rsplit_once('[').and_then(|(_, rest)| rest.strip_suffix(']')).and_then(|bit| bit.parse().ok());
+    // single bits sort first, higher indexes (lower bits) before lower ones
+    bit.map_or((0, 0), |bit: usize| (1, usize::MAX - bit))
+  };
+  let mut order: Vec<usize> = (0..netlist.inputs().len()).collect();
+  order.sort_by_key(|&pos| index(pos));
+  order
+}
+
+// decide whether two netlists compute the same outputs for every input,
+// inputs and outputs are matched by position
+pub fn equivalent(left: &Netlist, right: &Netlist) -> Result<Equivalence, String> {
+  equivalent_with_order(left, right, &default_order(left))
+}
+
+// `order[k]` is the input tested k-th by the diagrams
+pub fn equivalent_with_order(left: &Netlist, right: &Netlist, order: &[usize]) -> Result<Equivalence, String> {
+  let inputs = left.inputs().len();
+  if right.inputs().len() != inputs || left.outputs().len() != right.outputs().len() {
+    return Err(format!(
+      "netlists differ in shape: {} / {} inputs, {} / {} outputs",
+      inputs,
+      right.inputs().len(),
+      left.outputs().len(),
+      right.outputs().len()
+    ));
+  }
+  let mut sorted = order.to_vec();
+  sorted.sort_unstable();
+  if sorted != (0..inputs).collect::<Vec<_>>() {
+    return Err("the variable order must list every input once".to_string());
+  }
+
+  let mut bdd = Bdd::new();
+  let mut vars = vec![FALSE; inputs];
+  for (var, &input) in order.iter().enumerate() {
+    vars[input] = bdd.var(var as u32);
+  }
+  let left_outputs = bdd.netlist(left, &vars);
+  let right_outputs = bdd.netlist(right, &vars);
+
+  for (pos, (&l, &r)) in left_outputs.iter().zip(&right_outputs).enumerate() {
+    if l == r {
+      continue;
+    }
+    let difference = bdd.xor(l, r);
+    let by_var = bdd.satisfy(difference, inputs).expect("different diagrams have a distinguishing input");
+    let assignment: Vec<Bit> = vars.iter().map(|&var| bdd.eval(var, &by_var)).collect();
+    return Ok(Equivalence::Different {
+      output: left.outputs()[pos].0.clone(),
+      left: bdd.eval(l, &by_var),
+      right: bdd.eval(r, &by_var),
+      inputs: assignment,
+    });
+  }
+  Ok(Equivalence::Equivalent)
+}
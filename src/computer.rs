@@ -0,0 +1,340 @@
+#![allow(dead_code)]
+
+use crate::cpu::CPU;
+use crate::fault::StateFault;
+use crate::gates::{self, Bit, Bit16};
+use crate::history::{Forced, History};
+use crate::instruction::Isa;
+use crate::mem::{memory::Memory, rom32k::ROM32K};
+use crate::snapshot;
+
+/*
+ COMPUTER
+
+ +--------+  instruction  +-------+  out_m, write_m, address_m  +--------+
+ | ROM32K | ------------> |  CPU  | --------------------------> | Memory |
+ +--------+ <------------ +-------+ <-------------------------- +--------+
+               pc                             in_m
+*/
+
+// DFFs `set_faults` can reach: A, D, PC and the RAM16K
+pub const STATE_BITS: usize = 3 * 16 + 16384 * 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Access {
+  pub address: u16,
+  pub value: u16,
+}
+
+// everything observable about a single clock cycle
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Step {
+  pub cycle: u64,
+  pub pc: u16,
+  pub instruction: Bit16,
+  // register values after the clock edge
+  pub a: u16,
+  pub d: u16,
+  pub next_pc: u16,
+  // ALU and memory bus, before the clock edge
+  pub x: u16,
+  pub y: u16,
+  pub out: u16,
+  pub address_m: u16,
+  pub zr: Bit,
+  pub ng: Bit,
+  pub read: Option<Access>,
+  pub write: Option<Access>,
+  // instruction set the cycle ran with
+  pub isa: Isa,
+}
+
+impl Step {
+  pub fn controls(&self) -> [Bit; 6] {
+    self.instruction[4..10].try_into().unwrap()
+  }
+}
+
+#[derive(Clone)]
+pub struct Computer {
+  cpu: CPU,
+  rom: ROM32K,
+  memory: Memory,
+  cycle: u64,
+  // optional record of past cycles, for rewinding
+  history: Option<History>,
+  // DFF faults forced around every cycle, see `set_faults`
+  faults: Vec<StateFault>,
+}
+
+impl Computer {
+  pub fn new(program: Vec<Bit16>) -> Self {
+    Self::from_parts(CPU::new(), ROM32K::new(program), Memory::default(), 0)
+  }
+
+  pub fn from_parts(cpu: CPU, rom: ROM32K, memory: Memory, cycle: u64) -> Self {
+    Self { cpu, rom, memory, cycle, history: None, faults: Vec::new() }
+  }
+
+  // restart the program, memory is kept
+  pub fn reset(&mut self) {
+    self.cpu.tick([0; 16], [0; 16], 1);
+    self.forget_history();
+  }
+
+  pub fn step(&mut self) -> Step {
+    if self.history.as_ref().is_some_and(History::needs_checkpoint) {
+      let checkpoint = snapshot::save(self);
+      self.history.as_mut().unwrap().checkpoint(self.cycle, checkpoint);
+    }
+
+    let before = self.force(Some(self.cycle));
+    let pc = self.cpu.pc();
+    let instruction = self.rom.read(pc[1..16].try_into().unwrap());
+    let address_m = self.cpu.address_m();
+
+    // M only reaches the ALU for C-instructions with the a-bit set, other cycles skip the read
+    let uses_m = gates::and(instruction[0], instruction[3]);
+    let in_m = if uses_m == 1 { self.memory.tick(address_m, 0, [0; 16]) } else { [0; 16] };
+
+    let out = self.cpu.tick(in_m, instruction, 0);
+    if out.write_m == 1 {
+      self.memory.tick(out.address_m, 1, out.out_m);
+    }
+    let after = self.force(None);
+
+    let read = (uses_m == 1).then(|| Access { address: address_to_u16(address_m), value: gates::to_u16(in_m) });
+    let write =
+      (out.write_m == 1).then(|| Access { address: address_to_u16(out.address_m), value: gates::to_u16(out.out_m) });
+
+    let step = Step {
+      cycle: self.cycle,
+      pc: gates::to_u16(pc),
+      instruction,
+      a: gates::to_u16(self.cpu.a()),
+      d: gates::to_u16(self.cpu.d()),
+      next_pc: address_to_u16(out.pc),
+      x: gates::to_u16(out.x),
+      y: gates::to_u16(out.y),
+      out: gates::to_u16(out.out_m),
+      address_m: address_to_u16(address_m),
+      zr: out.zr,
+      ng: out.ng,
+      read,
+      write,
+      isa: self.cpu.isa(),
+    };
+    if let Some(history) = &mut self.history {
+      history.record(&step);
+      for (after_write, changed) in [(false, before), (true, after)] {
+        for access in changed {
+          history.record_forced(Forced { cycle: self.cycle, after_write, access });
+        }
+      }
+    }
+    self.cycle += 1;
+    step
+  }
+
+  pub fn run(&mut self, cycles: u64) {
+    for _ in 0..cycles {
+      self.step();
+    }
+  }
+
+  // read/write memory outside of the cpu (loading test data, inspecting results)
+  pub fn peek(&mut self, address: u16) -> u16 {
+    gates::to_u16(self.memory.tick(u16_to_address(address), 0, [0; 16]))
+  }
+
+  pub fn poke(&mut self, address: u16, value: u16) {
+    self.memory.tick(u16_to_address(address), 1, gates::from_u16(value));
+    self.forget_history();
+  }
+
+  pub fn set_key(&mut self, code: u16) {
+    self.memory.set_key(gates::from_u16(code));
+    self.forget_history();
+  }
+
+  // threads ticking the RAM4Ks of the RAM16K, see `RAM16K::set_threads`. it
+  // only pays off with a core per thread, `batch::run` decides
+  pub fn set_ram_threads(&mut self, threads: usize) {
+    self.memory.set_ram_threads(threads);
+  }
+
+  // DFF faults for every following cycle, bits are numbered like snapshots:
+  // A, D and PC at 0..48 (MSB first), then RAM word n at 48 + 16 * n. stuck
+  // bits hold before and after every cycle, a flip inverts its bit once just
+  // before its cycle runs
+  pub fn set_faults(&mut self, faults: Vec<StateFault>) -> Result<(), String> {
+    if let Some(fault) = faults.iter().find(|fault| fault.bit() >= STATE_BITS) {
+      return Err(format!("{}: the computer has {} DFFs", fault, STATE_BITS));
+    }
+    self.faults = faults;
+    self.force(None);
+    self.forget_history();
+    Ok(())
+  }
+
+  pub fn faults(&self) -> &[StateFault] {
+    &self.faults
+  }
+
+  // stuck bits always, flips only at `cycle`. returns the RAM words it changed,
+  // a history has to replay them since they are not cpu writes
+  fn force(&mut self, cycle: Option<u64>) -> Vec<Access> {
+    let mut changed = Vec::new();
+    for pos in 0..self.faults.len() {
+      let (bit, value) = match self.faults[pos] {
+        StateFault::StuckAt { bit, value } => (bit, Some(value)),
+        StateFault::Flip { bit, cycle: at } if Some(at as u64) == cycle => (bit, None),
+        StateFault::Flip { .. } => continue,
+      };
+      let (word, pos) = (bit / 16, bit % 16);
+      let mut bits = match word {
+        0 => self.cpu.a(),
+        1 => self.cpu.d(),
+        2 => self.cpu.pc(),
+        _ => self.memory.tick(u16_to_address(word as u16 - 3), 0, [0; 16]),
+      };
+      let forced = value.unwrap_or_else(|| gates::not(bits[pos]));
+      if bits[pos] == forced {
+        continue;
+      }
+      bits[pos] = forced;
+      match word {
+        0 => self.cpu.set_a(bits),
+        1 => self.cpu.set_d(bits),
+        2 => self.cpu.set_pc(bits),
+        _ => {
+          let address = word as u16 - 3;
+          self.memory.tick(u16_to_address(address), 1, bits);
+          changed.push(Access { address, value: gates::to_u16(bits) });
+        }
+      }
+    }
+    changed
+  }
+
+  pub fn isa(&self) -> Isa {
+    self.cpu.isa()
+  }
+
+  pub fn set_isa(&mut self, isa: Isa) {
+    self.cpu.set_isa(isa);
+    self.forget_history();
+  }
+
+  pub fn cpu(&self) -> &CPU {
+    &self.cpu
+  }
+
+  pub fn rom(&self) -> &ROM32K {
+    &self.rom
+  }
+
+  pub fn memory(&self) -> &Memory {
+    &self.memory
+  }
+
+  pub fn pc(&self) -> u16 {
+    gates::to_u16(self.cpu.pc())
+  }
+
+  pub fn a(&self) -> u16 {
+    gates::to_u16(self.cpu.a())
+  }
+
+  pub fn d(&self) -> u16 {
+    gates::to_u16(self.cpu.d())
+  }
+
+  pub fn cycle(&self) -> u64 {
+    self.cycle
+  }
+
+  pub fn set_a(&mut self, value: u16) {
+    self.cpu.set_a(gates::from_u16(value));
+    self.forget_history();
+  }
+
+  pub fn set_d(&mut self, value: u16) {
+    self.cpu.set_d(gates::from_u16(value));
+    self.forget_history();
+  }
+
+  pub fn set_pc(&mut self, value: u16) {
+    self.cpu.set_pc(gates::from_u16(value));
+    self.forget_history();
+  }
+
+  pub fn instruction_at(&self, address: u16) -> Bit16 {
+    self.rom.read(u16_to_address(address))
+  }
+
+  // history
+
+  pub fn record_history(&mut self, history: History) {
+    self.history = Some(history);
+  }
+
+  pub fn history(&self) -> Option<&History> {
+    self.history.as_ref()
+  }
+
+  // changes made outside of a cycle can't be replayed, recording starts over
+  fn forget_history(&mut self) {
+    if let Some(history) = &mut self.history {
+      history.clear();
+    }
+  }
+
+  // go back to the state before `cycle` ran, dropping the history after it
+  pub fn rewind(&mut self, cycle: u64) -> Result<(), String> {
+    let history = self.history.as_mut().ok_or("history recording is off")?;
+    let (checkpoint, deltas, forced) =
+      history.truncate(cycle).ok_or_else(|| format!("cycle {} is not in the recorded history", cycle))?;
+
+    let restored = snapshot::load(&checkpoint)?;
+    self.cpu = restored.cpu;
+    let threads = self.memory.ram_threads();
+    self.memory = restored.memory;
+    self.memory.set_ram_threads(threads);
+    // words forced by faults go before or after their cycle's write, as they ran
+    let mut forced = forced.iter().peekable();
+    for delta in &deltas {
+      while let Some(next) = forced.next_if(|forced| forced.cycle == delta.cycle && !forced.after_write) {
+        self.replay(next.access);
+      }
+      if let Some(write) = delta.write {
+        self.replay(write);
+      }
+      while let Some(next) = forced.next_if(|forced| forced.cycle == delta.cycle) {
+        self.replay(next.access);
+      }
+    }
+    if let Some(last) = deltas.last() {
+      self.cpu.set_a(gates::from_u16(last.a));
+      self.cpu.set_d(gates::from_u16(last.d));
+      self.cpu.set_pc(gates::from_u16(last.next_pc));
+    }
+    self.force(None);
+    self.cycle = cycle;
+    Ok(())
+  }
+
+  fn replay(&mut self, write: Access) {
+    self.memory.tick(u16_to_address(write.address), 1, gates::from_u16(write.value));
+  }
+}
+
+fn address_to_u16(address: [Bit; 15]) -> u16 {
+  let mut word = [0; 16];
+  word[1..16].copy_from_slice(&address);
+  gates::to_u16(word)
+}
+
+fn u16_to_address(address: u16) -> [Bit; 15] {
+  gates::from_u16(address)[1..16].try_into().unwrap()
+}
@@ -0,0 +1,241 @@
+#![allow(dead_code)]
+
+use std::slice;
+
+use crate::adders::{self, Adder};
+use crate::alu::{self, ALU};
+use crate::gates::{self, Bit, Bit16};
+use crate::instruction::{self, Group, Isa};
+use crate::mem::{pc::PC, register::Register};
+use crate::netlist::{Logic, Netlist, NodeId};
+
+/*
+ CPU
+
+ inputs:
+   in_m:        value of M (RAM[A]) read before the clock edge
+   instruction: current instruction, from ROM[pc]
+   reset:       restart the program (pc = 0)
+
+ outputs:
+   out_m:     value to be written into M
+   write_m:   write out_m into RAM[address_m]
+   address_m: address of M (the value of A before the clock edge)
+   pc:        address of the next instruction
+
+ instruction decoding (see `instruction.rs`):
+   is_c  = instruction[0]
+   a     = instruction[3]           (y = M instead of A)
+   c1-c6 = instruction[4..10]       (ALU controls)
+   d1-d3 = instruction[10..13]      (A D M destinations)
+   j1-j3 = instruction[13..16]      (< 0, == 0, > 0)
+
+ instruction sets (`set_isa`, see `instruction.rs`): with hack-ext, bits 14-13
+ of a C-instruction pick the ALU, the barrel shifter, the multiplier / divider
+ or the carry / overflow flags. x / y are the operands of the chosen unit.
+*/
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CpuOutput {
+  pub out_m: Bit16,
+  pub write_m: Bit,
+  pub address_m: [Bit; 15],
+  pub pc: [Bit; 15],
+  // ALU inputs and flags of the current cycle
+  pub x: Bit16,
+  pub y: Bit16,
+  pub zr: Bit,
+  pub ng: Bit,
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct CPU {
+  a: Register,
+  d: Register,
+  pc: PC,
+  // instruction set, hack-ext decodes bits 14-13
+  isa: Isa,
+}
+
+impl CPU {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn tick(&mut self, in_m: Bit16, instruction: Bit16, reset: Bit) -> CpuOutput {
+    let is_c = instruction[0];
+    let is_a = gates::not(is_c);
+
+    let a_out = self.a.output();
+    let d_out = self.d.output();
+
+    // ALU: x = D, y = A or M
+    let a_or_m = gates::mux16(a_out, in_m, gates::and(is_c, instruction[3]));
+    let mut controls: [Bit; 6] = instruction[4..10].try_into().unwrap();
+    let group = if is_c == 1 { Group::decode(instruction, self.isa) } else { Group::Alu };
+
+    // hack-ext groups pick their operands with the control bits, the ALU
+    // controls are then all 0 and `ext` replaces the f stage
+    let ext = instruction::ext_op(group, controls);
+    let one = gates::from_u16(1);
+    let (x, y) = match group {
+      Group::Alu => (d_out, a_or_m),
+      // x = value, y = amount (the other register, or 1)
+      Group::Shift => {
+        let other = gates::mux16(d_out, a_or_m, instruction[5]);
+        (gates::mux16(a_or_m, d_out, instruction[5]), gates::mux16(one, other, instruction[8]))
+      }
+      Group::MulDiv => (gates::mux16(d_out, a_or_m, instruction[6]), gates::mux16(a_or_m, d_out, instruction[6])),
+      Group::Flag => {
+        let other = gates::mux16(a_or_m, d_out, instruction[6]);
+        (gates::mux16(d_out, a_or_m, instruction[6]), gates::mux16(other, one, instruction[7]))
+      }
+    };
+    if ext.is_some() {
+      controls = [0; 6];
+    }
+
+    let mut alu = ALU::new(x, y);
+    alu.set_controls(controls);
+    alu.set_ext(ext);
+    let (alu_out, zr, ng) = alu.execute();
+
+    // destinations
+    let load_a = gates::or(is_a, gates::and(is_c, instruction[10]));
+    let load_d = gates::and(is_c, instruction[11]);
+    let write_m = gates::and(is_c, instruction[12]);
+
+    // jump condition
+    let positive = gates::and(gates::not(zr), gates::not(ng));
+    let jlt = gates::and(instruction[13], ng);
+    let jeq = gates::and(instruction[14], zr);
+    let jgt = gates::and(instruction[15], positive);
+    let jump = gates::and(is_c, gates::or(jlt, gates::or(jeq, jgt)));
+
+    // clock edge
+    self.a.tick(gates::mux16(instruction, alu_out, is_c), load_a);
+    self.d.tick(alu_out, load_d);
+    let pc = self.pc.tick(a_out, jump, 1, reset);
+
+    CpuOutput {
+      out_m: alu_out,
+      write_m,
+      address_m: a_out[1..16].try_into().unwrap(),
+      pc: pc[1..16].try_into().unwrap(),
+      x,
+      y,
+      zr,
+      ng,
+    }
+  }
+
+  pub fn set_isa(&mut self, isa: Isa) {
+    self.isa = isa;
+  }
+
+  pub fn isa(&self) -> Isa {
+    self.isa
+  }
+
+  pub fn address_m(&self) -> [Bit; 15] {
+    self.a.output()[1..16].try_into().unwrap()
+  }
+
+  pub fn a(&self) -> Bit16 {
+    self.a.output()
+  }
+
+  pub fn d(&self) -> Bit16 {
+    self.d.output()
+  }
+
+  pub fn pc(&self) -> Bit16 {
+    self.pc.output()
+  }
+
+  // force register values outside of a normal cycle (debugger `set`)
+  pub fn set_a(&mut self, value: Bit16) {
+    self.a.tick(value, 1);
+  }
+
+  pub fn set_d(&mut self, value: Bit16) {
+    self.d.tick(value, 1);
+  }
+
+  pub fn set_pc(&mut self, value: Bit16) {
+    self.pc.tick(value, 1, 0, 0);
+  }
+
+  // snapshots: A, D then PC
+  pub fn save(&self, bits: &mut Vec<Bit>) {
+    self.a.save(bits);
+    self.d.save(bits);
+    self.pc.save(bits);
+  }
+
+  pub fn restore(&mut self, bits: &mut slice::Iter<Bit>) {
+    self.a.restore(bits);
+    self.d.restore(bits);
+    self.pc.restore(bits);
+  }
+}
+
+// the hack-isa logic between the registers as a NAND netlist (`tick` without
+// the clock edge): inputs in_m, instruction, reset and the register outputs
+// a, d, pc; outputs out_m, write_m, and what the registers load next
+// (a_in / load_a, load_d with out_m, pc_next)
+pub fn netlist() -> Netlist {
+  let mut netlist = Netlist::new();
+  let in_m = netlist.input_bus::<16>("in_m");
+  let instruction = netlist.input_bus::<16>("instruction");
+  let reset = netlist.input("reset");
+  let a = netlist.input_bus::<16>("a");
+  let d = netlist.input_bus::<16>("d");
+  let pc = netlist.input_bus::<16>("pc");
+
+  let is_c = instruction[0];
+  let is_a = netlist.not(is_c);
+  let uses_m = netlist.and(is_c, instruction[3]);
+  let a_or_m = mux16(&mut netlist, a, in_m, uses_m);
+  let controls: [NodeId; 6] = instruction[4..10].try_into().unwrap();
+  let (out, zr, ng) = alu::circuit(&mut netlist, Adder::Ripple, d, a_or_m, controls);
+
+  let dest_a = netlist.and(is_c, instruction[10]);
+  let load_a = netlist.or(is_a, dest_a);
+  let load_d = netlist.and(is_c, instruction[11]);
+  let write_m = netlist.and(is_c, instruction[12]);
+
+  let (not_zr, not_ng) = (netlist.not(zr), netlist.not(ng));
+  let positive = netlist.and(not_zr, not_ng);
+  let jlt = netlist.and(instruction[13], ng);
+  let jeq = netlist.and(instruction[14], zr);
+  let jgt = netlist.and(instruction[15], positive);
+  let condition = netlist.or_n(&[jlt, jeq, jgt]);
+  let jump = netlist.and(is_c, condition);
+
+  let a_in = mux16(&mut netlist, instruction, out, is_c);
+  // PC: inc, load A on a jump, 0 on reset
+  let (zero, one) = (netlist.constant(0), netlist.constant(1));
+  let mut step = [zero; 16];
+  step[15] = one;
+  let (next, _) = adders::add(&mut netlist, Adder::Ripple, pc, step, zero);
+  let next = mux16(&mut netlist, next, a, jump);
+  let not_reset = netlist.not(reset);
+  let pc_next = next.map(|bit| netlist.and(bit, not_reset));
+
+  netlist.output_bus("out_m", &out);
+  netlist.output("write_m", write_m);
+  netlist.output_bus("a_in", &a_in);
+  netlist.output("load_a", load_a);
+  netlist.output("load_d", load_d);
+  netlist.output_bus("pc_next", &pc_next);
+  netlist
+}
+
+fn mux16<L: Logic>(logic: &mut L, a: [L::Signal; 16], b: [L::Signal; 16], sel: L::Signal) -> [L::Signal; 16] {
+  let mut out = a;
+  for pos in 0..16 {
+    out[pos] = logic.mux(a[pos], b[pos], sel);
+  }
+  out
+}
@@ -0,0 +1,554 @@
+#![allow(dead_code)]
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+use crate::asm::{self, SymbolTable};
+use crate::computer::{Computer, Step};
+use crate::gates::Bit16;
+use crate::history::{self, Delta, History};
+use crate::instruction::{Instruction, Isa};
+use crate::mem::rom32k::{self, ROM32K};
+use crate::snapshot;
+
+/*
+ INTERACTIVE DEBUGGER
+
+ drives a `Computer` one cycle at a time (see `HELP` for the commands).
+ breakpoints are ROM addresses or labels, watchpoints are RAM addresses or
+ variables taken from the assembler's symbol table.
+
+ execution is recorded (see `history.rs`), so `reverse-step` and
+ `reverse-continue` can walk back to earlier cycles. `set` clears the record.
+*/
+
+const HELP: &str = "\
+step [n]                 execute n instructions (default 1)
+next [n]                 like step, but runs jumps until pc + 1 is reached
+continue                 run until a breakpoint, watchpoint or halt
+reverse-step [n]         go back n instructions (default 1)
+reverse-continue         go back to the previous breakpoint or watchpoint write
+break <addr|label>       breakpoint on a ROM address
+delete <addr|label>      remove a breakpoint
+watch <addr|symbol>      stop when RAM[addr] changes
+unwatch <addr|symbol>    remove a watchpoint
+print[/x|/b|/d] <target> show A, D, PC, RAM[addr] or a variable
+set <target>=<value>     poke A, D, PC or RAM[addr]
+info <break|watch|regs>  list breakpoints, watchpoints or registers
+list [n]                 disassemble n instructions around pc
+quit                     leave the debugger";
+
+// cycles `continue` and `next` may run before giving control back
+pub const DEFAULT_LIMIT: u64 = 1_000_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stop {
+  Done,
+  Breakpoint(u16),
+  Watchpoint { address: u16, old: u16, new: u16 },
+  // the program reached the `(END) @END 0;JMP` idiom
+  Halted(u16),
+  Limit,
+  // reverse execution ran out of recorded history
+  HistoryStart,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Radix {
+  Decimal,
+  Hex,
+  Binary,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Target {
+  A,
+  D,
+  PC,
+  Ram(u16),
+}
+
+pub struct Debugger {
+  computer: Computer,
+  symbols: SymbolTable,
+  breakpoints: BTreeSet<u16>,
+  // watched address -> last value seen
+  watchpoints: BTreeMap<u16, u16>,
+  limit: u64,
+}
+
+impl Debugger {
+  pub fn new(mut computer: Computer, symbols: SymbolTable) -> Self {
+    computer.record_history(History::new(history::DEFAULT_INTERVAL, history::DEFAULT_SEGMENTS));
+    Self { computer, symbols, breakpoints: BTreeSet::new(), watchpoints: BTreeMap::new(), limit: DEFAULT_LIMIT }
+  }
+
+  pub fn set_limit(&mut self, limit: u64) {
+    self.limit = limit;
+  }
+
+  pub fn computer(&self) -> &Computer {
+    &self.computer
+  }
+
+  pub fn computer_mut(&mut self) -> &mut Computer {
+    &mut self.computer
+  }
+
+  pub fn symbols(&self) -> &SymbolTable {
+    &self.symbols
+  }
+
+  // read-eval-print loop, until `quit` or end of input
+  pub fn run<R: BufRead, W: Write>(&mut self, input: R, mut output: W) -> io::Result<()> {
+    writeln!(output, "{}", self.location())?;
+    write!(output, "(hack) ")?;
+    output.flush()?;
+    for line in input.lines() {
+      let line = line?;
+      let command = line.trim();
+      if command == "quit" || command == "q" {
+        break;
+      }
+      if !command.is_empty() {
+        match self.command(command) {
+          Ok(text) if text.is_empty() => {}
+          Ok(text) => writeln!(output, "{}", text)?,
+          Err(error) => writeln!(output, "error: {}", error)?,
+        }
+      }
+      write!(output, "(hack) ")?;
+      output.flush()?;
+    }
+    Ok(())
+  }
+
+  pub fn command(&mut self, line: &str) -> Result<String, String> {
+    let (name, args) = match line.trim().split_once(char::is_whitespace) {
+      Some((name, args)) => (name, args.trim()),
+      None => (line.trim(), ""),
+    };
+    let (name, radix) = match name.split_once('/') {
+      Some((name, format)) => (name, parse_radix(format)?),
+      None => (name, Radix::Decimal),
+    };
+
+    match name {
+      "step" | "s" => {
+        let stop = self.step(parse_count(args)?);
+        Ok(self.report(stop))
+      }
+      "next" | "n" => {
+        let stop = self.next(parse_count(args)?);
+        Ok(self.report(stop))
+      }
+      "continue" | "c" => {
+        let stop = self.resume();
+        Ok(self.report(stop))
+      }
+      "reverse-step" | "rs" => {
+        let stop = self.reverse_step(parse_count(args)?);
+        Ok(self.report(stop))
+      }
+      "reverse-continue" | "rc" => {
+        let stop = self.reverse_continue();
+        Ok(self.report(stop))
+      }
+      "break" | "b" => {
+        let address = self.rom_address(args)?;
+        self.breakpoints.insert(address);
+        Ok(format!("breakpoint at {}", self.describe_rom(address)))
+      }
+      "delete" | "d" => {
+        let address = self.rom_address(args)?;
+        match self.breakpoints.remove(&address) {
+          true => Ok(format!("deleted breakpoint at {}", self.describe_rom(address))),
+          false => Err(format!("no breakpoint at {}", address)),
+        }
+      }
+      "watch" | "w" => {
+        let address = self.ram_address(args)?;
+        let value = self.computer.peek(address);
+        self.watchpoints.insert(address, value);
+        Ok(format!("watchpoint on RAM[{}] = {}", address, value as i16))
+      }
+      "unwatch" => {
+        let address = self.ram_address(args)?;
+        match self.watchpoints.remove(&address) {
+          Some(_) => Ok(format!("deleted watchpoint on RAM[{}]", address)),
+          None => Err(format!("no watchpoint on RAM[{}]", address)),
+        }
+      }
+      "print" | "p" => {
+        let target = self.target(args)?;
+        let value = self.read(target);
+        Ok(format!("{} = {}", args, format_value(value, radix)))
+      }
+      "set" => {
+        let (target, value) = args.split_once('=').ok_or("usage: set <target>=<value>")?;
+        let target = self.target(target.trim())?;
+        let value = parse_value(value.trim())?;
+        self.write(target, value)?;
+        Ok(String::new())
+      }
+      "info" | "i" => self.info(args, radix),
+      "list" | "l" => Ok(self.list(if args.is_empty() { 5 } else { parse_count(args)? })),
+      "help" | "h" => Ok(HELP.to_string()),
+      _ => Err(format!("unknown command '{}', try 'help'", name)),
+    }
+  }
+
+  // execution
+
+  // one cycle, reporting a watchpoint hit
+  fn advance(&mut self) -> (Step, Option<Stop>) {
+    let step = self.computer.step();
+    let hit = step.write.and_then(|write| {
+      let old = self.watchpoints.get_mut(&write.address)?;
+      if *old == write.value {
+        return None;
+      }
+      let stop = Stop::Watchpoint { address: write.address, old: *old, new: write.value };
+      *old = write.value;
+      Some(stop)
+    });
+    (step, hit)
+  }
+
+  pub fn step(&mut self, count: u64) -> Stop {
+    for _ in 0..count {
+      let (_, hit) = self.advance();
+      if let Some(stop) = hit {
+        return stop;
+      }
+      if self.breakpoints.contains(&self.computer.pc()) {
+        return Stop::Breakpoint(self.computer.pc());
+      }
+    }
+    Stop::Done
+  }
+
+  pub fn next(&mut self, count: u64) -> Stop {
+    for _ in 0..count {
+      // ROM32K decodes the low 15 bits of PC, the last word falls through to 0
+      let target = self.computer.pc().wrapping_add(1) & 0x7fff;
+      let stop = self.run_until(|pc| pc & 0x7fff == target);
+      if stop != Stop::Done {
+        return stop;
+      }
+    }
+    Stop::Done
+  }
+
+  pub fn resume(&mut self) -> Stop {
+    self.run_until(|_| false)
+  }
+
+  fn run_until(&mut self, done: impl Fn(u16) -> bool) -> Stop {
+    for _ in 0..self.limit {
+      let (step, hit) = self.advance();
+      if let Some(stop) = hit {
+        return stop;
+      }
+      let pc = self.computer.pc();
+      if done(pc) {
+        return Stop::Done;
+      }
+      if self.breakpoints.contains(&pc) {
+        return Stop::Breakpoint(pc);
+      }
+      if self.is_halt(&step) {
+        return Stop::Halted(pc);
+      }
+    }
+    Stop::Limit
+  }
+
+  pub fn reverse_step(&mut self, count: u64) -> Stop {
+    self.reverse(Some(count))
+  }
+
+  pub fn reverse_continue(&mut self) -> Stop {
+    self.reverse(None)
+  }
+
+  // walk the recorded deltas backwards (newest first) looking for where to stop,
+  // then rewind the machine once to the state before that cycle
+  fn reverse(&mut self, count: Option<u64>) -> Stop {
+    if count == Some(0) {
+      return Stop::Done;
+    }
+    let Some(history) = self.computer.history() else {
+      return Stop::HistoryStart;
+    };
+    let changes = match self.watched_changes(history) {
+      Ok(changes) => changes,
+      Err(_) => return Stop::HistoryStart,
+    };
+    let mut target = None;
+    for (steps, (delta, changed)) in history.deltas().rev().zip(changes.into_iter().rev()).enumerate() {
+      let watched = delta.write.filter(|_| changed);
+      let stop = if let Some(write) = watched {
+        Some(Stop::Watchpoint { address: write.address, old: 0, new: write.value })
+      } else if self.breakpoints.contains(&delta.pc) {
+        Some(Stop::Breakpoint(delta.pc))
+      } else if count == Some(steps as u64 + 1) {
+        Some(Stop::Done)
+      } else {
+        None
+      };
+      if let Some(stop) = stop {
+        target = Some((delta.cycle, stop));
+        break;
+      }
+    }
+    let (cycle, stop) = match target {
+      Some(target) => target,
+      None => match history.oldest() {
+        Some(oldest) => (oldest, Stop::HistoryStart),
+        None => return Stop::HistoryStart,
+      },
+    };
+
+    self.computer.rewind(cycle).expect("error: recorded cycle must be rewindable");
+    for (&address, value) in self.watchpoints.iter_mut() {
+      *value = self.computer.peek(address);
+    }
+    match stop {
+      // the write is about to happen again: report the value it overwrites
+      Stop::Watchpoint { address, new, .. } => Stop::Watchpoint { address, old: self.watchpoints[&address], new },
+      stop => stop,
+    }
+  }
+
+  // for every recorded delta, whether it changed a watched address, like the
+  // watchpoints of `advance`: the values at the oldest checkpoint are played
+  // forward through the writes
+  fn watched_changes(&self, history: &History) -> Result<Vec<bool>, String> {
+    let watched = |delta: &Delta| delta.write.is_some_and(|write| self.watchpoints.contains_key(&write.address));
+    if !history.deltas().any(watched) {
+      return Ok(history.deltas().map(|_| false).collect());
+    }
+    let checkpoint = history.oldest_checkpoint().ok_or("no recorded history")?;
+    let mut oldest = snapshot::load(checkpoint)?;
+    let mut values: BTreeMap<u16, u16> =
+      self.watchpoints.keys().map(|&address| (address, oldest.peek(address))).collect();
+    let changes = history.deltas().map(|delta| match delta.write {
+      Some(write) => values.get_mut(&write.address).is_some_and(|value| {
+        let changed = *value != write.value;
+        *value = write.value;
+        changed
+      }),
+      None => false,
+    });
+    Ok(changes.collect())
+  }
+
+  // `@n` at address n followed by an unconditional jump back to it
+  fn is_halt(&self, step: &Step) -> bool {
+    let jumped_back = step.next_pc + 1 == step.pc;
+    let word = self.computer.instruction_at(step.next_pc);
+    jumped_back
+      && Instruction::decode(word, self.computer.isa()) == Instruction::A(step.next_pc)
+      && step.instruction[13..16] == [1, 1, 1]
+  }
+
+  // inspection
+
+  fn report(&self, stop: Stop) -> String {
+    let reason = match stop {
+      Stop::Done => String::new(),
+      Stop::Breakpoint(address) => format!("breakpoint at {}\n", self.describe_rom(address)),
+      Stop::Watchpoint { address, old, new } => {
+        format!("watchpoint RAM[{}]: {} -> {}\n", address, old as i16, new as i16)
+      }
+      Stop::Halted(address) => format!("program halted at {}\n", self.describe_rom(address)),
+      Stop::Limit => format!("stopped after {} cycles\n", self.limit),
+      Stop::HistoryStart => "reached the start of the recorded history\n".to_string(),
+    };
+    format!("{}{}", reason, self.location())
+  }
+
+  fn location(&self) -> String {
+    let pc = self.computer.pc();
+    format!(
+      "=> {}  {}",
+      self.describe_rom(pc),
+      Instruction::decode(self.computer.instruction_at(pc), self.computer.isa())
+    )
+  }
+
+  fn describe_rom(&self, address: u16) -> String {
+    match self.symbols.label_at(address) {
+      Some(label) => format!("{} ({})", address, label),
+      None => address.to_string(),
+    }
+  }
+
+  fn list(&self, count: u64) -> String {
+    let pc = self.computer.pc() as u64;
+    let start = pc.saturating_sub(count / 2);
+    let end = (start + count).min(self.computer.rom().words().len() as u64);
+    let mut lines = Vec::new();
+    for address in start..end {
+      let address = address as u16;
+      if let Some(label) = self.symbols.label_at(address) {
+        lines.push(format!("      ({})", label));
+      }
+      let marker = if address as u64 == pc {
+        "=>"
+      } else if self.breakpoints.contains(&address) {
+        " *"
+      } else {
+        "  "
+      };
+      lines.push(format!(
+        "{} {:>5}  {}",
+        marker,
+        address,
+        Instruction::decode(self.computer.instruction_at(address), self.computer.isa())
+      ));
+    }
+    lines.join("\n")
+  }
+
+  fn info(&mut self, what: &str, radix: Radix) -> Result<String, String> {
+    match what {
+      "break" | "breakpoints" => {
+        let list: Vec<String> = self.breakpoints.iter().map(|&address| self.describe_rom(address)).collect();
+        Ok(if list.is_empty() { "no breakpoints".to_string() } else { list.join("\n") })
+      }
+      "watch" | "watchpoints" => {
+        let list: Vec<String> =
+          self.watchpoints.iter().map(|(address, value)| format!("RAM[{}] = {}", address, *value as i16)).collect();
+        Ok(if list.is_empty() { "no watchpoints".to_string() } else { list.join("\n") })
+      }
+      "regs" | "registers" => Ok(format!(
+        "A  = {}\nD  = {}\nPC = {}\ncycle {}",
+        format_value(self.computer.a(), radix),
+        format_value(self.computer.d(), radix),
+        format_value(self.computer.pc(), radix),
+        self.computer.cycle()
+      )),
+      _ => Err("usage: info <break|watch|regs>".to_string()),
+    }
+  }
+
+  fn read(&mut self, target: Target) -> u16 {
+    match target {
+      Target::A => self.computer.a(),
+      Target::D => self.computer.d(),
+      Target::PC => self.computer.pc(),
+      Target::Ram(address) => self.computer.peek(address),
+    }
+  }
+
+  fn write(&mut self, target: Target, value: u16) -> Result<(), String> {
+    match target {
+      Target::A => self.computer.set_a(value),
+      Target::D => self.computer.set_d(value),
+      Target::PC if value as usize >= ROM32K::SIZE => {
+        return Err(format!("PC {} is outside ROM32K (0 .. {})", value, ROM32K::SIZE - 1))
+      }
+      Target::PC => self.computer.set_pc(value),
+      Target::Ram(address) => {
+        self.computer.poke(address, value);
+        if let Some(old) = self.watchpoints.get_mut(&address) {
+          *old = value;
+        }
+      }
+    }
+    Ok(())
+  }
+
+  // argument parsing
+
+  fn target(&self, text: &str) -> Result<Target, String> {
+    match text {
+      "A" => Ok(Target::A),
+      "D" => Ok(Target::D),
+      "PC" => Ok(Target::PC),
+      _ => self.ram_address(text).map(Target::Ram),
+    }
+  }
+
+  fn ram_address(&self, text: &str) -> Result<u16, String> {
+    let text = text.strip_prefix("RAM[").and_then(|text| text.strip_suffix(']')).unwrap_or(text).trim();
+    if let Some(address) = self.symbols.variable(text) {
+      return Ok(address);
+    }
+    match parse_value(text) {
+      Ok(address) if address < 0x8000 => Ok(address),
+      _ => Err(format!("'{}' is not a RAM address or variable", text)),
+    }
+  }
+
+  fn rom_address(&self, text: &str) -> Result<u16, String> {
+    if let Some(address) = self.symbols.label(text) {
+      return Ok(address);
+    }
+    match parse_value(text) {
+      Ok(address) if address < 0x8000 => Ok(address),
+      _ => Err(format!("'{}' is not a ROM address or label", text)),
+    }
+  }
+}
+
+fn parse_radix(format: &str) -> Result<Radix, String> {
+  match format {
+    "d" => Ok(Radix::Decimal),
+    "x" => Ok(Radix::Hex),
+    "b" => Ok(Radix::Binary),
+    _ => Err(format!("unknown format '/{}', expected /d, /x or /b", format)),
+  }
+}
+
+fn parse_count(text: &str) -> Result<u64, String> {
+  if text.is_empty() {
+    return Ok(1);
+  }
+  text.parse().map_err(|_| format!("'{}' is not a count", text))
+}
+
+// decimal (possibly negative), 0x hex or 0b binary
+fn parse_value(text: &str) -> Result<u16, String> {
+  let parsed = if let Some(hex) = text.strip_prefix("0x") {
+    u16::from_str_radix(hex, 16).ok()
+  } else if let Some(binary) = text.strip_prefix("0b") {
+    u16::from_str_radix(binary, 2).ok()
+  } else if text.starts_with('-') {
+    text.parse::<i16>().ok().map(|value| value as u16)
+  } else {
+    text.parse::<u16>().ok()
+  };
+  parsed.ok_or_else(|| format!("'{}' is not a 16-bit value", text))
+}
+
+fn format_value(value: u16, radix: Radix) -> String {
+  match radix {
+    Radix::Decimal => (value as i16).to_string(),
+    Radix::Hex => format!("0x{:04x}", value),
+    Radix::Binary => format!("0b{:016b}", value),
+  }
+}
+
+// load a `.asm` (assembled for `isa`, with symbols) or `.hack` program, a `.hack` file
+// picks up labels from an `.asm` file next to it when that assembles to the same words
+pub fn load_program(path: &Path, isa: Isa) -> Result<(Vec<Bit16>, SymbolTable), String> {
+  let source = fs::read_to_string(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+  if path.extension().is_some_and(|extension| extension == "asm") {
+    let program = asm::assemble_isa(&source, isa).map_err(|error| format!("{}: {}", path.display(), error))?;
+    return Ok((program.words, program.symbols));
+  }
+
+  let words = rom32k::parse_hack(&source).map_err(|error| format!("{}: {}", path.display(), error))?;
+  let symbols = fs::read_to_string(path.with_extension("asm"))
+    .ok()
+    .and_then(|source| asm::assemble_isa(&source, isa).ok())
+    .filter(|program| program.words == words)
+    .map(|program| program.symbols)
+    .unwrap_or_default();
+  Ok((words, symbols))
+}
@@ -0,0 +1,267 @@
+#![allow(dead_code)]
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::netlist::{Netlist, Node};
+use crate::verilog::{Declaration, Design, Direction, Expr, Item, Module};
+
+/*
+ DOT SCHEMATICS
+
+ graphviz drawings (`dot -Tsvg chip.dot > chip.svg`) of two kinds:
+
+ hierarchy | a module of a `verilog::Design` with its parts as boxes. parts are
+           | expanded into clusters `depth` levels down, so RAM4K at depth 0
+           | is a DMux8Way, eight RAM512s and a Mux8Way16, at depth 1 each
+           | RAM512 is opened up into its own DMux, RAM64s and Mux. leaves are
+           | unexpanded parts, `nand` primitives and DFFs
+ netlist   | every input, constant, NAND and output of a `Netlist`
+
+ wires follow assigns and ports through the hierarchy, an edge joins the part
+ driving a signal to each part reading it, labelled with the signal's name
+ in the outermost module it appears in (and its width, "out/16", for buses).
+*/
+
+// every NAND of a flattened netlist as its own node, left to right
+pub fn netlist(name: &str, netlist: &Netlist) -> String {
+  let mut out = header(name);
+  for (pos, node) in netlist.nodes().iter().enumerate() {
+    match node {
+      Node::Input(name) => out.push_str(&format!("  n{} [label=\"{}\", shape=plaintext];\n", pos, name)),
+      Node::Const(bit) => out.push_str(&format!("  n{} [label=\"{}\", shape=plaintext];\n", pos, bit)),
+      Node::Nand(..) => out.push_str(&format!("  n{} [label=\"nand\"];\n", pos)),
+    }
+  }
+  for (pos, (name, _)) in netlist.outputs().iter().enumerate() {
+    out.push_str(&format!("  o{} [label=\"{}\", shape=plaintext];\n", pos, name));
+  }
+  for (pos, node) in netlist.nodes().iter().enumerate() {
+    if let Node::Nand(a, b) = node {
+      out.push_str(&format!("  n{} -> n{};\n", a.0, pos));
+      out.push_str(&format!("  n{} -> n{};\n", b.0, pos));
+    }
+  }
+  for (pos, (_, id)) in netlist.outputs().iter().enumerate() {
+    out.push_str(&format!("  n{} -> o{};\n", id.0, pos));
+  }
+  out.push_str("}\n");
+  out
+}
+
+// `top` and its parts, expanded `depth` levels down
+pub fn hierarchy(design: &Design, top: &str, depth: usize) -> Result<String, String> {
+  let module = design.module(top).ok_or_else(|| format!("unknown module '{}'", top))?;
+  let mut graph = Graph {
+    design,
+    parents: Vec::new(),
+    names: Vec::new(),
+    blocks: Vec::new(),
+    clusters: vec![Cluster::default()],
+    drives: Vec::new(),
+    reads: Vec::new(),
+  };
+  let scope = graph.walk(module, depth, 0)?;
+  for (direction, port) in &module.ports {
+    let block = graph.block(port.name.clone(), "plaintext", 0);
+    for &net in &scope[&port.name].1 {
+      match direction {
+        Direction::Input => graph.drives.push((block, net)),
+        Direction::Output => graph.reads.push((block, net)),
+      }
+    }
+  }
+  Ok(graph.dot(top))
+}
+
+fn header(name: &str) -> String {
+  format!("digraph \"{}\" {{\n  rankdir=LR;\n  node [shape=box, fontname=\"monospace\"];\n", name)
+}
+
+#[derive(Clone, Debug, Default)]
+struct Block {
+  label: String,
+  shape: &'static str,
+}
+
+// the top module (0) or an expanded part
+#[derive(Clone, Debug, Default)]
+struct Cluster {
+  label: String,
+  blocks: Vec<usize>,
+  children: Vec<usize>,
+}
+
+struct Graph<'a> {
+  design: &'a Design,
+  // union-find over bit nets, the root is the net created first
+  parents: Vec<usize>,
+  names: Vec<String>,
+  blocks: Vec<Block>,
+  clusters: Vec<Cluster>,
+  // (block, net) pairs
+  drives: Vec<(usize, usize)>,
+  reads: Vec<(usize, usize)>,
+}
+
+// the nets of every signal of one module instance
+type Scope = HashMap<String, (Declaration, Vec<usize>)>;
+
+// a leaf reads and drives the nets on its ports, an expanded part joins them
+// to the nets inside
+enum Part {
+  Leaf(usize),
+  Expanded(Scope),
+}
+
+impl Graph<'_> {
+  fn net(&mut self, name: &str) -> usize {
+    self.parents.push(self.parents.len());
+    self.names.push(name.to_string());
+    self.parents.len() - 1
+  }
+
+  fn root(&mut self, mut net: usize) -> usize {
+    while self.parents[net] != net {
+      self.parents[net] = self.parents[self.parents[net]];
+      net = self.parents[net];
+    }
+    net
+  }
+
+  fn join(&mut self, a: usize, b: usize) {
+    let (a, b) = (self.root(a), self.root(b));
+    self.parents[a.max(b)] = a.min(b);
+  }
+
+  fn block(&mut self, label: String, shape: &'static str, cluster: usize) -> usize {
+    self.blocks.push(Block { label, shape });
+    self.clusters[cluster].blocks.push(self.blocks.len() - 1);
+    self.blocks.len() - 1
+  }
+
+  fn walk(&mut self, module: &Module, depth: usize, cluster: usize) -> Result<Scope, String> {
+    let mut scope = Scope::new();
+    for declaration in module.ports.iter().map(|(_, declaration)| declaration).chain(&module.wires) {
+      let nets = (0..declaration.width()).map(|_| self.net(&declaration.name)).collect();
+      scope.insert(declaration.name.clone(), (declaration.clone(), nets));
+    }
+
+    for item in &module.items {
+      match item {
+        Item::Assign(target, value) => {
+          let target = self.resolve(&scope, target, module, cluster)?;
+          let value = self.resolve(&scope, value, module, cluster)?;
+          for (target, value) in target.into_iter().zip(value) {
+            self.join(target, value);
+          }
+        }
+        Item::Nand(out, a, b) => {
+          let block = self.block("nand".to_string(), "box", cluster);
+          for net in self.resolve(&scope, out, module, cluster)? {
+            self.drives.push((block, net));
+          }
+          for expr in [a, b] {
+            for net in self.resolve(&scope, expr, module, cluster)? {
+              self.reads.push((block, net));
+            }
+          }
+        }
+        Item::Instance { module: chip, name, connections } => {
+          let child = self.design.module(chip).ok_or_else(|| format!("{}: unknown module '{}'", module.name, chip))?;
+          let label = format!("{}: {}", name, chip);
+          let part = if depth == 0 || child.behavioural {
+            Part::Leaf(self.block(label, "box", cluster))
+          } else {
+            let inner = self.clusters.len();
+            self.clusters.push(Cluster { label, ..Cluster::default() });
+            self.clusters[cluster].children.push(inner);
+            Part::Expanded(self.walk(child, depth - 1, inner)?)
+          };
+          for (port, expr) in connections {
+            let Some((direction, _)) = child.ports.iter().find(|(_, declaration)| &declaration.name == port) else {
+              return Err(format!("{}: module '{}' has no port '{}'", module.name, chip, port));
+            };
+            let outer = self.resolve(&scope, expr, module, cluster)?;
+            match &part {
+              Part::Expanded(child_scope) => {
+                for (outer, inner) in outer.into_iter().zip(child_scope[port].1.clone()) {
+                  self.join(outer, inner);
+                }
+              }
+              Part::Leaf(block) => {
+                for net in outer {
+                  match direction {
+                    Direction::Input => self.reads.push((*block, net)),
+                    Direction::Output => self.drives.push((*block, net)),
+                  }
+                }
+              }
+            }
+          }
+        }
+      }
+    }
+    Ok(scope)
+  }
+
+  fn resolve(&mut self, scope: &Scope, expr: &Expr, module: &Module, cluster: usize) -> Result<Vec<usize>, String> {
+    match expr {
+      Expr::Bits(bits) => {
+        let label: String = bits.iter().map(|bit| bit.to_string()).collect();
+        let block = self.block(format!("{}'b{}", bits.len(), label), "plaintext", cluster);
+        let nets: Vec<usize> = bits.iter().map(|_| self.net(&label)).collect();
+        self.drives.extend(nets.iter().map(|&net| (block, net)));
+        Ok(nets)
+      }
+      Expr::Signal { name, select } => {
+        let (declaration, nets) =
+          scope.get(name).ok_or_else(|| format!("{}: unknown signal '{}'", module.name, name))?;
+        let positions = declaration
+          .select(*select)
+          .map_err(|index| format!("{}: '{}[{}]' is out of range", module.name, name, index))?;
+        Ok(positions.into_iter().map(|pos| nets[pos]).collect())
+      }
+    }
+  }
+
+  fn dot(&mut self, name: &str) -> String {
+    let mut drivers: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (block, net) in self.drives.clone() {
+      drivers.entry(self.root(net)).or_default().push(block);
+    }
+    // (driver, reader) -> (signal, bits)
+    let mut edges: BTreeMap<(usize, usize), (String, usize)> = BTreeMap::new();
+    for (reader, net) in self.reads.clone() {
+      let root = self.root(net);
+      for &driver in drivers.get(&root).into_iter().flatten() {
+        let edge = edges.entry((driver, reader)).or_insert((self.names[root].clone(), 0));
+        edge.1 += 1;
+      }
+    }
+
+    let mut out = header(name);
+    self.cluster(&mut out, 0, 1);
+    for ((driver, reader), (signal, bits)) in edges {
+      let label = if bits > 1 { format!("{}/{}", signal, bits) } else { signal };
+      out.push_str(&format!("  b{} -> b{} [label=\"{}\"];\n", driver, reader, label));
+    }
+    out.push_str("}\n");
+    out
+  }
+
+  fn cluster(&self, out: &mut String, cluster: usize, indent: usize) {
+    let pad = "  ".repeat(indent);
+    for &block in &self.clusters[cluster].blocks {
+      let Block { label, shape } = &self.blocks[block];
+      out.push_str(&format!("{}b{} [label=\"{}\", shape={}];\n", pad, block, label, shape));
+    }
+    for &child in &self.clusters[cluster].children {
+      out.push_str(&format!(
+        "{}subgraph cluster_{} {{\n{}  label=\"{}\";\n",
+        pad, child, pad, self.clusters[child].label
+      ));
+      self.cluster(out, child, indent + 1);
+      out.push_str(&format!("{}}}\n", pad));
+    }
+  }
+}
@@ -0,0 +1,239 @@
+#![allow(dead_code)]
+
+use std::fmt;
+
+use crate::adders::Adder;
+use crate::alu;
+use crate::gates::{self, Bit};
+use crate::mem::ram8::RAM8;
+use crate::netlist::{self, Netlist, Node, NodeId, LANES};
+
+/*
+ FAULT INJECTION
+
+ fault        | where                        | model
+ -------------+------------------------------+-------------------------------
+ stuck-at 0/1 | a netlist node (input wire   | the node keeps the value
+              | or NAND output)              | whatever drives it
+ stuck-at 0/1 | a DFF of a sequential chip   | the state bit keeps the value
+ flip         | a DFF, at one cycle          | the state bit is inverted once
+              |                              | before that cycle (transient)
+
+ DFFs are numbered in snapshot order (`save`), for RAM8 bit = reg * 16 + pos
+ with pos MSB first. a running `Computer` takes the same faults through
+ `Computer::set_faults`: A, D and PC are bits 0..48, RAM word n starts at
+ 48 + 16 * n.
+
+ a fault is detected by a test vector when some output differs from the
+ fault-free chip. `Coverage` lists, for every fault, the vectors that detect
+ it, one fault at a time. stuck-at coverage runs 64 vectors per pass on the
+ bit-sliced netlist (`Netlist::batch_values_forced`).
+*/
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StuckAt {
+  pub node: NodeId,
+  pub value: Bit,
+}
+
+impl fmt::Display for StuckAt {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "node {} stuck-at-{}", self.node.0, self.value)
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StateFault {
+  StuckAt { bit: usize, value: Bit },
+  Flip { bit: usize, cycle: usize },
+}
+
+impl StateFault {
+  pub fn bit(&self) -> usize {
+    match *self {
+      StateFault::StuckAt { bit, .. } | StateFault::Flip { bit, .. } => bit,
+    }
+  }
+}
+
+impl fmt::Display for StateFault {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      StateFault::StuckAt { bit, value } => write!(f, "dff {} stuck-at-{}", bit, value),
+      StateFault::Flip { bit, cycle } => write!(f, "dff {} flip at cycle {}", bit, cycle),
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Coverage<F> {
+  // every fault with the indexes of the vectors that detect it
+  pub faults: Vec<(F, Vec<usize>)>,
+}
+
+impl<F> Coverage<F> {
+  pub fn detected(&self) -> usize {
+    self.faults.iter().filter(|(_, vectors)| !vectors.is_empty()).count()
+  }
+
+  pub fn undetected(&self) -> impl Iterator<Item = &F> {
+    self.faults.iter().filter(|(_, vectors)| vectors.is_empty()).map(|(fault, _)| fault)
+  }
+
+  // detected faults in percent
+  pub fn percent(&self) -> f64 {
+    if self.faults.is_empty() {
+      return 100.0;
+    }
+    100.0 * self.detected() as f64 / self.faults.len() as f64
+  }
+}
+
+impl<F: fmt::Display> fmt::Display for Coverage<F> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    for fault in self.undetected() {
+      writeln!(f, "undetected  {}", fault)?;
+    }
+    write!(f, "{} of {} faults detected ({:.1}%)", self.detected(), self.faults.len(), self.percent())
+  }
+}
+
+// output bits of `netlist` with the stuck-at faults applied
+pub fn eval(netlist: &Netlist, inputs: &[Bit], faults: &[StuckAt]) -> Vec<Bit> {
+  let forced: Vec<(NodeId, Bit)> = faults.iter().map(|fault| (fault.node, fault.value)).collect();
+  let values = netlist.values_forced(inputs, &forced);
+  netlist.outputs().iter().map(|(_, id)| values[id.0]).collect()
+}
+
+// stuck-at 0 and 1 on every input and NAND output (constants are skipped)
+pub fn stuck_at_faults(netlist: &Netlist) -> Vec<StuckAt> {
+  let sites = netlist.nodes().iter().enumerate().filter(|(_, node)| !matches!(node, Node::Const(_)));
+  sites.flat_map(|(pos, _)| [0, 1].map(|value| StuckAt { node: NodeId(pos), value })).collect()
+}
+
+// vectors are simulated 64 at a time, bit-sliced
+pub fn coverage(netlist: &Netlist, vectors: &[Vec<Bit>], faults: &[StuckAt]) -> Coverage<StuckAt> {
+  let chunks: Vec<(Vec<u64>, u64)> =
+    vectors.chunks(LANES).map(|chunk| (netlist::pack(chunk), netlist::lane_mask(chunk.len()))).collect();
+  let expected: Vec<Vec<u64>> = chunks.iter().map(|(inputs, _)| netlist.batch_eval(inputs)).collect();
+  let faults = faults.iter().map(|&fault| {
+    let mut detecting = Vec::new();
+    for (chunk, ((inputs, mask), expected)) in chunks.iter().zip(&expected).enumerate() {
+      let values = netlist.batch_values_forced(inputs, &[(fault.node, fault.value)]);
+      let outputs = netlist.outputs().iter().map(|(_, id)| values[id.0]);
+      let differ = outputs.zip(expected).fold(0, |differ, (actual, expected)| differ | (actual ^ expected)) & mask;
+      detecting.extend((0..LANES).filter(|lane| differ >> lane & 1 == 1).map(|lane| chunk * LANES + lane));
+    }
+    (fault, detecting)
+  });
+  Coverage { faults: faults.collect() }
+}
+
+// every row of the ALU table on a few operand pairs, in `alu::netlist` input
+// order: x, y, zx nx zy ny f no
+pub fn alu_vectors() -> Vec<Vec<Bit>> {
+  const CONTROLS: [[Bit; 6]; 18] = [
+    [1, 0, 1, 0, 1, 0],
+    [1, 1, 1, 1, 1, 1],
+    [1, 1, 1, 0, 1, 0],
+    [0, 0, 1, 1, 0, 0],
+    [1, 1, 0, 0, 0, 0],
+    [0, 0, 1, 1, 0, 1],
+    [1, 1, 0, 0, 0, 1],
+    [0, 0, 1, 1, 1, 1],
+    [1, 1, 0, 0, 1, 1],
+    [0, 1, 1, 1, 1, 1],
+    [1, 1, 0, 1, 1, 1],
+    [0, 0, 1, 1, 1, 0],
+    [1, 1, 0, 0, 1, 0],
+    [0, 0, 0, 0, 1, 0],
+    [0, 1, 0, 0, 1, 1],
+    [0, 0, 0, 1, 1, 1],
+    [0, 0, 0, 0, 0, 0],
+    [0, 1, 0, 1, 0, 1],
+  ];
+  const OPERANDS: [(u16, u16); 4] = [(0x0000, 0xffff), (0x5555, 0xaaaa), (0xaaaa, 0x5555), (0x7fff, 0x0001)];
+  let mut vectors = Vec::new();
+  for controls in CONTROLS {
+    for (x, y) in OPERANDS {
+      vectors.push(gates::from_u16(x).into_iter().chain(gates::from_u16(y)).chain(controls).collect());
+    }
+  }
+  vectors
+}
+
+pub fn alu_coverage(adder: Adder) -> Coverage<StuckAt> {
+  let netlist = alu::netlist(adder);
+  coverage(&netlist, &alu_vectors(), &stuck_at_faults(&netlist))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RamOp {
+  pub address: usize,
+  pub load: Bit,
+  pub input: u16,
+}
+
+// RAM8 outputs for every op: the op's tick, then a read of the same address
+// (load 0) so stuck bits forced after the write show up on the output
+pub fn run_ram8(ops: &[RamOp], faults: &[StateFault]) -> Vec<u16> {
+  let mut ram = RAM8::default();
+  force(&mut ram, faults, None);
+  let mut outputs = Vec::new();
+  for (cycle, op) in ops.iter().enumerate() {
+    force(&mut ram, faults, Some(cycle));
+    let address = [(op.address >> 2) & 1, (op.address >> 1) & 1, op.address & 1].map(|bit| bit as Bit);
+    ram.tick(address, op.load, gates::from_u16(op.input));
+    force(&mut ram, faults, None);
+    outputs.push(gates::to_u16(ram.tick(address, 0, [0; 16])));
+  }
+  outputs
+}
+
+// stuck bits always, flips only at `cycle`
+fn force(ram: &mut RAM8, faults: &[StateFault], cycle: Option<usize>) {
+  let mut bits = Vec::new();
+  ram.save(&mut bits);
+  for fault in faults {
+    match *fault {
+      StateFault::StuckAt { bit, value } => bits[bit] = value,
+      StateFault::Flip { bit, cycle: at } if Some(at) == cycle => bits[bit] = gates::not(bits[bit]),
+      StateFault::Flip { .. } => {}
+    }
+  }
+  ram.restore(&mut bits.iter());
+}
+
+// march-style tests: write a pattern to every register, then read them back
+pub fn ram8_tests() -> Vec<Vec<RamOp>> {
+  let pattern = |value: fn(usize) -> u16| {
+    let writes = (0..8).map(move |address| RamOp { address, load: 1, input: value(address) });
+    let reads = (0..8).map(|address| RamOp { address, load: 0, input: 0 });
+    writes.chain(reads).collect()
+  };
+  vec![
+    pattern(|_| 0x0000),
+    pattern(|_| 0xffff),
+    pattern(|address| if address % 2 == 0 { 0x5555 } else { 0xaaaa }),
+    pattern(|address| if address % 2 == 0 { 0xaaaa } else { 0x5555 }),
+  ]
+}
+
+// every DFF stuck at 0 and 1, and flipped between the writes and the reads
+pub fn ram8_faults() -> Vec<StateFault> {
+  let mut faults = Vec::new();
+  for bit in 0..8 * 16 {
+    faults.extend([StateFault::StuckAt { bit, value: 0 }, StateFault::StuckAt { bit, value: 1 }]);
+    faults.push(StateFault::Flip { bit, cycle: 8 });
+  }
+  faults
+}
+
+pub fn ram8_coverage(tests: &[Vec<RamOp>], faults: &[StateFault]) -> Coverage<StateFault> {
+  let expected: Vec<Vec<u16>> = tests.iter().map(|ops| run_ram8(ops, &[])).collect();
+  let faults = faults.iter().map(|&fault| {
+    let detecting = (0..tests.len()).filter(|&pos| run_ram8(&tests[pos], &[fault]) != expected[pos]);
+    (fault, detecting.collect())
+  });
+  Coverage { faults: faults.collect() }
+}
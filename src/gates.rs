@@ -5,6 +5,19 @@ pub type Bit4 = [Bit; 4];
 pub type Bit8 = [Bit; 8];
 pub type Bit16 = [Bit; 16];
 
+// conversions between bit arrays (MSB first) and integers
+pub fn from_u16(value: u16) -> Bit16 {
+  let mut out = [0; 16];
+  for (pos, bit) in out.iter_mut().enumerate() {
+    *bit = ((value >> (15 - pos)) & 1) as Bit;
+  }
+  out
+}
+
+pub fn to_u16(bits: Bit16) -> u16 {
+  bits.iter().fold(0, |acc, &bit| (acc << 1) | bit as u16)
+}
+
 fn eq_bit(a: Bit, b: Bit) -> Bit {
   not(xor(a, b))
 }
@@ -66,7 +79,7 @@ pub fn xor(a: Bit, b: Bit) -> Bit {
 }
 
 // 16-bit gates (not, and, or, mux)
-// todo: using simd to implement these gates?
+// netlist::Sliced runs circuits on 64 vectors at once, one per bit of a u64
 //
 
 // 16-bit NOT
@@ -74,7 +87,7 @@ pub fn xor(a: Bit, b: Bit) -> Bit {
 // -> = 0 | 1 | 0 | 1 | 0 | ....
 //
 pub fn not_16(a: Bit16) -> Bit16 {
-  return a.map(|x| not(x));
+  a.map(not)
 }
 
 // 16-bit AND
@@ -100,18 +113,47 @@ pub fn and_16(a: Bit16, b: Bit16) -> Bit16 {
 //
 pub fn or_16(a: Bit16, b: Bit16) -> Bit16 {
   let mut out = [0; 16];
-  for (pos, (bit_a, bit_b)) in a.into_iter().zip(b.into_iter()).enumerate() {
+  for (pos, (bit_a, bit_b)) in a.into_iter().zip(b).enumerate() {
     out[pos] = or(bit_a, bit_b);
   }
-  return out;
+  out
+}
+
+// 8-way OR
+// -> = n[0] | n[1] | ... | n[7]
+//
+pub fn or_8_way(n: Bit8) -> Bit {
+  or_n(&n)
+}
+
+// N-way reductions, as a balanced tree of 2-input gates
+// (an empty input reduces to the identity of the gate)
+//
+// and_n: 1 if every bit is 1
+// or_n:  1 if any bit is 1
+// xor_n: 1 if an odd number of bits are 1 (parity)
+//
+pub fn and_n(bits: &[Bit]) -> Bit {
+  reduce(bits, and, 1)
+}
+
+pub fn or_n(bits: &[Bit]) -> Bit {
+  reduce(bits, or, 0)
+}
+
+pub fn xor_n(bits: &[Bit]) -> Bit {
+  reduce(bits, xor, 0)
 }
 
-pub fn or_8_way(n: Bit8) -> Bit8 {
-  let mut out = [0; 8];
-  for (pos, bit) in n.into_iter().enumerate() {
-    out[pos] = or(bit, out[pos]);
+fn reduce(bits: &[Bit], gate: fn(Bit, Bit) -> Bit, identity: Bit) -> Bit {
+  match bits {
+    [] => identity,
+    [bit] => *bit,
+    _ => {
+      let (left, right) = bits.split_at(bits.len() / 2);
+      gate(reduce(left, gate, identity), reduce(right, gate, identity))
+    }
   }
-  return out;
 }
 
 /*
@@ -147,40 +189,59 @@ pub fn full_adder(a: Bit, b: Bit, c: Bit) -> (Bit, Bit) {
 }
 
 pub fn add_16(a: Bit16, b: Bit16) -> Bit16 {
-  let mut out = [0; 16];
-  let mut carry = 0;
-  for pos in (0..16).rev() {
-    let (sum, new_carry) = full_adder(a[pos], b[pos], carry);
-    out[pos] = sum;
-    carry = new_carry;
-  }
-  return out;
+  add_16_flags(a, b).0
 }
 
 pub fn inc_16(a: Bit16) -> Bit16 {
+  inc_16_flags(a).0
+}
+
+// a + b + carry with the flags the 16-bit result drops:
+//   carry:    carry out of bit 15 (unsigned overflow)
+//   overflow: a and b have the same sign and the sum has the other one
+//             (signed overflow)
+pub fn add_16_carry(a: Bit16, b: Bit16, carry: Bit) -> (Bit16, Bit, Bit) {
   let mut out = [0; 16];
-  let mut carry = 1;
+  let mut carry = carry;
   for pos in (0..16).rev() {
-    let (sum, new_carry) = full_adder(a[pos], 0, carry);
+    let (sum, new_carry) = full_adder(a[pos], b[pos], carry);
     out[pos] = sum;
     carry = new_carry;
   }
-  out
+  (out, carry, overflow(a[0], b[0], out[0]))
+}
+
+pub fn add_16_flags(a: Bit16, b: Bit16) -> (Bit16, Bit, Bit) {
+  add_16_carry(a, b, 0)
+}
+
+pub fn inc_16_flags(a: Bit16) -> (Bit16, Bit, Bit) {
+  add_16_carry(a, [0; 16], 1)
 }
 
-type Bit2 = [Bit; 2];
-type Bit3 = [Bit; 3];
+// signed overflow from the sign bits of both operands and the sum
+pub fn overflow(sign_a: Bit, sign_b: Bit, sign_sum: Bit) -> Bit {
+  and(eq_bit(sign_a, sign_b), xor(sign_a, sign_sum))
+}
+
+pub type Bit2 = [Bit; 2];
+pub type Bit3 = [Bit; 3];
+// mux/dmux come in two flavours with the same truth tables:
+//   shortcut:   rust `if`/`match`, the default fast path
+//   structural: composed from and/or/not like every other gate,
+//               selected with the `structural` feature
+#[cfg(not(feature = "structural"))]
+pub use shortcut::{dmux, dmux4way, dmux8way, mux, mux4way16, mux8way16};
+#[cfg(feature = "structural")]
+pub use structural::{dmux, dmux4way, dmux8way, mux, mux4way16, mux8way16};
+
 // MULTIPLEXER GATE
 //
 // sel | ->
 // ----+---
 //  0  | a
 //  1  | b
-pub fn mux(a: Bit, b: Bit, sel: Bit) -> Bit {
-  return if sel == 0 { a } else { b };
-  // (a & !sel) | (b & sel)
-}
-
+//
 // DEMULTIPLEXER GATE
 //
 // sel | a |  b
@@ -188,11 +249,140 @@ pub fn mux(a: Bit, b: Bit, sel: Bit) -> Bit {
 //  0  | n |  0
 //  1  | 0 |  n
 //
-pub fn dmux(n: Bit, sel: Bit) -> (Bit, Bit) {
-  if sel == 0 {
-    return (n, 0);
+// 4-way MUX
+//
+// sel0 | sel1 | ->
+// -----+------+---
+//   0  |   0  |  a
+//   1  |   0  |  b
+//   0  |   1  |  c
+//   1  |   1  |  d
+//
+// 8-way MUX (sel MSB first)
+//
+// sel0 | sel1 | sel2 | ->
+// -----+------+------+---
+//   0  |   0  |   0  |  a
+//   0  |   0  |   1  |  b
+//  ... |  ... |  ... | ...
+//   1  |   1  |   1  |  h
+//
+pub mod shortcut {
+  use super::{Bit, Bit16, Bit2, Bit3};
+
+  pub fn mux(a: Bit, b: Bit, sel: Bit) -> Bit {
+    if sel == 0 {
+      a
+    } else {
+      b
+    }
+  }
+
+  pub fn dmux(n: Bit, sel: Bit) -> (Bit, Bit) {
+    if sel == 0 {
+      (n, 0)
+    } else {
+      (0, n)
+    }
+  }
+
+  pub fn mux4way16(a: Bit16, b: Bit16, c: Bit16, d: Bit16, sel: Bit2) -> Bit16 {
+    match sel {
+      [0, 0] => a,
+      [1, 0] => b,
+      [0, 1] => c,
+      [1, 1] => d,
+      _ => panic!("error: sel value is not valid!"),
+    }
+  }
+
+  #[allow(clippy::too_many_arguments)]
+  pub fn mux8way16(a: Bit16, b: Bit16, c: Bit16, d: Bit16, e: Bit16, f: Bit16, g: Bit16, h: Bit16, sel: Bit3) -> Bit16 {
+    match sel {
+      [0, 0, 0] => a,
+      [0, 0, 1] => b,
+      [0, 1, 0] => c,
+      [0, 1, 1] => d,
+      [1, 0, 0] => e,
+      [1, 0, 1] => f,
+      [1, 1, 0] => g,
+      [1, 1, 1] => h,
+      _ => panic!("error: sel value is not valid!"),
+    }
+  }
+
+  pub fn dmux4way(n: Bit, sel: Bit2) -> (Bit, Bit, Bit, Bit) {
+    match sel {
+      [0, 0] => (n, 0, 0, 0),
+      [1, 0] => (0, n, 0, 0),
+      [0, 1] => (0, 0, n, 0),
+      [1, 1] => (0, 0, 0, n),
+      _ => panic!("error: sel value is not valid!"),
+    }
+  }
+
+  pub fn dmux8way(n: Bit, sel: Bit3) -> (Bit, Bit, Bit, Bit, Bit, Bit, Bit, Bit) {
+    match sel {
+      [0, 0, 0] => (n, 0, 0, 0, 0, 0, 0, 0),
+      [0, 0, 1] => (0, n, 0, 0, 0, 0, 0, 0),
+      [0, 1, 0] => (0, 0, n, 0, 0, 0, 0, 0),
+      [0, 1, 1] => (0, 0, 0, n, 0, 0, 0, 0),
+      [1, 0, 0] => (0, 0, 0, 0, n, 0, 0, 0),
+      [1, 0, 1] => (0, 0, 0, 0, 0, n, 0, 0),
+      [1, 1, 0] => (0, 0, 0, 0, 0, 0, n, 0),
+      [1, 1, 1] => (0, 0, 0, 0, 0, 0, 0, n),
+      _ => panic!("error: sel value is not valid!"),
+    }
+  }
+}
+
+pub mod structural {
+  use super::{and, not, or, Bit, Bit16, Bit2, Bit3};
+
+  // (a & !sel) | (b & sel)
+  pub fn mux(a: Bit, b: Bit, sel: Bit) -> Bit {
+    or(and(a, not(sel)), and(b, sel))
+  }
+
+  // (n & !sel, n & sel)
+  pub fn dmux(n: Bit, sel: Bit) -> (Bit, Bit) {
+    (and(n, not(sel)), and(n, sel))
+  }
+
+  fn mux16(a: Bit16, b: Bit16, sel: Bit) -> Bit16 {
+    let mut out = [0; 16];
+    for (pos, (bit_a, bit_b)) in a.into_iter().zip(b).enumerate() {
+      out[pos] = mux(bit_a, bit_b, sel);
+    }
+    out
+  }
+
+  // sel[0] is the low bit here
+  pub fn mux4way16(a: Bit16, b: Bit16, c: Bit16, d: Bit16, sel: Bit2) -> Bit16 {
+    mux16(mux16(a, b, sel[0]), mux16(c, d, sel[0]), sel[1])
+  }
+
+  // sel[0] is the high bit here
+  #[allow(clippy::too_many_arguments)]
+  pub fn mux8way16(a: Bit16, b: Bit16, c: Bit16, d: Bit16, e: Bit16, f: Bit16, g: Bit16, h: Bit16, sel: Bit3) -> Bit16 {
+    let low = [sel[2], sel[1]];
+    mux16(mux4way16(a, b, c, d, low), mux4way16(e, f, g, h, low), sel[0])
+  }
+
+  pub fn dmux4way(n: Bit, sel: Bit2) -> (Bit, Bit, Bit, Bit) {
+    let (lower, upper) = dmux(n, sel[1]);
+    let (a, b) = dmux(lower, sel[0]);
+    let (c, d) = dmux(upper, sel[0]);
+    (a, b, c, d)
+  }
+
+  pub fn dmux8way(n: Bit, sel: Bit3) -> (Bit, Bit, Bit, Bit, Bit, Bit, Bit, Bit) {
+    let (lower, upper) = dmux(n, sel[0]);
+    let low = [sel[2], sel[1]];
+    let (a, b, c, d) = dmux4way(lower, low);
+    let (e, f, g, h) = dmux4way(upper, low);
+    (a, b, c, d, e, f, g, h)
   }
-  return (0, n);
 }
 
 // 16-bit MUX
@@ -205,65 +395,55 @@ pub fn dmux(n: Bit, sel: Bit) -> (Bit, Bit) {
 //
 pub fn mux16(a: Bit16, b: Bit16, sel: Bit) -> Bit16 {
   let mut out = [0; 16]; // TODO: use a slice
-  for (pos, (bit_a, bit_b)) in a.into_iter().zip(b.into_iter()).enumerate() {
+  for (pos, (bit_a, bit_b)) in a.into_iter().zip(b).enumerate() {
     out[pos] = mux(bit_a, bit_b, sel);
   }
-  return out;
+  out
 }
 
-// 4-way MUX
+// N-way MUX/DMUX, built as trees of `mux16`/`dmux`
 //
-// sel0 | sel1 | ->
-// -----+------+---
-//   0  |   0  |  a
-//   1  |   0  |  b
-//   0  |   1  |  c
-//   1  |   1  |  d
-
-pub fn mux4way16(a: Bit16, b: Bit16, c: Bit16, d: Bit16, sel: Bit2) -> Bit16 {
+// `sel` is MSB first (like `mux8way16`): sel[0] picks the upper or lower half
+// of the inputs, the rest of `sel` picks inside that half.
+// `inputs.len()` must be 2^sel.len().
+//
+pub fn mux_n_way16(inputs: &[Bit16], sel: &[Bit]) -> Bit16 {
+  assert_eq!(inputs.len(), 1 << sel.len(), "error: {} inputs need a {}-bit sel", inputs.len(), sel.len());
   match sel {
-    [0, 0] => a,
-    [1, 0] => b,
-    [0, 1] => c,
-    [1, 1] => d,
-    _ => panic!("error: sel value is not valid!"),
+    [] => inputs[0],
+    [high, rest @ ..] => {
+      let (lower, upper) = inputs.split_at(inputs.len() / 2);
+      mux16(mux_n_way16(lower, rest), mux_n_way16(upper, rest), *high)
+    }
   }
 }
 
-pub fn mux8way16(a: Bit16, b: Bit16, c: Bit16, d: Bit16, e: Bit16, f: Bit16, g: Bit16, h: Bit16, sel: Bit3) -> Bit16 {
+pub fn dmux_n_way(n: Bit, sel: &[Bit]) -> Vec<Bit> {
   match sel {
-    [0, 0, 0] => a,
-    [0, 0, 1] => b,
-    [0, 1, 0] => c,
-    [0, 1, 1] => d,
-    [1, 0, 0] => e,
-    [1, 0, 1] => f,
-    [1, 1, 0] => g,
-    [1, 1, 1] => h,
-    _ => panic!("error: sel value is not valid!"),
+    [] => vec![n],
+    [high, rest @ ..] => {
+      let (lower, upper) = dmux(n, *high);
+      let mut out = dmux_n_way(lower, rest);
+      out.extend(dmux_n_way(upper, rest));
+      out
+    }
   }
 }
 
-pub fn dmux4way(n: Bit, sel: Bit2) -> (Bit, Bit, Bit, Bit) {
-  match sel {
-    [0, 0] => (n, 0, 0, 0),
-    [1, 0] => (0, n, 0, 0),
-    [0, 1] => (0, 0, n, 0),
-    [1, 1] => (0, 0, 0, n),
-    _ => panic!("error: sel value is not valid!"),
-  }
+// 16-way MUX
+//
+// sel     | ->
+// --------+---------
+// 0000    | inputs[0]
+// 0001    | inputs[1]
+// ...     | ...
+// 1111    | inputs[15]
+//
+pub fn mux16way16(inputs: [Bit16; 16], sel: Bit4) -> Bit16 {
+  mux_n_way16(&inputs, &sel)
 }
 
-pub fn dmux8way(n: Bit, sel: Bit3) -> (Bit, Bit, Bit, Bit, Bit, Bit, Bit, Bit) {
-  match sel {
-    [0, 0, 0] => (n, 0, 0, 0, 0, 0, 0, 0),
-    [0, 0, 1] => (0, n, 0, 0, 0, 0, 0, 0),
-    [0, 1, 0] => (0, 0, n, 0, 0, 0, 0, 0),
-    [0, 1, 1] => (0, 0, 0, n, 0, 0, 0, 0),
-    [1, 0, 0] => (0, 0, 0, 0, n, 0, 0, 0),
-    [1, 0, 1] => (0, 0, 0, 0, 0, n, 0, 0),
-    [1, 1, 0] => (0, 0, 0, 0, 0, 0, n, 0),
-    [1, 1, 1] => (0, 0, 0, 0, 0, 0, 0, n),
-    _ => panic!("error: sel value is not valid!"),
-  }
+// 16-way DMUX: n goes to out[sel], every other output is 0
+pub fn dmux16way(n: Bit, sel: Bit4) -> [Bit; 16] {
+  dmux_n_way(n, &sel).try_into().unwrap()
 }
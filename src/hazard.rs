@@ -0,0 +1,146 @@
+#![allow(dead_code)]
+
+use std::fmt;
+
+use crate::adders::Adder;
+use crate::alu;
+use crate::fault;
+use crate::gates::Bit;
+use crate::netlist::{Logic, Netlist, Node};
+use crate::timing::{self, Delays};
+
+/*
+ HAZARDS
+
+ an input change is replayed gate by gate (`timing::simulate`). an output
+ that should stay put but pulses, or that should switch once but switches
+ several times, has a hazard:
+
+ kind     | before -> after | output changes
+ ---------+-----------------+-----------------------
+ static-0 | 0 -> 0          | 0 -> 1 -> 0 (or more)
+ static-1 | 1 -> 1          | 1 -> 0 -> 1 (or more)
+ dynamic  | 0 -> 1, 1 -> 0  | 3 or more
+
+ the classic one is the mux: with a = b = 1, dropping sel turns b & sel off
+ before the slower a & !sel turns on, so the output dips to 0.
+*/
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+  Static0,
+  Static1,
+  Dynamic,
+}
+
+impl fmt::Display for Kind {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Kind::Static0 => write!(f, "static-0"),
+      Kind::Static1 => write!(f, "static-1"),
+      Kind::Dynamic => write!(f, "dynamic"),
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hazard {
+  pub output: String,
+  pub kind: Kind,
+  // the inputs that switched, as "sel 1->0"
+  pub switched: Vec<String>,
+  // every input before the change (in `Netlist::inputs` order)
+  pub before: Vec<Bit>,
+  // (time, value) of every output change
+  pub changes: Vec<(u64, Bit)>,
+}
+
+impl fmt::Display for Hazard {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let inputs: String = self.before.iter().map(|bit| bit.to_string()).collect();
+    let changes: Vec<String> = self.changes.iter().map(|(time, value)| format!("{} at {}", value, time)).collect();
+    write!(
+      f,
+      "{}: {} hazard when {} (inputs {}): {}",
+      self.output,
+      self.kind,
+      self.switched.join(", "),
+      inputs,
+      changes.join(", ")
+    )
+  }
+}
+
+// hazards on every output for one change of the inputs
+pub fn detect(netlist: &Netlist, delays: &Delays, before: &[Bit], after: &[Bit]) -> Vec<Hazard> {
+  let transition = timing::simulate(netlist, delays, before, after);
+  let initial = netlist.eval(before);
+  let mut hazards = Vec::new();
+  for (pos, changes) in transition.output_changes(netlist).into_iter().enumerate() {
+    let last = changes.last().map_or(initial[pos], |&(_, value)| value);
+    let kind = match (initial[pos] == last, changes.len()) {
+      (true, 2..) if initial[pos] == 0 => Kind::Static0,
+      (true, 2..) => Kind::Static1,
+      (false, 3..) => Kind::Dynamic,
+      _ => continue,
+    };
+    hazards.push(Hazard {
+      output: netlist.outputs()[pos].0.clone(),
+      kind,
+      switched: switched(netlist, before, after),
+      before: before.to_vec(),
+      changes,
+    });
+  }
+  hazards
+}
+
+fn switched(netlist: &Netlist, before: &[Bit], after: &[Bit]) -> Vec<String> {
+  let mut out = Vec::new();
+  for (pos, &id) in netlist.inputs().iter().enumerate() {
+    if before[pos] != after[pos] {
+      let Node::Input(name) = netlist.node(id) else { unreachable!() };
+      out.push(format!("{} {}->{}", name, before[pos], after[pos]));
+    }
+  }
+  out
+}
+
+// every single-input change from each of the `vectors`
+pub fn scan(netlist: &Netlist, delays: &Delays, vectors: &[Vec<Bit>]) -> Vec<Hazard> {
+  let mut hazards = Vec::new();
+  for before in vectors {
+    for pos in 0..before.len() {
+      let mut after = before.clone();
+      after[pos] = 1 - after[pos];
+      hazards.extend(detect(netlist, delays, before, &after));
+    }
+  }
+  hazards
+}
+
+// every input vector of a small netlist
+pub fn all_vectors(inputs: usize) -> Vec<Vec<Bit>> {
+  (0..1usize << inputs)
+    .map(|vector| (0..inputs).map(|pos| ((vector >> (inputs - 1 - pos)) & 1) as Bit).collect())
+    .collect()
+}
+
+// inputs a, b, sel, output out, built like gates::mux
+pub fn mux_netlist() -> Netlist {
+  let mut netlist = Netlist::new();
+  let (a, b, sel) = (netlist.input("a"), netlist.input("b"), netlist.input("sel"));
+  let out = netlist.mux(a, b, sel);
+  netlist.output("out", out);
+  netlist
+}
+
+pub fn mux_hazards(delays: &Delays) -> Vec<Hazard> {
+  let netlist = mux_netlist();
+  scan(&netlist, delays, &all_vectors(3))
+}
+
+// single-input changes from the fault-coverage vectors
+pub fn alu_hazards(adder: Adder, delays: &Delays) -> Vec<Hazard> {
+  scan(&alu::netlist(adder), delays, &fault::alu_vectors())
+}
@@ -0,0 +1,134 @@
+#![allow(dead_code)]
+
+use std::collections::VecDeque;
+
+use crate::computer::{Access, Step};
+
+/*
+ EXECUTION HISTORY
+
+ the run is split in segments of `interval` cycles, every segment starts with
+ a full machine snapshot (checkpoint) followed by one delta per cycle:
+
+ segment | checkpoint (cycle 0) | d0 | d1 | ... | d(interval - 1)
+ segment | checkpoint (cycle n) | dn | ...
+
+ a delta holds the registers after the cycle and the single RAM write it did,
+ so going back to cycle c means: restore the checkpoint of c's segment and
+ replay the deltas up to c. RAM words changed by DFF faults around a cycle
+ (see `Computer::set_faults`) are kept next to the deltas and replayed in
+ the same order, before or after that cycle's write. only the last `segments` segments are kept,
+ which bounds memory to roughly `segments` x (snapshot + interval deltas).
+*/
+
+pub const DEFAULT_INTERVAL: u64 = 256;
+pub const DEFAULT_SEGMENTS: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Delta {
+  pub cycle: u64,
+  // pc before the cycle
+  pub pc: u16,
+  // registers after the cycle
+  pub a: u16,
+  pub d: u16,
+  pub next_pc: u16,
+  pub write: Option<Access>,
+}
+
+impl From<&Step> for Delta {
+  fn from(step: &Step) -> Self {
+    Self { cycle: step.cycle, pc: step.pc, a: step.a, d: step.d, next_pc: step.next_pc, write: step.write }
+  }
+}
+
+// a RAM word set by a DFF fault rather than by the cpu
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Forced {
+  pub cycle: u64,
+  pub after_write: bool,
+  pub access: Access,
+}
+
+#[derive(Clone)]
+pub struct Segment {
+  pub checkpoint: Vec<u8>,
+  pub start: u64,
+  pub deltas: Vec<Delta>,
+  // empty unless the machine has DFF faults
+  pub forced: Vec<Forced>,
+}
+
+#[derive(Clone)]
+pub struct History {
+  interval: u64,
+  max_segments: usize,
+  segments: VecDeque<Segment>,
+}
+
+impl History {
+  pub fn new(interval: u64, max_segments: usize) -> Self {
+    assert!(interval > 0 && max_segments > 0, "error: history needs a positive interval and segment count");
+    Self { interval, max_segments, segments: VecDeque::new() }
+  }
+
+  // a new segment is due when there is none yet or the last one is full
+  pub fn needs_checkpoint(&self) -> bool {
+    self.segments.back().is_none_or(|segment| segment.deltas.len() as u64 >= self.interval)
+  }
+
+  pub fn checkpoint(&mut self, cycle: u64, snapshot: Vec<u8>) {
+    self.segments.push_back(Segment { checkpoint: snapshot, start: cycle, deltas: Vec::new(), forced: Vec::new() });
+    while self.segments.len() > self.max_segments {
+      self.segments.pop_front();
+    }
+  }
+
+  pub fn record(&mut self, step: &Step) {
+    let segment = self.segments.back_mut().expect("error: history records need a checkpoint first");
+    segment.deltas.push(Delta::from(step));
+  }
+
+  pub fn record_forced(&mut self, forced: Forced) {
+    let segment = self.segments.back_mut().expect("error: history records need a checkpoint first");
+    segment.forced.push(forced);
+  }
+
+  pub fn clear(&mut self) {
+    self.segments.clear();
+  }
+
+  // first cycle the machine can be rewound to
+  pub fn oldest(&self) -> Option<u64> {
+    self.segments.front().map(|segment| segment.start)
+  }
+
+  // snapshot of the state at `oldest`
+  pub fn oldest_checkpoint(&self) -> Option<&[u8]> {
+    self.segments.front().map(|segment| segment.checkpoint.as_slice())
+  }
+
+  // recorded deltas, oldest first
+  pub fn deltas(&self) -> impl DoubleEndedIterator<Item = &Delta> {
+    self.segments.iter().flat_map(|segment| segment.deltas.iter())
+  }
+
+  // checkpoint, deltas and forced words needed to rebuild the state before
+  // `cycle`, later ones are dropped since execution restarts from there
+  pub fn truncate(&mut self, cycle: u64) -> Option<(Vec<u8>, Vec<Delta>, Vec<Forced>)> {
+    let index = self.segments.iter().rposition(|segment| segment.start <= cycle)?;
+    let keep = (cycle - self.segments[index].start) as usize;
+    if keep > self.segments[index].deltas.len() {
+      return None;
+    }
+    self.segments.truncate(index + 1);
+    let segment = self.segments.back_mut()?;
+    segment.deltas.truncate(keep);
+    segment.forced.retain(|forced| forced.cycle < cycle);
+    Some((segment.checkpoint.clone(), segment.deltas.clone(), segment.forced.clone()))
+  }
+
+  pub fn segments(&self) -> usize {
+    self.segments.len()
+  }
+}
@@ -0,0 +1,295 @@
+#![allow(dead_code)]
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::alu::ExtOp;
+use crate::gates::{self, Bit, Bit16};
+use crate::shifter::Shift;
+
+/*
+ HACK INSTRUCTION FORMAT (bit 15 first)
+
+ A-instruction:  0 v v v v v v v v v v v v v v v    @value
+ C-instruction:  1 1 1 a c1 c2 c3 c4 c5 c6 d1 d2 d3 j1 j2 j3
+
+   a:     y input of the ALU is M when set, A otherwise
+   c1-c6: ALU control bits (zx nx zy ny f no)
+   d1-d3: destinations (A D M)
+   j1-j3: jump if out < 0, out == 0, out > 0
+
+ INSTRUCTION SETS
+
+ isa      | C-instruction bits 14-13
+ ---------+-------------------------------------------------------------
+ hack     | ignored, every C-instruction runs on the ALU (stock Hack)
+ hack-ext | 11 ALU, 01 shift, 10 multiply / divide, 00 carry / overflow
+
+ stock programs always set bits 14-13, so they run the same on both.
+
+ SHIFT (hack-ext)        1 0 1 a c1 c2 c3 c4 c5 c6 d1 d2 d3 j1 j2 j3
+
+   c1: shift left (right when 0)
+   c2: the value is D and the amount A/M (value A/M, amount D when 0)
+   c3: rotate
+   c4: logical right shift (arithmetic when 0)
+   c5: shift by the amount register & 15 (by 1 when 0)
+   c6: 0
+
+   `D<<`, `A>>`, `M<<`... keep the encoding of the common nand2tetris shift
+   extension. `>>` is arithmetic and `>>>` logical, like in Java / Jack.
+
+ MULTIPLY / DIVIDE (hack-ext)   1 1 0 a c1 c2 c3 0 0 0 d1 d2 d3 j1 j2 j3
+
+   c1 c2: 00 x * y (low 16 bits), 01 x / y, 10 x % y (unsigned)
+   c3:    x = A/M and y = D (x = D, y = A/M when 0)
+
+ CARRY / OVERFLOW (hack-ext)    1 0 0 a c1 c2 c3 c4 0 0 d1 d2 d3 j1 j2 j3
+
+   c1: subtract (add when 0)
+   c2: signed overflow (unsigned carry, or borrow when subtracting, when 0)
+   c3: x = A/M (x = D when 0)
+   c4: y = 1 (y = the other register when 0)
+
+   the result is the flag, 0 or 1: `D=C(D+A)` is the carry out of D+A, so a
+   32-bit add is low = D+A, high = X+Y+C(D+A).
+*/
+
+// comp mnemonics indexed by the ALU control bits, `A` is replaced by `M` when a = 1
+pub const COMP_TABLE: [([Bit; 6], &str); 18] = [
+  ([1, 0, 1, 0, 1, 0], "0"),
+  ([1, 1, 1, 1, 1, 1], "1"),
+  ([1, 1, 1, 0, 1, 0], "-1"),
+  ([0, 0, 1, 1, 0, 0], "D"),
+  ([1, 1, 0, 0, 0, 0], "A"),
+  ([0, 0, 1, 1, 0, 1], "!D"),
+  ([1, 1, 0, 0, 0, 1], "!A"),
+  ([0, 0, 1, 1, 1, 1], "-D"),
+  ([1, 1, 0, 0, 1, 1], "-A"),
+  ([0, 1, 1, 1, 1, 1], "D+1"),
+  ([1, 1, 0, 1, 1, 1], "A+1"),
+  ([0, 0, 1, 1, 1, 0], "D-1"),
+  ([1, 1, 0, 0, 1, 0], "A-1"),
+  ([0, 0, 0, 0, 1, 0], "D+A"),
+  ([0, 1, 0, 0, 1, 1], "D-A"),
+  ([0, 0, 0, 1, 1, 1], "A-D"),
+  ([0, 0, 0, 0, 0, 0], "D&A"),
+  ([0, 1, 0, 1, 0, 1], "D|A"),
+];
+
+// shift mnemonics indexed by the control bits of a shift instruction, `A` is
+// replaced by `M` when a = 1
+pub const SHIFT_TABLE: [([Bit; 6], &str); 20] = [
+  ([1, 1, 0, 0, 0, 0], "D<<"),
+  ([1, 0, 0, 0, 0, 0], "A<<"),
+  ([0, 1, 0, 0, 0, 0], "D>>"),
+  ([0, 0, 0, 0, 0, 0], "A>>"),
+  ([0, 1, 0, 1, 0, 0], "D>>>"),
+  ([0, 0, 0, 1, 0, 0], "A>>>"),
+  ([1, 1, 1, 0, 0, 0], "ROL(D)"),
+  ([1, 0, 1, 0, 0, 0], "ROL(A)"),
+  ([0, 1, 1, 0, 0, 0], "ROR(D)"),
+  ([0, 0, 1, 0, 0, 0], "ROR(A)"),
+  ([1, 1, 0, 0, 1, 0], "D<<A"),
+  ([1, 0, 0, 0, 1, 0], "A<<D"),
+  ([0, 1, 0, 0, 1, 0], "D>>A"),
+  ([0, 0, 0, 0, 1, 0], "A>>D"),
+  ([0, 1, 0, 1, 1, 0], "D>>>A"),
+  ([0, 0, 0, 1, 1, 0], "A>>>D"),
+  ([1, 1, 1, 0, 1, 0], "ROL(D,A)"),
+  ([1, 0, 1, 0, 1, 0], "ROL(A,D)"),
+  ([0, 1, 1, 0, 1, 0], "ROR(D,A)"),
+  ([0, 0, 1, 0, 1, 0], "ROR(A,D)"),
+];
+
+// multiply / divide and carry / overflow mnemonics, `A` is replaced by `M` when a = 1
+pub const MULDIV_TABLE: [([Bit; 6], &str); 6] = [
+  ([0, 0, 0, 0, 0, 0], "D*A"),
+  ([0, 0, 1, 0, 0, 0], "A*D"),
+  ([0, 1, 0, 0, 0, 0], "D/A"),
+  ([0, 1, 1, 0, 0, 0], "A/D"),
+  ([1, 0, 0, 0, 0, 0], "D%A"),
+  ([1, 0, 1, 0, 0, 0], "A%D"),
+];
+
+pub const FLAG_TABLE: [([Bit; 6], &str); 16] = [
+  ([0, 0, 0, 0, 0, 0], "C(D+A)"),
+  ([0, 0, 1, 0, 0, 0], "C(A+D)"),
+  ([0, 0, 0, 1, 0, 0], "C(D+1)"),
+  ([0, 0, 1, 1, 0, 0], "C(A+1)"),
+  ([1, 0, 0, 0, 0, 0], "C(D-A)"),
+  ([1, 0, 1, 0, 0, 0], "C(A-D)"),
+  ([1, 0, 0, 1, 0, 0], "C(D-1)"),
+  ([1, 0, 1, 1, 0, 0], "C(A-1)"),
+  ([0, 1, 0, 0, 0, 0], "V(D+A)"),
+  ([0, 1, 1, 0, 0, 0], "V(A+D)"),
+  ([0, 1, 0, 1, 0, 0], "V(D+1)"),
+  ([0, 1, 1, 1, 0, 0], "V(A+1)"),
+  ([1, 1, 0, 0, 0, 0], "V(D-A)"),
+  ([1, 1, 1, 0, 0, 0], "V(A-D)"),
+  ([1, 1, 0, 1, 0, 0], "V(D-1)"),
+  ([1, 1, 1, 1, 0, 0], "V(A-1)"),
+];
+
+pub const DEST_TABLE: [&str; 8] = ["", "M", "D", "MD", "A", "AM", "AD", "AMD"];
+
+pub const JUMP_TABLE: [&str; 8] = ["", "JGT", "JEQ", "JGE", "JLT", "JNE", "JLE", "JMP"];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Isa {
+  #[default]
+  Hack,
+  HackExt,
+}
+
+impl Isa {
+  pub const ALL: [Isa; 2] = [Isa::Hack, Isa::HackExt];
+
+  pub fn name(self) -> &'static str {
+    match self {
+      Isa::Hack => "hack",
+      Isa::HackExt => "hack-ext",
+    }
+  }
+}
+
+impl fmt::Display for Isa {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str(self.name())
+  }
+}
+
+impl FromStr for Isa {
+  type Err = String;
+
+  fn from_str(name: &str) -> Result<Self, String> {
+    Isa::ALL.into_iter().find(|isa| isa.name() == name).ok_or_else(|| format!("unknown instruction set '{}'", name))
+  }
+}
+
+// what runs a C-instruction, from bits 14-13
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Group {
+  Alu,
+  Shift,
+  MulDiv,
+  Flag,
+}
+
+impl Group {
+  pub fn decode(word: Bit16, isa: Isa) -> Self {
+    match (isa, word[1], word[2]) {
+      (Isa::Hack, _, _) | (_, 1, 1) => Group::Alu,
+      (_, 0, 1) => Group::Shift,
+      (_, 1, 0) => Group::MulDiv,
+      _ => Group::Flag,
+    }
+  }
+
+  // bits 14-13
+  pub fn prefix(self) -> [Bit; 2] {
+    match self {
+      Group::Alu => [1, 1],
+      Group::Shift => [0, 1],
+      Group::MulDiv => [1, 0],
+      Group::Flag => [0, 0],
+    }
+  }
+
+  pub fn table(self) -> &'static [([Bit; 6], &'static str)] {
+    match self {
+      Group::Alu => &COMP_TABLE,
+      Group::Shift => &SHIFT_TABLE,
+      Group::MulDiv => &MULDIV_TABLE,
+      Group::Flag => &FLAG_TABLE,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+  A(u16),
+  C { group: Group, a: Bit, comp: [Bit; 6], dest: [Bit; 3], jump: [Bit; 3] },
+}
+
+impl Instruction {
+  pub fn decode(word: Bit16, isa: Isa) -> Self {
+    if word[0] == 0 {
+      return Instruction::A(gates::to_u16(word));
+    }
+    Instruction::C {
+      group: Group::decode(word, isa),
+      a: word[3],
+      comp: word[4..10].try_into().unwrap(),
+      dest: word[10..13].try_into().unwrap(),
+      jump: word[13..16].try_into().unwrap(),
+    }
+  }
+
+  pub fn comp_mnemonic(group: Group, a: Bit, comp: [Bit; 6]) -> Option<String> {
+    let (_, mnemonic) = group.table().iter().find(|(bits, _)| *bits == comp)?;
+    Some(if a == 1 { mnemonic.replace('A', "M") } else { mnemonic.to_string() })
+  }
+}
+
+// shifter operation of a shift instruction (c1 left, c3 rotate, c4 logical)
+pub fn shift_op(comp: [Bit; 6]) -> Shift {
+  match (comp[0], comp[2], comp[3]) {
+    (1, 1, _) => Shift::Rol,
+    (_, 1, _) => Shift::Ror,
+    (1, _, _) => Shift::Shl,
+    (_, _, 1) => Shift::Shr,
+    _ => Shift::Sar,
+  }
+}
+
+// extended ALU operation of a hack-ext instruction, None for the stock ALU
+pub fn ext_op(group: Group, comp: [Bit; 6]) -> Option<ExtOp> {
+  match group {
+    Group::Alu => None,
+    Group::Shift => Some(ExtOp::Shift(shift_op(comp))),
+    Group::MulDiv => Some(match (comp[0], comp[1]) {
+      (1, _) => ExtOp::Rem,
+      (_, 1) => ExtOp::Div,
+      _ => ExtOp::Mul,
+    }),
+    Group::Flag => Some(match (comp[0], comp[1]) {
+      (0, 0) => ExtOp::AddCarry,
+      (0, _) => ExtOp::AddOverflow,
+      (_, 0) => ExtOp::SubBorrow,
+      _ => ExtOp::SubOverflow,
+    }),
+  }
+}
+
+fn bits_index(bits: [Bit; 3]) -> usize {
+  (bits[0] as usize) << 2 | (bits[1] as usize) << 1 | bits[2] as usize
+}
+
+impl fmt::Display for Instruction {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match *self {
+      Instruction::A(value) => write!(f, "@{}", value),
+      Instruction::C { group, a, comp, dest, jump } => {
+        let dest = DEST_TABLE[bits_index(dest)];
+        let jump = JUMP_TABLE[bits_index(jump)];
+        if !dest.is_empty() {
+          write!(f, "{}=", dest)?;
+        }
+        match Self::comp_mnemonic(group, a, comp) {
+          Some(comp) => write!(f, "{}", comp)?,
+          // control bits outside the documented table still drive the ALU
+          None if group == Group::Alu => write!(f, "?{}{}", a, comp.map(|bit| bit.to_string()).join(""))?,
+          None => {
+            let [x, y] = group.prefix();
+            write!(f, "?{}{}{}{}", x, y, a, comp.map(|bit| bit.to_string()).join(""))?
+          }
+        }
+        if !jump.is_empty() {
+          write!(f, ";{}", jump)?;
+        }
+        Ok(())
+      }
+    }
+  }
+}
@@ -1,4 +1,27 @@
+pub mod adders;
 pub mod alu;
+pub mod arith;
+pub mod asm;
+pub mod batch;
+pub mod bdd;
+pub mod computer;
 pub mod cpu;
+pub mod debugger;
+pub mod dot;
+pub mod fault;
 pub mod gates;
+pub mod hazard;
+pub mod history;
+pub mod instruction;
 pub mod mem;
+pub mod minimise;
+pub mod netlist;
+pub mod power;
+pub mod shifter;
+pub mod snapshot;
+pub mod techmap;
+pub mod timing;
+pub mod trace;
+pub mod vcd;
+pub mod verify;
+pub mod verilog;
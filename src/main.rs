@@ -1,6 +1,223 @@
-mod alu;
-mod cpu;
-mod gates;
-mod mem;
+use std::{env, fs, fs::File, io, path::Path, process};
 
-fn main() {}
+use nand_computer::{
+  adders::{self, Adder},
+  alu,
+  asm::SymbolTable,
+  batch, bdd,
+  computer::Computer,
+  debugger, dot, fault, hazard,
+  instruction::Isa,
+  minimise, power, techmap, timing, vcd, verify, verilog,
+};
+
+const USAGE: &str = "usage:
+  nand-computer [--isa hack|hack-ext] debug <program.hack|program.asm>
+  nand-computer [--isa hack|hack-ext] vcd <program.hack|program.asm> <output.vcd> <cycles>
+  nand-computer adders
+  nand-computer verify [seed]
+  nand-computer equiv <adder> <adder>
+  nand-computer faults alu|ram8
+  nand-computer timing [period]
+  nand-computer hazards mux|alu
+  nand-computer [--isa hack|hack-ext] power <program.hack|program.asm> <cycles>
+  nand-computer verilog <output.v> [--behavioural-memory]
+  nand-computer dot <chip> [depth|nands]
+  nand-computer minimise [table.txt]
+  nand-computer techmap
+  nand-computer [--isa hack|hack-ext] batch <cycles> <program.hack|program.asm>...";
+
+fn main() {
+  let mut args: Vec<String> = env::args().collect();
+  let isa = match isa_option(&mut args) {
+    Ok(isa) => isa,
+    Err(error) => {
+      eprintln!("error: {}\n{}", error, USAGE);
+      process::exit(2);
+    }
+  };
+  let result = match (args.get(1).map(String::as_str), args.len()) {
+    (Some("debug"), 3) => debug(Path::new(&args[2]), isa),
+    (Some("vcd"), 5) => waveform(Path::new(&args[2]), Path::new(&args[3]), &args[4], isa),
+    (Some("adders"), 2) => {
+      print!("{}", adders::report_table());
+      Ok(())
+    }
+    (Some("verify"), 2 | 3) => verify_gates(args.get(2).map(String::as_str)),
+    (Some("equiv"), 4) => equiv(&args[2], &args[3]),
+    (Some("faults"), 3) => faults(&args[2]),
+    (Some("timing"), 2 | 3) => timing_report(args.get(2).map(String::as_str)),
+    (Some("hazards"), 3) => hazards(&args[2]),
+    (Some("power"), 4) => activity(Path::new(&args[2]), &args[3], isa),
+    (Some("techmap"), 2) => {
+      print!("{}", techmap::report_table(&techmap::reports()));
+      Ok(())
+    }
+    (Some("minimise"), 2 | 3) => minimise_table(args.get(2).map(Path::new)),
+    (Some("dot"), 3 | 4) => schematic(&args[2], args.get(3).map(String::as_str)),
+    (Some("batch"), 4..) => batch_run(&args[2], &args[3..], isa),
+    (Some("verilog"), 3 | 4) => export_verilog(Path::new(&args[2]), args.get(3).map(String::as_str)),
+    _ => {
+      eprintln!("{}", USAGE);
+      process::exit(2);
+    }
+  };
+  if let Err(error) = result {
+    eprintln!("error: {}", error);
+    process::exit(1);
+  }
+}
+
+// removes `--isa <name>` from the arguments
+fn isa_option(args: &mut Vec<String>) -> Result<Isa, String> {
+  let Some(pos) = args.iter().position(|arg| arg == "--isa") else {
+    return Ok(Isa::Hack);
+  };
+  let name = args.get(pos + 1).ok_or("--isa needs an instruction set")?.clone();
+  args.drain(pos..pos + 2);
+  name.parse()
+}
+
+fn load(path: &Path, isa: Isa) -> Result<(Computer, SymbolTable), String> {
+  let (program, symbols) = debugger::load_program(path, isa)?;
+  let mut computer = Computer::new(program);
+  computer.set_isa(isa);
+  Ok((computer, symbols))
+}
+
+fn debug(path: &Path, isa: Isa) -> Result<(), String> {
+  let (computer, symbols) = load(path, isa)?;
+  let mut debugger = debugger::Debugger::new(computer, symbols);
+  debugger.run(io::stdin().lock(), io::stdout()).map_err(|error| error.to_string())
+}
+
+fn waveform(path: &Path, output: &Path, cycles: &str, isa: Isa) -> Result<(), String> {
+  let cycles = cycles.parse().map_err(|_| format!("'{}' is not a cycle count", cycles))?;
+  let (mut computer, _) = load(path, isa)?;
+  let file = File::create(output).map_err(|error| format!("{}: {}", output.display(), error))?;
+  vcd::record(&mut computer, cycles, io::BufWriter::new(file)).map_err(|error| error.to_string())?;
+  Ok(())
+}
+
+fn verify_gates(seed: Option<&str>) -> Result<(), String> {
+  let seed = match seed {
+    Some(seed) => seed.parse().map_err(|_| format!("'{}' is not a seed", seed))?,
+    None => verify::DEFAULT_SEED,
+  };
+  let report = verify::suite(verify::DEFAULT_CASES, seed);
+  println!("{}", report);
+  if report.ok() {
+    Ok(())
+  } else {
+    Err(format!("{} checks failed (seed {})", report.failures.len(), seed))
+  }
+}
+
+// the two adders, then the ALU built on each
+fn equiv(left: &str, right: &str) -> Result<(), String> {
+  let (left, right): (Adder, Adder) = (left.parse()?, right.parse()?);
+  let adders = bdd::equivalent(&adders::netlist(left), &adders::netlist(right))?;
+  let alus = bdd::equivalent(&alu::netlist(left), &alu::netlist(right))?;
+  println!("adder: {}\nalu:   {}", adders, alus);
+  Ok(())
+}
+
+// fault coverage of the built-in test vectors
+fn faults(chip: &str) -> Result<(), String> {
+  match chip {
+    "alu" => println!("{}", fault::alu_coverage(Adder::Ripple)),
+    "ram8" => println!("{}", fault::ram8_coverage(&fault::ram8_tests(), &fault::ram8_faults())),
+    _ => return Err(format!("no fault tests for '{}'", chip)),
+  }
+  Ok(())
+}
+
+fn timing_report(period: Option<&str>) -> Result<(), String> {
+  let mut clock = timing::DEFAULT_CLOCK;
+  if let Some(period) = period {
+    clock.period = period.parse().map_err(|_| format!("'{}' is not a clock period", period))?;
+  }
+  print!("{}", timing::report_table(&timing::reports(&timing::Delays::default(), clock), clock));
+  Ok(())
+}
+
+// hazards of every single-input change
+fn hazards(chip: &str) -> Result<(), String> {
+  let delays = timing::Delays::default();
+  let hazards = match chip {
+    "mux" => hazard::mux_hazards(&delays),
+    "alu" => hazard::alu_hazards(Adder::Ripple, &delays),
+    _ => return Err(format!("no hazard scan for '{}'", chip)),
+  };
+  for hazard in &hazards {
+    println!("{}", hazard);
+  }
+  println!("{} hazards", hazards.len());
+  Ok(())
+}
+
+// toggle counts and heatmap of a run
+fn activity(path: &Path, cycles: &str, isa: Isa) -> Result<(), String> {
+  let cycles = cycles.parse().map_err(|_| format!("'{}' is not a cycle count", cycles))?;
+  let (mut computer, _) = load(path, isa)?;
+  let mut activity = power::Activity::new(Adder::Ripple);
+  activity.run(&mut computer, cycles);
+  println!("{}\n\n{}", activity, activity.heatmap());
+  Ok(())
+}
+
+// the whole computer as structural verilog
+fn export_verilog(path: &Path, option: Option<&str>) -> Result<(), String> {
+  let behavioural_memory = match option {
+    None => false,
+    Some("--behavioural-memory") => true,
+    Some(option) => return Err(format!("unknown option '{}'", option)),
+  };
+  let source = verilog::export(verilog::Options { behavioural_memory });
+  fs::write(path, &source).map_err(|error| format!("could not write {}: {}", path.display(), error))?;
+  println!("wrote {} lines to {}", source.lines().count(), path.display());
+  Ok(())
+}
+
+// a chip of the verilog export as graphviz, its parts expanded `depth` levels
+// down (0 by default) or flattened to NANDs
+fn schematic(chip: &str, view: Option<&str>) -> Result<(), String> {
+  let design = verilog::parse(&verilog::export(verilog::Options::default()))?;
+  let dot = match view {
+    Some("nands") => dot::netlist(chip, &design.netlist(chip)?),
+    Some(depth) => {
+      let depth = depth.parse().map_err(|_| format!("'{}' is not a depth", depth))?;
+      dot::hierarchy(&design, chip, depth)?
+    }
+    None => dot::hierarchy(&design, chip, 0)?,
+  };
+  print!("{}", dot);
+  Ok(())
+}
+
+// the gates against their minimised NAND networks, or one truth table
+fn minimise_table(path: Option<&Path>) -> Result<(), String> {
+  let Some(path) = path else {
+    print!("{}", minimise::comparison_table(&minimise::compare_gates(8)));
+    return Ok(());
+  };
+  let text = fs::read_to_string(path).map_err(|error| format!("could not read {}: {}", path.display(), error))?;
+  let table = minimise::TruthTable::parse(&text)?;
+  print!("{}", minimise::equations(&table));
+  println!("{} nands", minimise::synthesise(&table).nand_count());
+  Ok(())
+}
+
+// every program for `cycles` cycles, one machine per worker thread
+fn batch_run(cycles: &str, paths: &[String], isa: Isa) -> Result<(), String> {
+  let cycles = cycles.parse().map_err(|_| format!("'{}' is not a cycle count", cycles))?;
+  let mut jobs = Vec::new();
+  for path in paths {
+    let (program, _) = debugger::load_program(Path::new(path), isa)?;
+    jobs.push(batch::Job { isa, ..batch::Job::new(program, cycles) });
+  }
+  for (path, outcome) in paths.iter().zip(batch::run(&jobs, batch::threads())) {
+    println!("{}: {}", path, outcome);
+  }
+  Ok(())
+}
@@ -0,0 +1,157 @@
+#![allow(dead_code)]
+
+use std::slice;
+
+use crate::gates::{self, Bit};
+
+/*
+ MASTER-SLAVE FLIP-FLOP
+
+ SR latch: two cross-coupled NANDs, set_n / reset_n are active low
+
+   q   = nand(set_n, q_n)
+   q_n = nand(reset_n, q)
+
+ D latch: an SR latch behind two more NANDs, transparent while enable is 1
+
+   set_n   = nand(d, enable)
+   reset_n = nand(!d, enable)
+
+ DFF: a master D latch enabled by clk and a slave D latch enabled by !clk,
+ fed by the master. one tick is a full clock period:
+
+   clk = 1  master follows the input, slave holds
+   clk = 0  master holds, slave copies the master to the output
+
+ so the output changes once per tick, to that tick's input, like the
+ stored-byte DFF of register.rs.
+*/
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SrLatch {
+  q: Bit,
+  q_n: Bit,
+}
+
+impl Default for SrLatch {
+  fn default() -> Self {
+    Self::new(0)
+  }
+}
+
+impl SrLatch {
+  pub fn new(q: Bit) -> Self {
+    Self { q, q_n: gates::not(q) }
+  }
+
+  // feeds the NANDs their own outputs until they settle (two rounds at most,
+  // the D latch never drives set_n and reset_n low together)
+  pub fn update(&mut self, set_n: Bit, reset_n: Bit) {
+    loop {
+      let q = gates::nand(set_n, self.q_n);
+      let q_n = gates::nand(reset_n, q);
+      if (q, q_n) == (self.q, self.q_n) {
+        return;
+      }
+      (self.q, self.q_n) = (q, q_n);
+    }
+  }
+
+  pub fn q(&self) -> Bit {
+    self.q
+  }
+
+  pub fn q_n(&self) -> Bit {
+    self.q_n
+  }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DLatch {
+  latch: SrLatch,
+}
+
+impl DLatch {
+  pub fn new(q: Bit) -> Self {
+    Self { latch: SrLatch::new(q) }
+  }
+
+  pub fn update(&mut self, d: Bit, enable: Bit) {
+    let set_n = gates::nand(d, enable);
+    let reset_n = gates::nand(gates::nand(d, d), enable);
+    self.latch.update(set_n, reset_n);
+  }
+
+  pub fn q(&self) -> Bit {
+    self.latch.q()
+  }
+
+  pub fn latch(&self) -> &SrLatch {
+    &self.latch
+  }
+}
+
+// the four latch outputs packed in one byte (bit 0 master q, 1 master q_n,
+// 2 slave q, 3 slave q_n), the same size as the stored DFF so RAM16K fits
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MasterSlaveDFF {
+  nodes: u8,
+}
+
+impl Default for MasterSlaveDFF {
+  fn default() -> Self {
+    Self::new(0)
+  }
+}
+
+impl MasterSlaveDFF {
+  pub fn new(state: u8) -> Self {
+    let mut dff = Self { nodes: 0 };
+    dff.set(DLatch::new(state), DLatch::new(state));
+    dff
+  }
+
+  // one clock phase
+  pub fn clock(&mut self, input: u8, clk: Bit) {
+    let (mut master, mut slave) = (self.master(), self.slave());
+    master.update(input, clk);
+    slave.update(master.q(), gates::not(clk));
+    self.set(master, slave);
+  }
+
+  pub fn tick(&mut self, input: u8) {
+    self.clock(input, 1);
+    self.clock(input, 0);
+  }
+
+  pub fn output(&self) -> u8 {
+    self.slave().q()
+  }
+
+  pub fn master(&self) -> DLatch {
+    self.unpack(0)
+  }
+
+  pub fn slave(&self) -> DLatch {
+    self.unpack(2)
+  }
+
+  fn unpack(&self, shift: u8) -> DLatch {
+    let latch = SrLatch { q: (self.nodes >> shift) & 1, q_n: (self.nodes >> (shift + 1)) & 1 };
+    DLatch { latch }
+  }
+
+  fn set(&mut self, master: DLatch, slave: DLatch) {
+    let pack = |latch: DLatch| latch.latch.q | latch.latch.q_n << 1;
+    self.nodes = pack(master) | pack(slave) << 2;
+  }
+
+  // snapshots: same format as the stored DFF, one entry (the output)
+  pub fn save(&self, bits: &mut Vec<u8>) {
+    bits.push(self.output());
+  }
+
+  pub fn restore(&mut self, bits: &mut slice::Iter<u8>) {
+    self.tick(*bits.next().expect("error: snapshot is missing DFF state"));
+  }
+}
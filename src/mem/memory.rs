@@ -0,0 +1,68 @@
+#![allow(dead_code)]
+use super::{ram16k::RAM16K, register::Register, screen::Screen};
+use crate::gates::{self, Bit16};
+
+/*
+ DATA MEMORY MAP
+
+ address         | chip
+ ----------------+----------------------------
+ 0     .. 16383  | RAM16K
+ 16384 .. 24575  | Screen (8K)
+ 24576           | Keyboard (read-only)
+*/
+#[derive(Clone)]
+pub struct Memory {
+  // boxed: the RAM hierarchy is too large to be moved around on the stack
+  ram: Box<RAM16K>,
+  screen: Box<Screen>,
+  keyboard: Register,
+}
+
+impl Default for Memory {
+  fn default() -> Self {
+    Self::new(Box::default(), Box::default(), Register::new())
+  }
+}
+
+impl Memory {
+  pub fn new(ram: Box<RAM16K>, screen: Box<Screen>, keyboard: Register) -> Self {
+    Self { ram, screen, keyboard }
+  }
+
+  pub fn tick(&mut self, address: [u8; 15], load: u8, input: Bit16) -> Bit16 {
+    let (ram_load, io_load) = gates::dmux(load, address[0]);
+    // the keyboard is never written by the cpu, only `screen_load` is used
+    let (screen_load, _) = gates::dmux(io_load, address[1]);
+
+    let ram_out = self.ram.tick(address[1..15].try_into().unwrap(), ram_load, input);
+    let screen_out = self.screen.tick(address[2..15].try_into().unwrap(), screen_load, input);
+
+    gates::mux16(ram_out, gates::mux16(screen_out, self.keyboard.output(), address[1]), address[0])
+  }
+
+  // the same results for any count, see `RAM16K::set_threads`
+  pub fn set_ram_threads(&mut self, threads: usize) {
+    self.ram.set_threads(threads);
+  }
+
+  pub fn ram_threads(&self) -> usize {
+    self.ram.threads()
+  }
+
+  pub fn set_key(&mut self, code: Bit16) {
+    self.keyboard.tick(code, 1);
+  }
+
+  pub fn ram(&self) -> &RAM16K {
+    &self.ram
+  }
+
+  pub fn screen(&self) -> &Screen {
+    &self.screen
+  }
+
+  pub fn keyboard(&self) -> &Register {
+    &self.keyboard
+  }
+}
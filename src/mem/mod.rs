@@ -1,6 +1,11 @@
+pub mod latch;
+pub mod memory;
+pub mod pc;
 pub mod ram16k;
 pub mod ram4k;
 pub mod ram512;
 pub mod ram64;
 pub mod ram8;
 pub mod register;
+pub mod rom32k;
+pub mod screen;
@@ -0,0 +1,47 @@
+#![allow(dead_code)]
+use std::slice;
+
+use super::register::Register;
+use crate::gates::{self, Bit, Bit16};
+
+/*
+ PROGRAM COUNTER
+
+ reset | load | inc | out(t+1)
+ ------+------+-----+-----------
+   1   |  x   |  x  | 0
+   0   |  1   |  x  | input
+   0   |  0   |  1  | out(t) + 1
+   0   |  0   |  0  | out(t)
+*/
+#[derive(Clone, Copy, Default)]
+pub struct PC {
+  register: Register,
+}
+
+impl PC {
+  pub fn new(register: Register) -> Self {
+    Self { register }
+  }
+
+  pub fn tick(&mut self, input: Bit16, load: Bit, inc: Bit, reset: Bit) -> Bit16 {
+    let current = self.register.output();
+    let next = gates::mux16(current, gates::inc_16(current), inc);
+    let next = gates::mux16(next, input, load);
+    let next = gates::mux16(next, [0; 16], reset);
+    self.register.tick(next, 1);
+    self.register.output()
+  }
+
+  pub fn output(&self) -> Bit16 {
+    self.register.output()
+  }
+
+  pub fn save(&self, bits: &mut Vec<Bit>) {
+    self.register.save(bits);
+  }
+
+  pub fn restore(&mut self, bits: &mut slice::Iter<Bit>) {
+    self.register.restore(bits);
+  }
+}
@@ -1,21 +1,82 @@
 #![allow(dead_code)]
+use std::hint;
+use std::slice;
+use std::sync::atomic::{AtomicBool, AtomicU16, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
 use super::ram4k::RAM4K;
 use crate::gates::{self, Bit16};
 
-#[derive(Clone, Copy)]
+/*
+ PARALLEL TICKS
+
+ with `set_threads(n)` the four RAM4Ks are shared by the calling thread and
+ n - 1 persistent workers, RAM4K i ticks on thread i % n:
+
+ step | calling thread                 | worker
+ -----+--------------------------------+-------------------------------
+ 1    | publish the request, wake them | spin (then park) on `generation`
+ 2    | tick its own RAM4Ks            | tick its RAM4Ks, count `done`
+ 3    | wait for `done`, mux the outs  | back to 1
+
+ the request (12 address bits, 4 loads, 16 input bits) and the outputs go
+ through atomics, so a tick costs no allocation or thread spawn, only the
+ handoff. every RAM4K still ticks once with the same inputs, the results
+ are the ones of `threads = 1`.
+*/
+
+// spins before a waiting worker parks, a few microseconds
+const SPINS: u32 = 1 << 12;
+
+struct Shared {
+  // boxed one by one: four RAM4Ks at once overflow a thread's stack
+  chips: [Mutex<Box<RAM4K>>; 4],
+  // bumped once per parallel tick
+  generation: AtomicU64,
+  request: AtomicU64,
+  outs: [AtomicU16; 4],
+  done: AtomicUsize,
+  stop: AtomicBool,
+}
+
 pub struct RAM16K {
-  ram4ks: [RAM4K; 4],
+  shared: Arc<Shared>,
+  workers: Vec<JoinHandle<()>>,
 }
 
 impl Default for RAM16K {
   fn default() -> Self {
-    Self::new([RAM4K::default(); 4])
+    Self::new(std::array::from_fn(|_| Box::default()))
+  }
+}
+
+impl Clone for RAM16K {
+  // the copy gets the same number of threads, with workers of its own
+  fn clone(&self) -> Self {
+    let mut ram = Self::new(self.chips());
+    ram.set_threads(self.threads());
+    ram
+  }
+}
+
+impl Drop for RAM16K {
+  fn drop(&mut self) {
+    self.stop_workers();
   }
 }
 
 impl RAM16K {
-  pub fn new(ram4ks: [RAM4K; 4]) -> Self {
-    Self { ram4ks }
+  pub fn new(ram4ks: [Box<RAM4K>; 4]) -> Self {
+    let shared = Shared {
+      chips: ram4ks.map(Mutex::new),
+      generation: AtomicU64::new(0),
+      request: AtomicU64::new(0),
+      outs: Default::default(),
+      done: AtomicUsize::new(0),
+      stop: AtomicBool::new(false),
+    };
+    Self { shared: Arc::new(shared), workers: Vec::new() }
   }
 
   pub fn tick(&mut self, address: [u8; 14], load: u8, input: Bit16) -> Bit16 {
@@ -23,17 +84,131 @@ impl RAM16K {
 
     let (load0, load1, load2, load3) = gates::dmux4way(load, upper_addr);
 
-    self.ram4ks[0].tick(lower_addr, load0, input);
-    self.ram4ks[1].tick(lower_addr, load1, input);
-    self.ram4ks[2].tick(lower_addr, load2, input);
-    self.ram4ks[3].tick(lower_addr, load3, input);
-
+    if !self.workers.is_empty() {
+      return self.tick_parallel(lower_addr, [load0, load1, load2, load3], input, upper_addr);
+    }
+    let chips = &self.shared.chips;
     gates::mux4way16(
-      self.ram4ks[0].tick(lower_addr, 0, input),
-      self.ram4ks[1].tick(lower_addr, 0, input),
-      self.ram4ks[2].tick(lower_addr, 0, input),
-      self.ram4ks[3].tick(lower_addr, 0, input),
+      chips[0].lock().unwrap().tick(lower_addr, load0, input),
+      chips[1].lock().unwrap().tick(lower_addr, load1, input),
+      chips[2].lock().unwrap().tick(lower_addr, load2, input),
+      chips[3].lock().unwrap().tick(lower_addr, load3, input),
       upper_addr,
     )
   }
+
+  fn tick_parallel(&mut self, lower_addr: [u8; 12], loads: [u8; 4], input: Bit16, upper_addr: [u8; 2]) -> Bit16 {
+    let shared = &self.shared;
+    shared.request.store(encode(lower_addr, loads, input), Ordering::Relaxed);
+    shared.done.store(0, Ordering::Relaxed);
+    shared.generation.fetch_add(1, Ordering::Release);
+    self.workers.iter().for_each(|worker| worker.thread().unpark());
+
+    let threads = self.threads();
+    for chip in (0..4).step_by(threads) {
+      let out = shared.chips[chip].lock().unwrap().tick(lower_addr, loads[chip], input);
+      shared.outs[chip].store(gates::to_u16(out), Ordering::Relaxed);
+    }
+
+    let mut spins = 0;
+    while shared.done.load(Ordering::Acquire) < self.workers.len() {
+      if spins < SPINS {
+        spins += 1;
+        hint::spin_loop();
+      } else {
+        assert!(!self.workers.iter().any(JoinHandle::is_finished), "error: RAM4K worker panicked");
+        thread::yield_now();
+      }
+    }
+    let outs = shared.outs.each_ref().map(|out| gates::from_u16(out.load(Ordering::Relaxed)));
+    gates::mux4way16(outs[0], outs[1], outs[2], outs[3], upper_addr)
+  }
+
+  // threads that tick the RAM4Ks, 1 (no workers) to 4
+  pub fn set_threads(&mut self, threads: usize) {
+    let threads = threads.clamp(1, 4);
+    if threads == self.threads() {
+      return;
+    }
+    self.stop_workers();
+    self.shared.stop.store(false, Ordering::Relaxed);
+    let seen = self.shared.generation.load(Ordering::Relaxed);
+    self.workers = (1..threads)
+      .map(|first| {
+        let shared = Arc::clone(&self.shared);
+        thread::spawn(move || work(&shared, seen, first, threads))
+      })
+      .collect();
+  }
+
+  pub fn threads(&self) -> usize {
+    self.workers.len() + 1
+  }
+
+  fn stop_workers(&mut self) {
+    if self.workers.is_empty() {
+      return;
+    }
+    self.shared.stop.store(true, Ordering::Relaxed);
+    self.shared.generation.fetch_add(1, Ordering::Release);
+    for worker in self.workers.drain(..) {
+      worker.thread().unpark();
+      worker.join().expect("error: RAM4K worker panicked");
+    }
+  }
+
+  fn chips(&self) -> [Box<RAM4K>; 4] {
+    self.shared.chips.each_ref().map(|chip| chip.lock().unwrap().clone())
+  }
+
+  pub fn save(&self, bits: &mut Vec<u8>) {
+    self.shared.chips.iter().for_each(|chip| chip.lock().unwrap().save(bits));
+  }
+
+  pub fn restore(&mut self, bits: &mut slice::Iter<u8>) {
+    self.shared.chips.iter().for_each(|chip| chip.lock().unwrap().restore(bits));
+  }
+}
+
+// a worker ticks RAM4Ks first, first + threads, .. of every request after `seen`
+fn work(shared: &Shared, mut seen: u64, first: usize, threads: usize) {
+  loop {
+    let mut spins = 0;
+    let generation = loop {
+      let generation = shared.generation.load(Ordering::Acquire);
+      if generation != seen {
+        break generation;
+      }
+      if spins < SPINS {
+        spins += 1;
+        hint::spin_loop();
+      } else {
+        thread::park();
+      }
+    };
+    seen = generation;
+    if shared.stop.load(Ordering::Relaxed) {
+      return;
+    }
+
+    let (lower_addr, loads, input) = decode(shared.request.load(Ordering::Relaxed));
+    for chip in (first..4).step_by(threads) {
+      let out = shared.chips[chip].lock().unwrap().tick(lower_addr, loads[chip], input);
+      shared.outs[chip].store(gates::to_u16(out), Ordering::Relaxed);
+    }
+    shared.done.fetch_add(1, Ordering::Release);
+  }
+}
+
+// bits 0..16 input, 16..20 loads, 20..32 address
+fn encode(lower_addr: [u8; 12], loads: [u8; 4], input: Bit16) -> u64 {
+  let address = lower_addr.iter().fold(0, |word, &bit| word << 1 | bit as u64);
+  let loads = loads.iter().fold(0, |word, &bit| word << 1 | bit as u64);
+  address << 20 | loads << 16 | gates::to_u16(input) as u64
+}
+
+fn decode(request: u64) -> ([u8; 12], [u8; 4], Bit16) {
+  let address = std::array::from_fn(|pos| (request >> (31 - pos) & 1) as u8);
+  let loads = std::array::from_fn(|pos| (request >> (19 - pos) & 1) as u8);
+  (address, loads, gates::from_u16(request as u16))
 }
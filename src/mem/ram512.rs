@@ -1,4 +1,6 @@
 #![allow(dead_code)]
+use std::slice;
+
 use super::ram64::RAM64;
 use crate::gates::{self, Bit16};
 
@@ -23,25 +25,24 @@ impl RAM512 {
 
     let (load0, load1, load2, load3, load4, load5, load6, load7) = gates::dmux8way(load, upper_addr);
 
-    self.ram64s[0].tick(lower_addr, load0, input);
-    self.ram64s[1].tick(lower_addr, load1, input);
-    self.ram64s[2].tick(lower_addr, load2, input);
-    self.ram64s[3].tick(lower_addr, load3, input);
-    self.ram64s[4].tick(lower_addr, load4, input);
-    self.ram64s[5].tick(lower_addr, load5, input);
-    self.ram64s[6].tick(lower_addr, load6, input);
-    self.ram64s[7].tick(lower_addr, load7, input);
-
     gates::mux8way16(
-      self.ram64s[0].tick(lower_addr, 0, input),
-      self.ram64s[1].tick(lower_addr, 0, input),
-      self.ram64s[2].tick(lower_addr, 0, input),
-      self.ram64s[3].tick(lower_addr, 0, input),
-      self.ram64s[4].tick(lower_addr, 0, input),
-      self.ram64s[5].tick(lower_addr, 0, input),
-      self.ram64s[6].tick(lower_addr, 0, input),
-      self.ram64s[7].tick(lower_addr, 0, input),
+      self.ram64s[0].tick(lower_addr, load0, input),
+      self.ram64s[1].tick(lower_addr, load1, input),
+      self.ram64s[2].tick(lower_addr, load2, input),
+      self.ram64s[3].tick(lower_addr, load3, input),
+      self.ram64s[4].tick(lower_addr, load4, input),
+      self.ram64s[5].tick(lower_addr, load5, input),
+      self.ram64s[6].tick(lower_addr, load6, input),
+      self.ram64s[7].tick(lower_addr, load7, input),
       upper_addr,
     )
   }
+
+  pub fn save(&self, bits: &mut Vec<u8>) {
+    self.ram64s.iter().for_each(|chip| chip.save(bits));
+  }
+
+  pub fn restore(&mut self, bits: &mut slice::Iter<u8>) {
+    self.ram64s.iter_mut().for_each(|chip| chip.restore(bits));
+  }
 }
@@ -1,4 +1,6 @@
 #![allow(dead_code)]
+use std::slice;
+
 use super::ram8::RAM8;
 use crate::gates::{self, Bit16};
 
@@ -23,25 +25,24 @@ impl RAM64 {
 
     let (load0, load1, load2, load3, load4, load5, load6, load7) = gates::dmux8way(load, upper_addr);
 
-    self.ram8s[0].tick(lower_addr, load0, input);
-    self.ram8s[1].tick(lower_addr, load1, input);
-    self.ram8s[2].tick(lower_addr, load2, input);
-    self.ram8s[3].tick(lower_addr, load3, input);
-    self.ram8s[4].tick(lower_addr, load4, input);
-    self.ram8s[5].tick(lower_addr, load5, input);
-    self.ram8s[6].tick(lower_addr, load6, input);
-    self.ram8s[7].tick(lower_addr, load7, input);
-
     gates::mux8way16(
-      self.ram8s[0].tick(lower_addr, 0, input),
-      self.ram8s[1].tick(lower_addr, 0, input),
-      self.ram8s[2].tick(lower_addr, 0, input),
-      self.ram8s[3].tick(lower_addr, 0, input),
-      self.ram8s[4].tick(lower_addr, 0, input),
-      self.ram8s[5].tick(lower_addr, 0, input),
-      self.ram8s[6].tick(lower_addr, 0, input),
-      self.ram8s[7].tick(lower_addr, 0, input),
+      self.ram8s[0].tick(lower_addr, load0, input),
+      self.ram8s[1].tick(lower_addr, load1, input),
+      self.ram8s[2].tick(lower_addr, load2, input),
+      self.ram8s[3].tick(lower_addr, load3, input),
+      self.ram8s[4].tick(lower_addr, load4, input),
+      self.ram8s[5].tick(lower_addr, load5, input),
+      self.ram8s[6].tick(lower_addr, load6, input),
+      self.ram8s[7].tick(lower_addr, load7, input),
       upper_addr,
     )
   }
+
+  pub fn save(&self, bits: &mut Vec<u8>) {
+    self.ram8s.iter().for_each(|chip| chip.save(bits));
+  }
+
+  pub fn restore(&mut self, bits: &mut slice::Iter<u8>) {
+    self.ram8s.iter_mut().for_each(|chip| chip.restore(bits));
+  }
 }
@@ -1,4 +1,6 @@
 #![allow(dead_code)]
+use std::slice;
+
 use crate::gates::{self, Bit16};
 
 use super::register::Register;
@@ -42,4 +44,12 @@ impl RAM8 {
       address,
     )
   }
+
+  pub fn save(&self, bits: &mut Vec<u8>) {
+    self.regs.iter().for_each(|chip| chip.save(bits));
+  }
+
+  pub fn restore(&mut self, bits: &mut slice::Iter<u8>) {
+    self.regs.iter_mut().for_each(|chip| chip.restore(bits));
+  }
 }
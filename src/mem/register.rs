@@ -1,19 +1,28 @@
 #![allow(dead_code)]
 
+use std::slice;
+
 use crate::gates::{self, Bit16};
 
+// the DFF used by `Bit`: a stored byte, or with `--features nand-dff` the
+// master-slave flip-flop of latch.rs
+#[cfg(not(feature = "nand-dff"))]
+pub type DFF = StoredDFF;
+#[cfg(feature = "nand-dff")]
+pub type DFF = super::latch::MasterSlaveDFF;
+
 #[derive(Clone, Copy)]
-pub struct DFF {
+pub struct StoredDFF {
   state: u8,
 }
 
-impl Default for DFF {
+impl Default for StoredDFF {
   fn default() -> Self {
     Self::new(0)
   }
 }
 
-impl DFF {
+impl StoredDFF {
   pub fn new(state: u8) -> Self {
     Self { state }
   }
@@ -25,6 +34,15 @@ impl DFF {
   pub fn output(&self) -> u8 {
     self.state
   }
+
+  // snapshots: one entry per DFF, in declaration order
+  pub fn save(&self, bits: &mut Vec<u8>) {
+    bits.push(self.output());
+  }
+
+  pub fn restore(&mut self, bits: &mut slice::Iter<u8>) {
+    self.tick(*bits.next().expect("error: snapshot is missing DFF state"));
+  }
 }
 
 // BIT
@@ -52,6 +70,14 @@ impl Bit {
   pub fn output(&self) -> u8 {
     self.dff.output()
   }
+
+  pub fn save(&self, bits: &mut Vec<u8>) {
+    self.dff.save(bits);
+  }
+
+  pub fn restore(&mut self, bits: &mut slice::Iter<u8>) {
+    self.dff.restore(bits);
+  }
 }
 
 #[derive(Clone, Copy)]
@@ -59,6 +85,12 @@ pub struct Register {
   bits: [Bit; 16],
 }
 
+impl Default for Register {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
 impl Register {
   pub fn new() -> Self {
     Self { bits: [Bit::default(); 16] }
@@ -73,4 +105,12 @@ impl Register {
   pub fn output(&self) -> [u8; 16] {
     self.bits.map(|bit| bit.output())
   }
+
+  pub fn save(&self, bits: &mut Vec<u8>) {
+    self.bits.iter().for_each(|bit| bit.save(bits));
+  }
+
+  pub fn restore(&mut self, bits: &mut slice::Iter<u8>) {
+    self.bits.iter_mut().for_each(|bit| bit.restore(bits));
+  }
 }
@@ -0,0 +1,52 @@
+#![allow(dead_code)]
+use crate::gates::{self, Bit16};
+
+// ROM32K is a builtin chip: read-only, 15-bit address, 16-bit words.
+// programs are loaded once, before the computer starts running.
+#[derive(Clone, Default)]
+pub struct ROM32K {
+  words: Vec<Bit16>,
+}
+
+impl ROM32K {
+  pub const SIZE: usize = 32 * 1024;
+
+  pub fn new(program: Vec<Bit16>) -> Self {
+    assert!(program.len() <= Self::SIZE, "error: program does not fit in ROM32K ({} words)", program.len());
+    Self { words: program }
+  }
+
+  pub fn read(&self, address: [u8; 15]) -> Bit16 {
+    let mut word = [0; 16];
+    word[1..].copy_from_slice(&address);
+    // words past the end of the program read as zero
+    self.words.get(gates::to_u16(word) as usize).copied().unwrap_or([0; 16])
+  }
+
+  pub fn words(&self) -> &[Bit16] {
+    &self.words
+  }
+}
+
+// parse the textual `.hack` format: one 16-character binary word per line.
+pub fn parse_hack(source: &str) -> Result<Vec<Bit16>, String> {
+  let mut program = Vec::new();
+  for (number, line) in source.lines().enumerate() {
+    let line = line.trim();
+    if line.is_empty() {
+      continue;
+    }
+    if line.len() != 16 || !line.chars().all(|c| c == '0' || c == '1') {
+      return Err(format!("line {}: expected 16 binary digits, found '{}'", number + 1, line));
+    }
+    let mut word = [0; 16];
+    for (pos, c) in line.chars().enumerate() {
+      word[pos] = if c == '1' { 1 } else { 0 };
+    }
+    program.push(word);
+  }
+  if program.len() > ROM32K::SIZE {
+    return Err(format!("program has {} words, ROM32K holds {}", program.len(), ROM32K::SIZE));
+  }
+  Ok(program)
+}
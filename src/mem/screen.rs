@@ -0,0 +1,43 @@
+#![allow(dead_code)]
+use std::slice;
+
+use super::ram4k::RAM4K;
+use crate::gates::{self, Bit16};
+
+// 8K memory map for the 512x256 black and white display (one bit per pixel).
+#[derive(Clone, Copy)]
+pub struct Screen {
+  ram4ks: [RAM4K; 2],
+}
+
+impl Default for Screen {
+  fn default() -> Self {
+    Self::new([RAM4K::default(); 2])
+  }
+}
+
+impl Screen {
+  pub fn new(ram4ks: [RAM4K; 2]) -> Self {
+    Self { ram4ks }
+  }
+
+  pub fn tick(&mut self, address: [u8; 13], load: u8, input: Bit16) -> Bit16 {
+    let (upper_addr, lower_addr) = (address[0], address[1..13].try_into().unwrap());
+
+    let (load0, load1) = gates::dmux(load, upper_addr);
+
+    gates::mux16(
+      self.ram4ks[0].tick(lower_addr, load0, input),
+      self.ram4ks[1].tick(lower_addr, load1, input),
+      upper_addr,
+    )
+  }
+
+  pub fn save(&self, bits: &mut Vec<u8>) {
+    self.ram4ks.iter().for_each(|chip| chip.save(bits));
+  }
+
+  pub fn restore(&mut self, bits: &mut slice::Iter<u8>) {
+    self.ram4ks.iter_mut().for_each(|chip| chip.restore(bits));
+  }
+}
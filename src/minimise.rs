@@ -0,0 +1,369 @@
+#![allow(dead_code)]
+
+use std::collections::{BTreeSet, HashMap};
+
+use crate::gates::Bit;
+use crate::netlist::{Logic, Netlist, Node};
+use crate::verilog;
+
+/*
+ LOGIC MINIMISATION
+
+ a truth table becomes a minimal sum of products, then a NAND network:
+
+ 1. Quine-McCluskey: cubes (a value and a mask of don't-care inputs) that
+    differ in one bit merge into a bigger cube, until nothing merges. the
+    cubes left over are the prime implicants
+ 2. cover: primes that are the only cover of some minterm are essential,
+    then (like espresso's heuristic, not an exact cover) the prime covering
+    the most minterms still open is taken until all are covered
+ 3. NAND-NAND: f = a&!b | c  ==  nand(nand(a, !b), !c). every term is a NAND
+    of its literals, the output a NAND of the terms. inverted inputs and
+    terms are shared between the outputs of a table
+
+ rows are numbered with input 0 as the most significant bit, like
+ `hazard::all_vectors`. don't-care rows come out as 0 or 1, whichever
+ makes bigger cubes.
+*/
+
+// a truth table of up to 16 inputs is enumerated row by row
+pub const MAX_INPUTS: usize = 16;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TruthTable {
+  pub inputs: Vec<String>,
+  pub outputs: Vec<String>,
+  // every row's outputs, None for don't care
+  pub rows: Vec<Vec<Option<Bit>>>,
+}
+
+impl TruthTable {
+  // every row of a small netlist
+  pub fn of(netlist: &Netlist) -> Result<Self, String> {
+    let inputs: Vec<String> = netlist
+      .inputs()
+      .iter()
+      .map(|&id| match netlist.node(id) {
+        Node::Input(name) => name.clone(),
+        _ => unreachable!(),
+      })
+      .collect();
+    if inputs.len() > MAX_INPUTS {
+      return Err(format!("{} inputs, at most {} fit in a truth table", inputs.len(), MAX_INPUTS));
+    }
+    let rows = (0..1usize << inputs.len())
+      .map(|row| netlist.eval(&bits(row, inputs.len())).into_iter().map(Some).collect())
+      .collect();
+    Ok(Self { inputs, outputs: netlist.outputs().iter().map(|(name, _)| name.clone()).collect(), rows })
+  }
+
+  // a header of input names, '|', output names, then rows in the same shape.
+  // '-' in an input column stands for both values, 'x' in an output for
+  // don't care; rows not listed are don't care too
+  //
+  //   zx nx | x
+  //   0  -  | 1
+  //   1  -  | 0
+  pub fn parse(text: &str) -> Result<Self, String> {
+    let mut lines = text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+    let (number, header) = lines.next().ok_or("the truth table is empty")?;
+    let (inputs, outputs) =
+      split_row(header).ok_or_else(|| format!("line {}: the header needs inputs | outputs", number + 1))?;
+    let (inputs, outputs): (Vec<String>, Vec<String>) =
+      (inputs.into_iter().map(String::from).collect(), outputs.into_iter().map(String::from).collect());
+    if inputs.len() > MAX_INPUTS {
+      return Err(format!("{} inputs, at most {} fit in a truth table", inputs.len(), MAX_INPUTS));
+    }
+
+    let mut rows = vec![vec![None; outputs.len()]; 1 << inputs.len()];
+    for (number, line) in lines {
+      let error = |message: &str| format!("line {}: {}", number + 1, message);
+      let (pattern, values) = split_row(line).ok_or_else(|| error("expected inputs | outputs"))?;
+      if pattern.len() != inputs.len() || values.len() != outputs.len() {
+        return Err(error(&format!("expected {} inputs and {} outputs", inputs.len(), outputs.len())));
+      }
+      let values: Vec<Option<Bit>> = values
+        .iter()
+        .map(|value| match *value {
+          "0" => Ok(Some(0)),
+          "1" => Ok(Some(1)),
+          "x" => Ok(None),
+          _ => Err(error(&format!("'{}' is not 0, 1 or x", value))),
+        })
+        .collect::<Result<_, _>>()?;
+      // every row the pattern matches
+      let mut matching = vec![0usize];
+      for value in &pattern {
+        let choices: &[usize] = match *value {
+          "0" => &[0],
+          "1" => &[1],
+          "-" => &[0, 1],
+          _ => return Err(error(&format!("'{}' is not 0, 1 or -", value))),
+        };
+        matching = matching.iter().flat_map(|row| choices.iter().map(move |bit| row << 1 | bit)).collect();
+      }
+      for row in matching {
+        for (out, value) in rows[row].iter_mut().zip(&values) {
+          if out.is_some_and(|old| Some(old) != *value) && value.is_some() {
+            return Err(error(&format!("row {} is given twice with different outputs", row)));
+          }
+          *out = out.or(*value);
+        }
+      }
+    }
+    Ok(Self { inputs, outputs, rows })
+  }
+
+  // rows where `output` is 1
+  pub fn on(&self, output: usize) -> Vec<u32> {
+    self.matching(output, Some(1))
+  }
+
+  pub fn dont_care(&self, output: usize) -> Vec<u32> {
+    self.matching(output, None)
+  }
+
+  fn matching(&self, output: usize, value: Option<Bit>) -> Vec<u32> {
+    (0..self.rows.len()).filter(|&row| self.rows[row][output] == value).map(|row| row as u32).collect()
+  }
+}
+
+fn split_row(line: &str) -> Option<(Vec<&str>, Vec<&str>)> {
+  let (inputs, outputs) = line.split_once('|')?;
+  Some((inputs.split_whitespace().collect(), outputs.split_whitespace().collect()))
+}
+
+// the inputs of a row, input 0 first
+fn bits(row: usize, inputs: usize) -> Vec<Bit> {
+  (0..inputs).map(|pos| ((row >> (inputs - 1 - pos)) & 1) as Bit).collect()
+}
+
+// a product term: the inputs outside `mask` must equal `value`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Cube {
+  pub value: u32,
+  pub mask: u32,
+}
+
+impl Cube {
+  pub fn minterm(row: u32) -> Self {
+    Self { value: row, mask: 0 }
+  }
+
+  pub fn covers(&self, row: u32) -> bool {
+    row & !self.mask == self.value
+  }
+
+  // (input, wanted value) of every literal, input 0 first
+  pub fn literals(&self, inputs: usize) -> Vec<(usize, Bit)> {
+    (0..inputs)
+      .filter(|pos| self.mask >> (inputs - 1 - pos) & 1 == 0)
+      .map(|pos| (pos, (self.value >> (inputs - 1 - pos) & 1) as Bit))
+      .collect()
+  }
+
+  // the cube covering both, when they differ in exactly one cared-for bit
+  fn merge(&self, other: &Cube) -> Option<Cube> {
+    let difference = self.value ^ other.value;
+    (self.mask == other.mask && difference.count_ones() == 1)
+      .then_some(Cube { value: self.value & !difference, mask: self.mask | difference })
+  }
+}
+
+pub fn prime_implicants(on: &[u32], dont_care: &[u32]) -> Vec<Cube> {
+  let mut cubes: BTreeSet<Cube> = on.iter().chain(dont_care).map(|&row| Cube::minterm(row)).collect();
+  let mut primes = BTreeSet::new();
+  while !cubes.is_empty() {
+    let list: Vec<Cube> = cubes.iter().copied().collect();
+    let mut merged = BTreeSet::new();
+    let mut used = BTreeSet::new();
+    for (pos, cube) in list.iter().enumerate() {
+      for other in &list[pos + 1..] {
+        if let Some(bigger) = cube.merge(other) {
+          merged.insert(bigger);
+          used.insert(*cube);
+          used.insert(*other);
+        }
+      }
+    }
+    primes.extend(list.into_iter().filter(|cube| !used.contains(cube)));
+    cubes = merged;
+  }
+  primes.into_iter().collect()
+}
+
+// a small set of prime implicants covering every row of `on`
+pub fn minimise(on: &[u32], dont_care: &[u32]) -> Vec<Cube> {
+  let primes = prime_implicants(on, dont_care);
+  let mut open: BTreeSet<u32> = on.iter().copied().collect();
+  let mut cover = Vec::new();
+
+  for &row in on {
+    let mut covering = primes.iter().filter(|prime| prime.covers(row));
+    if let (Some(&only), None) = (covering.next(), covering.next()) {
+      if !cover.contains(&only) {
+        cover.push(only);
+        open.retain(|&row| !only.covers(row));
+      }
+    }
+  }
+  while !open.is_empty() {
+    // most open rows, then fewest literals
+    let best = *primes
+      .iter()
+      .max_by_key(|prime| (open.iter().filter(|&&row| prime.covers(row)).count(), prime.mask.count_ones()))
+      .unwrap();
+    cover.push(best);
+    open.retain(|&row| !best.covers(row));
+  }
+  cover.sort();
+  cover
+}
+
+// "a & !b | c", "0" for no terms and "1" for the empty product
+pub fn expression(cover: &[Cube], inputs: &[String]) -> String {
+  if cover.is_empty() {
+    return "0".to_string();
+  }
+  let terms: Vec<String> = cover
+    .iter()
+    .map(|cube| {
+      let literals: Vec<String> = cube
+        .literals(inputs.len())
+        .into_iter()
+        .map(|(input, value)| format!("{}{}", if value == 0 { "!" } else { "" }, inputs[input]))
+        .collect();
+      if literals.is_empty() {
+        "1".to_string()
+      } else {
+        literals.join(" & ")
+      }
+    })
+    .collect();
+  terms.join(" | ")
+}
+
+// the NAND-NAND form of every cover, sharing inverted inputs and terms
+pub fn nand_network<L: Logic>(logic: &mut L, inputs: &[L::Signal], covers: &[Vec<Cube>]) -> Vec<L::Signal> {
+  let mut inverted: HashMap<usize, L::Signal> = HashMap::new();
+  let mut literal = |logic: &mut L, (input, value): (usize, Bit)| match value {
+    1 => inputs[input],
+    _ => *inverted.entry(input).or_insert_with(|| logic.not(inputs[input])),
+  };
+  let mut terms: HashMap<Cube, L::Signal> = HashMap::new();
+
+  let mut out = Vec::new();
+  for cover in covers {
+    let signal = match cover.as_slice() {
+      [] => logic.constant(0),
+      [cube] if cube.literals(inputs.len()).is_empty() => logic.constant(1),
+      // one product needs no output NAND
+      [cube] => {
+        let literals: Vec<L::Signal> = cube.literals(inputs.len()).into_iter().map(|lit| literal(logic, lit)).collect();
+        logic.and_n(&literals)
+      }
+      cubes => {
+        let mut inverted_terms = Vec::new();
+        for cube in cubes {
+          let signal = match terms.get(cube) {
+            Some(&signal) => signal,
+            None => {
+              let literals = cube.literals(inputs.len());
+              let signal = match literals.as_slice() {
+                // a NAND of one literal is the other polarity
+                [(input, value)] => literal(logic, (*input, 1 - value)),
+                _ => {
+                  let literals: Vec<L::Signal> = literals.into_iter().map(|lit| literal(logic, lit)).collect();
+                  nand_n(logic, &literals)
+                }
+              };
+              terms.insert(*cube, signal);
+              signal
+            }
+          };
+          inverted_terms.push(signal);
+        }
+        nand_n(logic, &inverted_terms)
+      }
+    };
+    out.push(signal);
+  }
+  out
+}
+
+fn nand_n<L: Logic>(logic: &mut L, signals: &[L::Signal]) -> L::Signal {
+  match signals {
+    [single] => logic.not(*single),
+    _ => {
+      let (left, right) = signals.split_at(signals.len() / 2);
+      let (left, right) = (logic.and_n(left), logic.and_n(right));
+      logic.nand(left, right)
+    }
+  }
+}
+
+// the minimised cover of every output
+pub fn covers(table: &TruthTable) -> Vec<Vec<Cube>> {
+  (0..table.outputs.len()).map(|output| minimise(&table.on(output), &table.dont_care(output))).collect()
+}
+
+// a netlist with the table's inputs and outputs
+pub fn synthesise(table: &TruthTable) -> Netlist {
+  let mut netlist = Netlist::new();
+  let inputs: Vec<_> = table.inputs.iter().map(|name| netlist.input(name)).collect();
+  let outputs = nand_network(&mut netlist, &inputs, &covers(table));
+  for (name, signal) in table.outputs.iter().zip(outputs) {
+    netlist.output(name, signal);
+  }
+  netlist
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Comparison {
+  pub chip: String,
+  pub inputs: usize,
+  // NANDs of the hand-written chip and of the minimised network
+  pub hand: usize,
+  pub minimised: usize,
+  pub terms: usize,
+}
+
+// every gate chip of the verilog export with at most `max_inputs` inputs
+pub fn compare_gates(max_inputs: usize) -> Vec<Comparison> {
+  let mut comparisons = Vec::new();
+  for (chip, netlist) in verilog::gate_chips() {
+    if netlist.inputs().len() > max_inputs {
+      continue;
+    }
+    let table = TruthTable::of(&netlist).expect("small chips fit in a truth table");
+    comparisons.push(Comparison {
+      chip: chip.to_string(),
+      inputs: netlist.inputs().len(),
+      hand: netlist.nand_count(),
+      minimised: synthesise(&table).nand_count(),
+      terms: covers(&table).iter().map(Vec::len).sum(),
+    });
+  }
+  comparisons
+}
+
+pub fn comparison_table(comparisons: &[Comparison]) -> String {
+  let mut out = format!("{:<10} | {:>6} | {:>4} | {:>9} | {:>5}\n", "chip", "inputs", "hand", "minimised", "terms");
+  out.push_str(&format!("{:-<10}-+-{:->6}-+-{:->4}-+-{:->9}-+-{:->5}\n", "", "", "", "", ""));
+  for comparison in comparisons {
+    out.push_str(&format!(
+      "{:<10} | {:>6} | {:>4} | {:>9} | {:>5}\n",
+      comparison.chip, comparison.inputs, comparison.hand, comparison.minimised, comparison.terms
+    ));
+  }
+  out
+}
+
+// the minimised expression of every output, one per line
+pub fn equations(table: &TruthTable) -> String {
+  let mut out = String::new();
+  for (name, cover) in table.outputs.iter().zip(covers(table)) {
+    out.push_str(&format!("{} = {}\n", name, expression(&cover, &table.inputs)));
+  }
+  out
+}
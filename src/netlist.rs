@@ -0,0 +1,337 @@
+#![allow(dead_code)]
+
+use crate::gates::{self, Bit};
+
+/*
+ NAND NETLISTS
+
+ circuits written against `Logic` only use nand (and constants), the other
+ gates are derived with the same formulas as gates.rs. the same code can then:
+
+   Eval     compute the output bits directly
+   Sliced   compute 64 circuits at once, one per bit (lane) of a u64
+   Netlist  record every NAND as a node, to count gates, measure depth or
+            evaluate the recorded circuit later
+
+ `Netlist::batch_eval` runs a recorded circuit bit-sliced the same way: pack
+ up to 64 input vectors with `pack` (word n holds input n of every vector,
+ vector k in lane k), evaluate once, `unpack` the output words.
+
+ node    | meaning
+ --------+------------------------------------------
+ Input   | named primary input
+ Const   | constant 0 / 1
+ Nand    | nand of two earlier nodes
+*/
+
+pub trait Logic {
+  type Signal: Copy;
+
+  fn nand(&mut self, a: Self::Signal, b: Self::Signal) -> Self::Signal;
+  fn constant(&mut self, bit: Bit) -> Self::Signal;
+
+  fn not(&mut self, a: Self::Signal) -> Self::Signal {
+    let one = self.constant(1);
+    self.nand(a, one)
+  }
+
+  fn and(&mut self, a: Self::Signal, b: Self::Signal) -> Self::Signal {
+    let nand = self.nand(a, b);
+    self.not(nand)
+  }
+
+  fn or(&mut self, a: Self::Signal, b: Self::Signal) -> Self::Signal {
+    let (not_a, not_b) = (self.not(a), self.not(b));
+    self.nand(not_a, not_b)
+  }
+
+  fn xor(&mut self, a: Self::Signal, b: Self::Signal) -> Self::Signal {
+    let nand = self.nand(a, b);
+    let (left, right) = (self.nand(a, nand), self.nand(b, nand));
+    self.nand(left, right)
+  }
+
+  // (a & !sel) | (b & sel)
+  fn mux(&mut self, a: Self::Signal, b: Self::Signal, sel: Self::Signal) -> Self::Signal {
+    let not_sel = self.not(sel);
+    let (left, right) = (self.and(a, not_sel), self.and(b, sel));
+    self.or(left, right)
+  }
+
+  // balanced trees, like gates::and_n / gates::or_n
+  fn and_n(&mut self, bits: &[Self::Signal]) -> Self::Signal {
+    match bits {
+      [] => self.constant(1),
+      [bit] => *bit,
+      _ => {
+        let (left, right) = bits.split_at(bits.len() / 2);
+        let (left, right) = (self.and_n(left), self.and_n(right));
+        self.and(left, right)
+      }
+    }
+  }
+
+  fn or_n(&mut self, bits: &[Self::Signal]) -> Self::Signal {
+    match bits {
+      [] => self.constant(0),
+      [bit] => *bit,
+      _ => {
+        let (left, right) = bits.split_at(bits.len() / 2);
+        let (left, right) = (self.or_n(left), self.or_n(right));
+        self.or(left, right)
+      }
+    }
+  }
+}
+
+// evaluates circuits on plain bits
+#[derive(Clone, Copy, Default)]
+pub struct Eval;
+
+impl Logic for Eval {
+  type Signal = Bit;
+
+  fn nand(&mut self, a: Bit, b: Bit) -> Bit {
+    gates::nand(a, b)
+  }
+
+  fn constant(&mut self, bit: Bit) -> Bit {
+    bit
+  }
+}
+
+// vectors per u64 word
+pub const LANES: usize = 64;
+
+// evaluates 64 circuits at once, lane k of every signal belongs to circuit k
+#[derive(Clone, Copy, Default)]
+pub struct Sliced;
+
+impl Logic for Sliced {
+  type Signal = u64;
+
+  fn nand(&mut self, a: u64, b: u64) -> u64 {
+    !(a & b)
+  }
+
+  fn constant(&mut self, bit: Bit) -> u64 {
+    lanes(bit)
+  }
+}
+
+// a bit copied to every lane
+pub fn lanes(bit: Bit) -> u64 {
+  0u64.wrapping_sub((bit & 1) as u64)
+}
+
+// the lanes in use by `count` vectors
+pub fn lane_mask(count: usize) -> u64 {
+  if count >= LANES {
+    !0
+  } else {
+    (1 << count) - 1
+  }
+}
+
+// up to 64 vectors of equal length into one word per position
+pub fn pack(vectors: &[Vec<Bit>]) -> Vec<u64> {
+  assert!(vectors.len() <= LANES, "error: {} vectors do not fit in {} lanes", vectors.len(), LANES);
+  let width = vectors.first().map_or(0, Vec::len);
+  let mut words = vec![0; width];
+  for (lane, vector) in vectors.iter().enumerate() {
+    for (word, &bit) in words.iter_mut().zip(vector) {
+      *word |= ((bit & 1) as u64) << lane;
+    }
+  }
+  words
+}
+
+// the first `count` vectors back out of the words
+pub fn unpack(words: &[u64], count: usize) -> Vec<Vec<Bit>> {
+  (0..count).map(|lane| words.iter().map(|word| ((word >> lane) & 1) as Bit).collect()).collect()
+}
+
+// up to 64 16-bit values as a sliced bus, MSB first like Bit16
+pub fn pack_16(values: &[u16]) -> [u64; 16] {
+  assert!(values.len() <= LANES, "error: {} values do not fit in {} lanes", values.len(), LANES);
+  let mut bus = [0; 16];
+  for (lane, value) in values.iter().enumerate() {
+    for (pos, word) in bus.iter_mut().enumerate() {
+      *word |= (((value >> (15 - pos)) & 1) as u64) << lane;
+    }
+  }
+  bus
+}
+
+pub fn unpack_16(bus: &[u64; 16], count: usize) -> Vec<u16> {
+  (0..count).map(|lane| bus.iter().fold(0, |value, word| value << 1 | ((word >> lane) & 1) as u16)).collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(pub usize);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Node {
+  Input(String),
+  Const(Bit),
+  Nand(NodeId, NodeId),
+}
+
+// records circuits as a graph of NAND nodes, nodes only point to earlier nodes
+#[derive(Clone, Default)]
+pub struct Netlist {
+  nodes: Vec<Node>,
+  inputs: Vec<NodeId>,
+  outputs: Vec<(String, NodeId)>,
+  // one shared node per constant value, every `not` reuses the 1
+  constants: [Option<NodeId>; 2],
+}
+
+impl Logic for Netlist {
+  type Signal = NodeId;
+
+  fn nand(&mut self, a: NodeId, b: NodeId) -> NodeId {
+    self.push(Node::Nand(a, b))
+  }
+
+  fn constant(&mut self, bit: Bit) -> NodeId {
+    let bit = bit & 1;
+    if let Some(id) = self.constants[bit as usize] {
+      return id;
+    }
+    let id = self.push(Node::Const(bit));
+    self.constants[bit as usize] = Some(id);
+    id
+  }
+}
+
+impl Netlist {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn input(&mut self, name: &str) -> NodeId {
+    let id = self.push(Node::Input(name.to_string()));
+    self.inputs.push(id);
+    id
+  }
+
+  // N inputs named name[0] .. name[N - 1], MSB first like Bit16
+  pub fn input_bus<const N: usize>(&mut self, name: &str) -> [NodeId; N] {
+    std::array::from_fn(|pos| self.input(&format!("{}[{}]", name, pos)))
+  }
+
+  pub fn output(&mut self, name: &str, id: NodeId) {
+    self.outputs.push((name.to_string(), id));
+  }
+
+  pub fn output_bus(&mut self, name: &str, ids: &[NodeId]) {
+    for (pos, &id) in ids.iter().enumerate() {
+      self.output(&format!("{}[{}]", name, pos), id);
+    }
+  }
+
+  pub fn nodes(&self) -> &[Node] {
+    &self.nodes
+  }
+
+  pub fn node(&self, id: NodeId) -> &Node {
+    &self.nodes[id.0]
+  }
+
+  pub fn inputs(&self) -> &[NodeId] {
+    &self.inputs
+  }
+
+  pub fn outputs(&self) -> &[(String, NodeId)] {
+    &self.outputs
+  }
+
+  pub fn nand_count(&self) -> usize {
+    self.nodes.iter().filter(|node| matches!(node, Node::Nand(..))).count()
+  }
+
+  // NAND levels of every node, inputs and constants are level 0
+  pub fn levels(&self) -> Vec<usize> {
+    let mut levels = vec![0; self.nodes.len()];
+    for (pos, node) in self.nodes.iter().enumerate() {
+      if let Node::Nand(a, b) = node {
+        levels[pos] = 1 + levels[a.0].max(levels[b.0]);
+      }
+    }
+    levels
+  }
+
+  // longest NAND path from an input to an output
+  pub fn depth(&self) -> usize {
+    let levels = self.levels();
+    self.outputs.iter().map(|(_, id)| levels[id.0]).max().unwrap_or(0)
+  }
+
+  // every node value for the given input bits (in `inputs()` order)
+  pub fn values(&self, inputs: &[Bit]) -> Vec<Bit> {
+    self.values_forced(inputs, &[])
+  }
+
+  // like `values`, the `forced` nodes keep the given bit whatever drives them
+  pub fn values_forced(&self, inputs: &[Bit], forced: &[(NodeId, Bit)]) -> Vec<Bit> {
+    assert_eq!(inputs.len(), self.inputs.len(), "error: netlist has {} inputs", self.inputs.len());
+    let mut values = vec![0; self.nodes.len()];
+    for (id, &bit) in self.inputs.iter().zip(inputs) {
+      values[id.0] = bit & 1;
+    }
+    for (pos, node) in self.nodes.iter().enumerate() {
+      match node {
+        Node::Input(_) => {}
+        Node::Const(bit) => values[pos] = *bit,
+        Node::Nand(a, b) => values[pos] = gates::nand(values[a.0], values[b.0]),
+      }
+      if let Some(&(_, bit)) = forced.iter().find(|(id, _)| id.0 == pos) {
+        values[pos] = bit & 1;
+      }
+    }
+    values
+  }
+
+  // output bits for the given input bits
+  pub fn eval(&self, inputs: &[Bit]) -> Vec<Bit> {
+    let values = self.values(inputs);
+    self.outputs.iter().map(|(_, id)| values[id.0]).collect()
+  }
+
+  // `values` for 64 packed vectors at once
+  pub fn batch_values(&self, inputs: &[u64]) -> Vec<u64> {
+    self.batch_values_forced(inputs, &[])
+  }
+
+  // `values_forced` for 64 packed vectors, a forced bit holds in every lane
+  pub fn batch_values_forced(&self, inputs: &[u64], forced: &[(NodeId, Bit)]) -> Vec<u64> {
+    assert_eq!(inputs.len(), self.inputs.len(), "error: netlist has {} inputs", self.inputs.len());
+    let mut values = vec![0; self.nodes.len()];
+    for (id, &word) in self.inputs.iter().zip(inputs) {
+      values[id.0] = word;
+    }
+    for (pos, node) in self.nodes.iter().enumerate() {
+      match node {
+        Node::Input(_) => {}
+        Node::Const(bit) => values[pos] = lanes(*bit),
+        Node::Nand(a, b) => values[pos] = !(values[a.0] & values[b.0]),
+      }
+      if let Some(&(_, bit)) = forced.iter().find(|(id, _)| id.0 == pos) {
+        values[pos] = lanes(bit);
+      }
+    }
+    values
+  }
+
+  // output words for packed input words, see `pack`
+  pub fn batch_eval(&self, inputs: &[u64]) -> Vec<u64> {
+    let values = self.batch_values(inputs);
+    self.outputs.iter().map(|(_, id)| values[id.0]).collect()
+  }
+
+  fn push(&mut self, node: Node) -> NodeId {
+    self.nodes.push(node);
+    NodeId(self.nodes.len() - 1)
+  }
+}
@@ -0,0 +1,217 @@
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::adders::Adder;
+use crate::alu;
+use crate::computer::Computer;
+use crate::gates::{self, Bit};
+use crate::netlist::{Netlist, Node};
+
+/*
+ SWITCHING ACTIVITY
+
+ dynamic power goes into charging wires, so the number of gate outputs and
+ DFFs that toggle is a proxy for it. while a `Computer` runs:
+
+ chip   | counts
+ -------+-----------------------------------------------------------------
+ ALU    | NAND outputs of `alu::netlist` that differ from the last cycle
+        | (settled values, glitches are not counted; hack-ext ops run the
+        | plain ALU netlist on their operands)
+ A D PC | register DFFs that change
+ RAM16K | DFFs of the written word that change
+ Screen | same, for writes to the screen
+
+ the heatmap shades every ALU NAND level and every 256-word RAM page
+ (128-word screen page) by toggles per cycle.
+*/
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Chip {
+  Alu,
+  A,
+  D,
+  Pc,
+  Ram,
+  Screen,
+}
+
+impl Chip {
+  pub const ALL: [Chip; 6] = [Chip::Alu, Chip::A, Chip::D, Chip::Pc, Chip::Ram, Chip::Screen];
+
+  pub fn name(self) -> &'static str {
+    match self {
+      Chip::Alu => "ALU",
+      Chip::A => "A",
+      Chip::D => "D",
+      Chip::Pc => "PC",
+      Chip::Ram => "RAM16K",
+      Chip::Screen => "Screen",
+    }
+  }
+}
+
+const SCREEN: u16 = 16384;
+const KEYBOARD: u16 = 24576;
+const PAGES: usize = 64;
+
+pub struct Activity {
+  alu: Netlist,
+  alu_values: Vec<Bit>,
+  // toggles of every ALU node
+  alu_toggles: Vec<u64>,
+  chips: HashMap<Chip, u64>,
+  ram_pages: [u64; PAGES],
+  screen_pages: [u64; PAGES],
+  // total toggles of every cycle
+  per_cycle: Vec<u64>,
+  // memory words seen so far, to know what a write changes
+  memory: HashMap<u16, u16>,
+  registers: Option<(u16, u16, u16)>,
+}
+
+impl Activity {
+  pub fn new(adder: Adder) -> Self {
+    let alu = alu::netlist(adder);
+    let alu_values = alu.values(&[0; 38]);
+    let alu_toggles = vec![0; alu.nodes().len()];
+    Self {
+      alu,
+      alu_values,
+      alu_toggles,
+      chips: HashMap::new(),
+      ram_pages: [0; PAGES],
+      screen_pages: [0; PAGES],
+      per_cycle: Vec::new(),
+      memory: HashMap::new(),
+      registers: None,
+    }
+  }
+
+  // step the computer `cycles` times, counting what toggles
+  pub fn run(&mut self, computer: &mut Computer, cycles: u64) {
+    for _ in 0..cycles {
+      let instruction = computer.instruction_at(computer.pc());
+      let (a, d, pc) = *self.registers.get_or_insert((computer.a(), computer.d(), computer.pc()));
+      // a write goes to A, remember what was there before the step
+      if instruction[0] == 1 && instruction[12] == 1 && computer.a() < KEYBOARD {
+        let address = computer.a();
+        self.memory.entry(address).or_insert_with(|| computer.peek(address));
+      }
+
+      let step = computer.step();
+      let mut cycle = 0;
+
+      let inputs: Vec<Bit> =
+        gates::from_u16(step.x).into_iter().chain(gates::from_u16(step.y)).chain(step.controls()).collect();
+      let values = self.alu.values(&inputs);
+      let mut alu = 0;
+      for (pos, (old, new)) in self.alu_values.iter().zip(&values).enumerate() {
+        if old != new && matches!(self.alu.nodes()[pos], Node::Nand(..)) {
+          self.alu_toggles[pos] += 1;
+          alu += 1;
+        }
+      }
+      self.alu_values = values;
+      cycle += self.add(Chip::Alu, alu);
+
+      cycle += self.add(Chip::A, (a ^ step.a).count_ones() as u64);
+      cycle += self.add(Chip::D, (d ^ step.d).count_ones() as u64);
+      cycle += self.add(Chip::Pc, (pc ^ step.next_pc).count_ones() as u64);
+      self.registers = Some((step.a, step.d, step.next_pc));
+
+      if let Some(write) = step.write.filter(|write| write.address < KEYBOARD) {
+        let old = self.memory.insert(write.address, write.value).unwrap_or(0);
+        let toggles = (old ^ write.value).count_ones() as u64;
+        if write.address < SCREEN {
+          self.ram_pages[write.address as usize / 256] += toggles;
+          cycle += self.add(Chip::Ram, toggles);
+        } else {
+          self.screen_pages[(write.address - SCREEN) as usize / 128] += toggles;
+          cycle += self.add(Chip::Screen, toggles);
+        }
+      }
+      self.per_cycle.push(cycle);
+    }
+  }
+
+  fn add(&mut self, chip: Chip, toggles: u64) -> u64 {
+    *self.chips.entry(chip).or_insert(0) += toggles;
+    toggles
+  }
+
+  pub fn cycles(&self) -> usize {
+    self.per_cycle.len()
+  }
+
+  pub fn toggles(&self, chip: Chip) -> u64 {
+    self.chips.get(&chip).copied().unwrap_or(0)
+  }
+
+  pub fn total(&self) -> u64 {
+    self.per_cycle.iter().sum()
+  }
+
+  pub fn per_cycle(&self) -> &[u64] {
+    &self.per_cycle
+  }
+
+  pub fn alu_toggles(&self) -> &[u64] {
+    &self.alu_toggles
+  }
+
+  // the ALU netlist the node toggles refer to
+  pub fn alu(&self) -> &Netlist {
+    &self.alu
+  }
+
+  // one row per ALU NAND level, then one row of RAM and screen pages
+  pub fn heatmap(&self) -> String {
+    let cycles = self.cycles().max(1) as f64;
+    let levels = self.alu.levels();
+    let depth = levels.iter().max().copied().unwrap_or(0);
+    let mut out = String::from("ALU toggles per cycle by NAND level\n");
+    for level in 1..=depth {
+      let rates: Vec<f64> = (0..levels.len())
+        .filter(|&pos| levels[pos] == level)
+        .map(|pos| self.alu_toggles[pos] as f64 / cycles)
+        .collect();
+      out.push_str(&format!("{:>4} |{}|\n", level, shade_row(&rates)));
+    }
+    out.push_str("memory DFF toggles per cycle by page\n");
+    for (name, pages) in [("RAM", &self.ram_pages), ("SCR", &self.screen_pages)] {
+      let rates: Vec<f64> = pages.iter().map(|&toggles| toggles as f64 / cycles).collect();
+      out.push_str(&format!("{:>4} |{}|\n", name, shade_row(&rates)));
+    }
+    out
+  }
+}
+
+impl fmt::Display for Activity {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let cycles = self.cycles().max(1) as f64;
+    writeln!(f, "{:<8} | {:>10} | {:>9}", "chip", "toggles", "per cycle")?;
+    writeln!(f, "{:-<8}-+-{:->10}-+-{:->9}", "", "", "")?;
+    for chip in Chip::ALL {
+      let toggles = self.toggles(chip);
+      writeln!(f, "{:<8} | {:>10} | {:>9.2}", chip.name(), toggles, toggles as f64 / cycles)?;
+    }
+    write!(f, "{:<8} | {:>10} | {:>9.2}", "total", self.total(), self.total() as f64 / cycles)
+  }
+}
+
+// at most 64 columns, shaded from ' ' (never) to '@' (every cycle or more)
+fn shade_row(rates: &[f64]) -> String {
+  const SHADES: &[u8] = b" .:-=+*#%@";
+  let columns = rates.len().min(PAGES);
+  (0..columns)
+    .map(|column| {
+      let (start, end) = (column * rates.len() / columns, (column + 1) * rates.len() / columns);
+      let rate = rates[start..end].iter().sum::<f64>() / (end - start) as f64;
+      let shade = if rate <= 0.0 { 0 } else { 1 + ((rate.min(1.0) * (SHADES.len() - 2) as f64) as usize) };
+      SHADES[shade] as char
+    })
+    .collect()
+}
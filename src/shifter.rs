@@ -0,0 +1,79 @@
+#![allow(dead_code)]
+
+use crate::gates::{self, Bit, Bit16, Bit4};
+
+/*
+ BARREL SHIFTER
+
+ four mux16 stages shift by 1, 2, 4 and 8, each one enabled by a bit of the
+ amount, so any amount 0..15 goes through the same 4 stages:
+
+   amount[3] --> by 1 --> amount[2] --> by 2 --> amount[1] --> by 4 --> amount[0] --> by 8
+
+ the stages only shift left. right shifts reverse the bits (wiring only)
+ before and after the stages. the bits coming in on the empty side are:
+
+ op  | right | arithmetic | rotate | fill
+ ----+-------+------------+--------+------------------------
+ shl |   0   |     0      |   0    | 0
+ shr |   1   |     0      |   0    | 0          (logical)
+ sar |   1   |     1      |   0    | sign bit   (arithmetic)
+ rol |   0   |     x      |   1    | bits shifted out
+ ror |   1   |     x      |   1    | bits shifted out
+
+ an arithmetic shift left is the same as a logical one.
+*/
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shift {
+  Shl,
+  Shr,
+  Sar,
+  Rol,
+  Ror,
+}
+
+impl Shift {
+  pub const ALL: [Shift; 5] = [Shift::Shl, Shift::Shr, Shift::Sar, Shift::Rol, Shift::Ror];
+
+  // (right, arithmetic, rotate) control bits
+  pub fn controls(self) -> (Bit, Bit, Bit) {
+    match self {
+      Shift::Shl => (0, 0, 0),
+      Shift::Shr => (1, 0, 0),
+      Shift::Sar => (1, 1, 0),
+      Shift::Rol => (0, 0, 1),
+      Shift::Ror => (1, 0, 1),
+    }
+  }
+}
+
+pub fn shift(value: Bit16, amount: Bit4, op: Shift) -> Bit16 {
+  let (right, arithmetic, rotate) = op.controls();
+  barrel_shift(value, amount, right, arithmetic, rotate)
+}
+
+pub fn barrel_shift(value: Bit16, amount: Bit4, right: Bit, arithmetic: Bit, rotate: Bit) -> Bit16 {
+  let fill = gates::and(value[0], gates::and(right, arithmetic));
+  let mut out = gates::mux16(value, reverse(value), right);
+  for (stage, &enable) in amount.iter().rev().enumerate() {
+    out = gates::mux16(out, shift_left(out, 1 << stage, fill, rotate), enable);
+  }
+  gates::mux16(out, reverse(out), right)
+}
+
+// wiring: every bit moves `by` places towards the MSB, the LSB side gets
+// either `fill` or (rotate) the bits that fell off the MSB side
+fn shift_left(value: Bit16, by: usize, fill: Bit, rotate: Bit) -> Bit16 {
+  let mut out = [0; 16];
+  for (pos, bit) in out.iter_mut().enumerate() {
+    *bit = if pos + by < 16 { value[pos + by] } else { gates::mux(fill, value[pos + by - 16], rotate) };
+  }
+  out
+}
+
+fn reverse(value: Bit16) -> Bit16 {
+  let mut out = value;
+  out.reverse();
+  out
+}
@@ -0,0 +1,178 @@
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::Path;
+
+use crate::computer::Computer;
+use crate::cpu::CPU;
+use crate::gates::{self, Bit};
+use crate::instruction::Isa;
+use crate::mem::{memory::Memory, ram16k::RAM16K, register::Register, rom32k::ROM32K, screen::Screen};
+
+/*
+ MACHINE SNAPSHOT
+
+ offset | size | field
+ -------+------+------------------------------------------------
+ 0      | 8    | magic "NANDSNAP"
+ 8      | 2    | format version (little endian)
+ 10     | ...  | sections, in this order:
+
+ tag    | payload
+ -------+------------------------------------------------------------
+ "CPU " | A, D, PC register DFFs (48 bits)
+ "CYCL" | cycle counter (u64, little endian)
+ "ISA " | instruction set, 0 hack / 1 hack-ext (1 byte, since version 2)
+ "RAM " | every DFF of RAM16K (16K x 16 bits)
+ "SCRN" | every DFF of the screen (8K x 16 bits)
+ "KBD " | keyboard register DFFs (16 bits)
+ "ROM " | program words (2 bytes each, big endian), at most 32K
+
+ every section is `tag (4) | payload length (u32, little endian) | payload`.
+ DFF states are packed 8 per byte, first DFF in the most significant bit.
+*/
+
+pub const MAGIC: &[u8; 8] = b"NANDSNAP";
+pub const VERSION: u16 = 2;
+
+const CPU_BITS: usize = 3 * 16;
+const RAM_BITS: usize = 16 * 1024 * 16;
+const SCREEN_BITS: usize = 8 * 1024 * 16;
+const KEYBOARD_BITS: usize = 16;
+
+pub fn save(computer: &Computer) -> Vec<u8> {
+  let mut out = Vec::new();
+  out.extend_from_slice(MAGIC);
+  out.extend_from_slice(&VERSION.to_le_bytes());
+
+  let mut bits = Vec::with_capacity(RAM_BITS);
+  computer.cpu().save(&mut bits);
+  section(&mut out, b"CPU ", &pack(&bits));
+  section(&mut out, b"CYCL", &computer.cycle().to_le_bytes());
+  section(&mut out, b"ISA ", &[Isa::ALL.iter().position(|&isa| isa == computer.isa()).unwrap() as u8]);
+
+  bits.clear();
+  computer.memory().ram().save(&mut bits);
+  section(&mut out, b"RAM ", &pack(&bits));
+
+  bits.clear();
+  computer.memory().screen().save(&mut bits);
+  section(&mut out, b"SCRN", &pack(&bits));
+
+  bits.clear();
+  computer.memory().keyboard().save(&mut bits);
+  section(&mut out, b"KBD ", &pack(&bits));
+
+  let words = computer.rom().words();
+  let rom: Vec<u8> = words.iter().flat_map(|&word| gates::to_u16(word).to_be_bytes()).collect();
+  section(&mut out, b"ROM ", &rom);
+  out
+}
+
+pub fn load(bytes: &[u8]) -> Result<Computer, String> {
+  let mut reader = Reader { bytes, offset: 0 };
+  if reader.take(MAGIC.len())? != MAGIC {
+    return Err("not a machine snapshot (bad magic)".to_string());
+  }
+  let version = u16::from_le_bytes(reader.take(2)?.try_into().unwrap());
+  // version 1 snapshots are the same without the ISA section
+  if version != 1 && version != VERSION {
+    return Err(format!("unsupported snapshot version {} (expected {})", version, VERSION));
+  }
+
+  let mut cpu = CPU::new();
+  cpu.restore(&mut unpack(reader.section(b"CPU ", CPU_BITS.div_ceil(8))?, CPU_BITS).iter());
+
+  let cycle = u64::from_le_bytes(reader.section(b"CYCL", 8)?.try_into().unwrap());
+  if version >= 2 {
+    let isa = reader.section(b"ISA ", 1)?[0];
+    cpu.set_isa(*Isa::ALL.get(isa as usize).ok_or_else(|| format!("unknown instruction set {}", isa))?);
+  }
+
+  let mut ram = Box::<RAM16K>::default();
+  ram.restore(&mut unpack(reader.section(b"RAM ", RAM_BITS / 8)?, RAM_BITS).iter());
+
+  let mut screen = Box::<Screen>::default();
+  screen.restore(&mut unpack(reader.section(b"SCRN", SCREEN_BITS / 8)?, SCREEN_BITS).iter());
+
+  let mut keyboard = Register::new();
+  keyboard.restore(&mut unpack(reader.section(b"KBD ", KEYBOARD_BITS / 8)?, KEYBOARD_BITS).iter());
+
+  let rom = reader.any_section(b"ROM ")?;
+  if rom.len() % 2 != 0 || rom.len() / 2 > ROM32K::SIZE {
+    return Err(format!("section 'ROM ' has invalid length {}", rom.len()));
+  }
+  let program = rom.chunks(2).map(|word| gates::from_u16(u16::from_be_bytes([word[0], word[1]]))).collect();
+
+  if reader.offset != bytes.len() {
+    return Err(format!("{} unexpected trailing bytes", bytes.len() - reader.offset));
+  }
+  Ok(Computer::from_parts(cpu, ROM32K::new(program), Memory::new(ram, screen, keyboard), cycle))
+}
+
+pub fn save_file(computer: &Computer, path: &Path) -> Result<(), String> {
+  fs::write(path, save(computer)).map_err(|error| format!("{}: {}", path.display(), error))
+}
+
+pub fn load_file(path: &Path) -> Result<Computer, String> {
+  let bytes = fs::read(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+  load(&bytes).map_err(|error| format!("{}: {}", path.display(), error))
+}
+
+fn section(out: &mut Vec<u8>, tag: &[u8; 4], payload: &[u8]) {
+  out.extend_from_slice(tag);
+  out.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+  out.extend_from_slice(payload);
+}
+
+fn pack(bits: &[Bit]) -> Vec<u8> {
+  bits
+    .chunks(8)
+    .map(|chunk| chunk.iter().enumerate().fold(0, |byte, (pos, &bit)| byte | (bit & 1) << (7 - pos)))
+    .collect()
+}
+
+fn unpack(bytes: &[u8], count: usize) -> Vec<Bit> {
+  (0..count).map(|pos| (bytes[pos / 8] >> (7 - pos % 8)) & 1).collect()
+}
+
+struct Reader<'a> {
+  bytes: &'a [u8],
+  offset: usize,
+}
+
+impl<'a> Reader<'a> {
+  fn take(&mut self, count: usize) -> Result<&'a [u8], String> {
+    let end = self.offset.checked_add(count).filter(|&end| end <= self.bytes.len());
+    let end = end.ok_or_else(|| format!("snapshot truncated at byte {}", self.offset))?;
+    let slice = &self.bytes[self.offset..end];
+    self.offset = end;
+    Ok(slice)
+  }
+
+  fn any_section(&mut self, tag: &[u8; 4]) -> Result<&'a [u8], String> {
+    let found = self.take(4)?;
+    if found != tag {
+      return Err(format!(
+        "expected section '{}', found '{}'",
+        String::from_utf8_lossy(tag),
+        String::from_utf8_lossy(found)
+      ));
+    }
+    let length = u32::from_le_bytes(self.take(4)?.try_into().unwrap()) as usize;
+    self.take(length)
+  }
+
+  fn section(&mut self, tag: &[u8; 4], length: usize) -> Result<&'a [u8], String> {
+    let payload = self.any_section(tag)?;
+    if payload.len() != length {
+      return Err(format!(
+        "section '{}' has {} bytes, expected {}",
+        String::from_utf8_lossy(tag),
+        payload.len(),
+        length
+      ));
+    }
+    Ok(payload)
+  }
+}
@@ -0,0 +1,396 @@
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+
+use crate::adders::{self, Adder};
+use crate::alu;
+use crate::bdd::{self, Equivalence};
+use crate::gates::{self, Bit};
+use crate::netlist::{Logic, Netlist, Node, NodeId};
+
+/*
+ TECHNOLOGY MAPPING
+
+ `Gates` records a circuit as AND / OR / NOT / XOR / MUX gates (any circuit
+ written against `Logic`). mapping it onto NANDs gate by gate uses the
+ gates.rs formulas:
+
+ gate | NANDs | naive form
+ -----+-------+-----------------------------------
+ not  |   1   | nand(a, 1)
+ and  |   2   | not(nand(a, b))
+ or   |   3   | nand(not a, not b)
+ xor  |   4   | nand(nand(a, n), nand(b, n)), n = nand(a, b)
+ mux  |   8   | or(and(a, not sel), and(b, sel))
+
+ `optimise` then rebuilds the NAND netlist node by node:
+
+ - constants fold: nand(a, 0) = 1, nand(a, 1) = not a
+ - nand(a, a) = not a, nand(a, not a) = 1
+ - double inversions cancel: not(not a) = a, so an and feeding an or
+   (not(nand) feeding nand(not, not)) becomes nand feeding nand, and the
+   mux drops to nand(nand(a, not sel), nand(b, sel)), 4 NANDs
+ - equal nands are built once, nand(a, b) and nand(b, a) included
+ - nodes no output needs are dropped
+
+ and repeats until the count stops falling.
+*/
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GateId(pub usize);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Gate {
+  Input(String),
+  Const(Bit),
+  Nand(GateId, GateId),
+  Not(GateId),
+  And(GateId, GateId),
+  Or(GateId, GateId),
+  Xor(GateId, GateId),
+  // a when sel is 0, b when sel is 1
+  Mux(GateId, GateId, GateId),
+}
+
+// a netlist of mixed gates, gates only point to earlier gates
+#[derive(Clone, Default)]
+pub struct Gates {
+  gates: Vec<Gate>,
+  inputs: Vec<GateId>,
+  outputs: Vec<(String, GateId)>,
+}
+
+impl Logic for Gates {
+  type Signal = GateId;
+
+  fn nand(&mut self, a: GateId, b: GateId) -> GateId {
+    self.push(Gate::Nand(a, b))
+  }
+
+  fn constant(&mut self, bit: Bit) -> GateId {
+    self.push(Gate::Const(bit & 1))
+  }
+
+  fn not(&mut self, a: GateId) -> GateId {
+    self.push(Gate::Not(a))
+  }
+
+  fn and(&mut self, a: GateId, b: GateId) -> GateId {
+    self.push(Gate::And(a, b))
+  }
+
+  fn or(&mut self, a: GateId, b: GateId) -> GateId {
+    self.push(Gate::Or(a, b))
+  }
+
+  fn xor(&mut self, a: GateId, b: GateId) -> GateId {
+    self.push(Gate::Xor(a, b))
+  }
+
+  fn mux(&mut self, a: GateId, b: GateId, sel: GateId) -> GateId {
+    self.push(Gate::Mux(a, b, sel))
+  }
+}
+
+impl Gates {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn input(&mut self, name: &str) -> GateId {
+    let id = self.push(Gate::Input(name.to_string()));
+    self.inputs.push(id);
+    id
+  }
+
+  // N inputs named name[0] .. name[N - 1], MSB first like Bit16
+  pub fn input_bus<const N: usize>(&mut self, name: &str) -> [GateId; N] {
+    std::array::from_fn(|pos| self.input(&format!("{}[{}]", name, pos)))
+  }
+
+  pub fn output(&mut self, name: &str, id: GateId) {
+    self.outputs.push((name.to_string(), id));
+  }
+
+  pub fn output_bus(&mut self, name: &str, ids: &[GateId]) {
+    for (pos, &id) in ids.iter().enumerate() {
+      self.output(&format!("{}[{}]", name, pos), id);
+    }
+  }
+
+  pub fn gates(&self) -> &[Gate] {
+    &self.gates
+  }
+
+  pub fn inputs(&self) -> &[GateId] {
+    &self.inputs
+  }
+
+  pub fn outputs(&self) -> &[(String, GateId)] {
+    &self.outputs
+  }
+
+  // gates other than inputs and constants
+  pub fn gate_count(&self) -> usize {
+    self.gates.iter().filter(|gate| !matches!(gate, Gate::Input(_) | Gate::Const(_))).count()
+  }
+
+  // output bits for the given input bits, with the gates.rs gates
+  pub fn eval(&self, inputs: &[Bit]) -> Vec<Bit> {
+    let mut values = vec![0; self.gates.len()];
+    for (id, &bit) in self.inputs.iter().zip(inputs) {
+      values[id.0] = bit & 1;
+    }
+    for (pos, gate) in self.gates.iter().enumerate() {
+      values[pos] = match *gate {
+        Gate::Input(_) => values[pos],
+        Gate::Const(bit) => bit,
+        Gate::Nand(a, b) => gates::nand(values[a.0], values[b.0]),
+        Gate::Not(a) => gates::not(values[a.0]),
+        Gate::And(a, b) => gates::and(values[a.0], values[b.0]),
+        Gate::Or(a, b) => gates::or(values[a.0], values[b.0]),
+        Gate::Xor(a, b) => gates::xor(values[a.0], values[b.0]),
+        Gate::Mux(a, b, sel) => gates::mux(values[a.0], values[b.0], values[sel.0]),
+      };
+    }
+    self.outputs.iter().map(|(_, id)| values[id.0]).collect()
+  }
+
+  fn push(&mut self, gate: Gate) -> GateId {
+    self.gates.push(gate);
+    GateId(self.gates.len() - 1)
+  }
+}
+
+// every gate replaced by its NAND formula, see the table above
+pub fn naive(gates: &Gates) -> Netlist {
+  let mut netlist = Netlist::new();
+  let mut nodes = Vec::with_capacity(gates.gates().len());
+  for gate in gates.gates() {
+    let node = |id: &GateId| nodes[id.0];
+    let id = match gate {
+      Gate::Input(name) => netlist.input(name),
+      Gate::Const(bit) => netlist.constant(*bit),
+      Gate::Nand(a, b) => netlist.nand(node(a), node(b)),
+      Gate::Not(a) => netlist.not(node(a)),
+      Gate::And(a, b) => netlist.and(node(a), node(b)),
+      Gate::Or(a, b) => netlist.or(node(a), node(b)),
+      Gate::Xor(a, b) => netlist.xor(node(a), node(b)),
+      Gate::Mux(a, b, sel) => netlist.mux(node(a), node(b), node(sel)),
+    };
+    nodes.push(id);
+  }
+  for (name, id) in gates.outputs() {
+    netlist.output(name, nodes[id.0]);
+  }
+  netlist
+}
+
+// the naive mapping, optimised
+pub fn map(gates: &Gates) -> Netlist {
+  optimise(&naive(gates))
+}
+
+// rebuilds `netlist` with the rewrites of the header until no NAND is saved
+pub fn optimise(netlist: &Netlist) -> Netlist {
+  let mut best = rebuild(netlist);
+  loop {
+    let next = rebuild(&best);
+    if next.nand_count() >= best.nand_count() {
+      return best;
+    }
+    best = next;
+  }
+}
+
+fn rebuild(netlist: &Netlist) -> Netlist {
+  // nodes an output depends on
+  let mut live = vec![false; netlist.nodes().len()];
+  for (_, id) in netlist.outputs() {
+    live[id.0] = true;
+  }
+  for (pos, node) in netlist.nodes().iter().enumerate().rev() {
+    if let (true, Node::Nand(a, b)) = (live[pos], node) {
+      live[a.0] = true;
+      live[b.0] = true;
+    }
+  }
+
+  let mut builder = Builder::default();
+  let mut nodes: Vec<Option<NodeId>> = vec![None; netlist.nodes().len()];
+  for &id in netlist.inputs() {
+    let Node::Input(name) = netlist.node(id) else { unreachable!() };
+    nodes[id.0] = Some(builder.netlist.input(name));
+  }
+  for (pos, node) in netlist.nodes().iter().enumerate() {
+    nodes[pos] = match (live[pos], node) {
+      (false, _) | (_, Node::Input(_)) => nodes[pos],
+      (true, Node::Const(bit)) => Some(builder.constant(*bit)),
+      (true, Node::Nand(a, b)) => Some(builder.nand(nodes[a.0].unwrap(), nodes[b.0].unwrap())),
+    };
+  }
+  for (name, id) in netlist.outputs() {
+    builder.netlist.output(name, nodes[id.0].unwrap());
+  }
+  builder.netlist
+}
+
+#[derive(Default)]
+struct Builder {
+  netlist: Netlist,
+  constants: [Option<NodeId>; 2],
+  // every nand built, by its (sorted) inputs
+  nands: HashMap<(NodeId, NodeId), NodeId>,
+  // a node and its inversion, both ways
+  inverse: HashMap<NodeId, NodeId>,
+}
+
+impl Builder {
+  fn constant(&mut self, bit: Bit) -> NodeId {
+    match self.constants[bit as usize] {
+      Some(id) => id,
+      None => {
+        let id = self.netlist.constant(bit);
+        self.constants[bit as usize] = Some(id);
+        id
+      }
+    }
+  }
+
+  fn value(&self, id: NodeId) -> Option<Bit> {
+    match self.netlist.node(id) {
+      Node::Const(bit) => Some(*bit),
+      _ => None,
+    }
+  }
+
+  fn nand(&mut self, a: NodeId, b: NodeId) -> NodeId {
+    match (self.value(a), self.value(b)) {
+      (Some(0), _) | (_, Some(0)) => return self.constant(1),
+      (Some(_), Some(_)) => return self.constant(0),
+      (Some(_), None) => return self.not(b),
+      (None, Some(_)) => return self.not(a),
+      (None, None) => {}
+    }
+    if a == b {
+      return self.not(a);
+    }
+    if self.inverse.get(&a) == Some(&b) {
+      return self.constant(1);
+    }
+    self.hashed(a, b)
+  }
+
+  fn not(&mut self, a: NodeId) -> NodeId {
+    if let Some(bit) = self.value(a) {
+      return self.constant(1 - bit);
+    }
+    if let Some(&inverse) = self.inverse.get(&a) {
+      return inverse;
+    }
+    let one = self.constant(1);
+    let inverse = self.hashed(a, one);
+    self.inverse.insert(a, inverse);
+    self.inverse.insert(inverse, a);
+    inverse
+  }
+
+  fn hashed(&mut self, a: NodeId, b: NodeId) -> NodeId {
+    let key = (a.min(b), a.max(b));
+    match self.nands.get(&key) {
+      Some(&id) => id,
+      None => {
+        let id = self.netlist.nand(a, b);
+        self.nands.insert(key, id);
+        id
+      }
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Report {
+  pub chip: String,
+  pub gates: usize,
+  pub naive: usize,
+  pub mapped: usize,
+  // the mapped netlist computes the same function, checked with BDDs
+  pub equivalent: bool,
+}
+
+pub fn report(chip: &str, gates: &Gates) -> Report {
+  let (naive, mapped) = (naive(gates), map(gates));
+  Report {
+    chip: chip.to_string(),
+    gates: gates.gate_count(),
+    naive: naive.nand_count(),
+    mapped: mapped.nand_count(),
+    equivalent: bdd::equivalent(&naive, &mapped) == Ok(Equivalence::Equivalent),
+  }
+}
+
+// a few small chips, then every adder and the ALU with every adder
+pub fn chips() -> Vec<(String, Gates)> {
+  let mut chips = Vec::new();
+
+  let mut mux = Gates::new();
+  let (a, b, sel) = (mux.input("a"), mux.input("b"), mux.input("sel"));
+  let out = mux.mux(a, b, sel);
+  mux.output("out", out);
+  chips.push(("Mux".to_string(), mux));
+
+  // two half adders and an or, like gates::full_adder
+  let mut full_adder = Gates::new();
+  let (a, b, c) = (full_adder.input("a"), full_adder.input("b"), full_adder.input("c"));
+  let (sum_one, carry_one) = (full_adder.xor(a, b), full_adder.and(a, b));
+  let (sum, carry_two) = (full_adder.xor(sum_one, c), full_adder.and(sum_one, c));
+  let carry = full_adder.or(carry_one, carry_two);
+  full_adder.output("sum", sum);
+  full_adder.output("carry", carry);
+  chips.push(("FullAdder".to_string(), full_adder));
+
+  for adder in Adder::ALL {
+    let mut gates = Gates::new();
+    let (a, b) = (gates.input_bus::<16>("a"), gates.input_bus::<16>("b"));
+    let zero = gates.constant(0);
+    let (sum, _) = adders::add(&mut gates, adder, a, b, zero);
+    gates.output_bus("sum", &sum);
+    chips.push((format!("Add16 ({})", adder), gates));
+  }
+  for adder in Adder::ALL {
+    let mut gates = Gates::new();
+    let (x, y) = (gates.input_bus::<16>("x"), gates.input_bus::<16>("y"));
+    let controls = ["zx", "nx", "zy", "ny", "f", "no"].map(|name| gates.input(name));
+    let (out, zr, ng) = alu::circuit(&mut gates, adder, x, y, controls);
+    gates.output_bus("out", &out);
+    gates.output("zr", zr);
+    gates.output("ng", ng);
+    chips.push((format!("ALU ({})", adder), gates));
+  }
+  chips
+}
+
+pub fn reports() -> Vec<Report> {
+  chips().iter().map(|(chip, gates)| report(chip, gates)).collect()
+}
+
+pub fn report_table(reports: &[Report]) -> String {
+  let mut out = format!(
+    "{:<24} | {:>5} | {:>5} | {:>6} | {:>5} | {:>10}\n",
+    "chip", "gates", "naive", "mapped", "saved", "equivalent"
+  );
+  out.push_str(&format!("{:-<24}-+-{:->5}-+-{:->5}-+-{:->6}-+-{:->5}-+-{:->10}\n", "", "", "", "", "", ""));
+  for report in reports {
+    let saved = 100.0 * report.naive.saturating_sub(report.mapped) as f64 / report.naive.max(1) as f64;
+    out.push_str(&format!(
+      "{:<24} | {:>5} | {:>5} | {:>6} | {:>4.0}% | {:>10}\n",
+      report.chip,
+      report.gates,
+      report.naive,
+      report.mapped,
+      saved,
+      if report.equivalent { "yes" } else { "NO" }
+    ));
+  }
+  out
+}
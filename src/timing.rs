@@ -0,0 +1,345 @@
+#![allow(dead_code)]
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::fmt;
+
+use crate::adders::Adder;
+use crate::alu;
+use crate::gates::Bit;
+use crate::netlist::{Logic, Netlist, Node, NodeId};
+
+/*
+ TIMING
+
+ every NAND takes `Delays::nand` time units (think picoseconds) to follow its
+ inputs, single gates can be given their own delay. inputs switch at time 0,
+ constants never switch, so the shortest path only runs through inputs.
+
+ static:  arrival times over every path, the latest one at an output is the
+          critical path (worst-case settling), the earliest one the shortest
+ dynamic: `simulate` applies one input change and replays every gate output
+          change in time order (transport delay: pulses shorter than a gate
+          delay still propagate), settling is the last output change
+
+ setup / hold: a chip sits between two register clock edges `period` apart.
+ its outputs must settle `setup` before the next edge (critical + setup <=
+ period) and must not change until `hold` after the launching edge
+ (shortest >= hold).
+
+ the RAM reads are the mux trees that select one register (per output bit):
+ RAM8 is one 8-way mux, RAM64 a RAM8 mux feeding another 8-way mux, ...
+*/
+
+pub const NAND_DELAY: u64 = 10;
+pub const DEFAULT_CLOCK: Clock = Clock { period: 1000, setup: 20, hold: 10 };
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Delays {
+  pub nand: u64,
+  overrides: HashMap<NodeId, u64>,
+}
+
+impl Default for Delays {
+  fn default() -> Self {
+    Self::uniform(NAND_DELAY)
+  }
+}
+
+impl Delays {
+  pub fn uniform(nand: u64) -> Self {
+    Self { nand, overrides: HashMap::new() }
+  }
+
+  // a slower or faster gate
+  pub fn set(&mut self, node: NodeId, delay: u64) {
+    self.overrides.insert(node, delay);
+  }
+
+  pub fn of(&self, node: NodeId) -> u64 {
+    self.overrides.get(&node).copied().unwrap_or(self.nand)
+  }
+}
+
+// (latest, earliest) arrival time of every node, a node fed only by
+// constants never switches and arrives earliest at u64::MAX
+pub fn arrivals(netlist: &Netlist, delays: &Delays) -> Vec<(u64, u64)> {
+  let mut times = vec![(0, 0); netlist.nodes().len()];
+  for (pos, node) in netlist.nodes().iter().enumerate() {
+    match node {
+      Node::Input(_) => {}
+      Node::Const(_) => times[pos] = (0, u64::MAX),
+      Node::Nand(a, b) => {
+        let delay = delays.of(NodeId(pos));
+        let (a, b) = (times[a.0], times[b.0]);
+        times[pos] = (a.0.max(b.0) + delay, a.1.min(b.1).saturating_add(delay));
+      }
+    }
+  }
+  times
+}
+
+// latest arrival at any output: the worst-case settling time
+pub fn critical_path(netlist: &Netlist, delays: &Delays) -> u64 {
+  let times = arrivals(netlist, delays);
+  netlist.outputs().iter().map(|(_, id)| times[id.0].0).max().unwrap_or(0)
+}
+
+// earliest arrival at any output: how soon an output may start to change
+pub fn shortest_path(netlist: &Netlist, delays: &Delays) -> u64 {
+  let times = arrivals(netlist, delays);
+  netlist.outputs().iter().map(|(_, id)| times[id.0].1).min().unwrap_or(0)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Clock {
+  pub period: u64,
+  pub setup: u64,
+  pub hold: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Violation {
+  // the output settles at `arrival`, after `required` = period - setup
+  Setup { output: String, arrival: u64, required: u64 },
+  // the output may change at `arrival`, before `required` = hold
+  Hold { output: String, arrival: u64, required: u64 },
+}
+
+impl fmt::Display for Violation {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Violation::Setup { output, arrival, required } => {
+        write!(f, "setup violation: {} settles at {}, required by {}", output, arrival, required)
+      }
+      Violation::Hold { output, arrival, required } => {
+        write!(f, "hold violation: {} changes at {}, must hold until {}", output, arrival, required)
+      }
+    }
+  }
+}
+
+pub fn check(netlist: &Netlist, delays: &Delays, clock: Clock) -> Vec<Violation> {
+  let times = arrivals(netlist, delays);
+  let required = clock.period.saturating_sub(clock.setup);
+  let mut violations = Vec::new();
+  for (name, id) in netlist.outputs() {
+    let (latest, earliest) = times[id.0];
+    if latest > required {
+      violations.push(Violation::Setup { output: name.clone(), arrival: latest, required });
+    }
+    if earliest < clock.hold {
+      violations.push(Violation::Hold { output: name.clone(), arrival: earliest, required: clock.hold });
+    }
+  }
+  violations
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Change {
+  pub time: u64,
+  pub node: NodeId,
+  pub value: Bit,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Transition {
+  // every node change, in time order
+  pub changes: Vec<Change>,
+  // time of the last output change, 0 when no output changed
+  pub settle: u64,
+}
+
+impl Transition {
+  // the changes of every output (in `Netlist::outputs` order)
+  pub fn output_changes(&self, netlist: &Netlist) -> Vec<Vec<(u64, Bit)>> {
+    let outputs = netlist.outputs();
+    let mut out = vec![Vec::new(); outputs.len()];
+    for change in &self.changes {
+      for (pos, (_, id)) in outputs.iter().enumerate() {
+        if *id == change.node {
+          out[pos].push((change.time, change.value));
+        }
+      }
+    }
+    out
+  }
+}
+
+// settle on `before`, switch the inputs to `after` at time 0 and replay the
+// gate output changes until nothing is left to switch
+pub fn simulate(netlist: &Netlist, delays: &Delays, before: &[Bit], after: &[Bit]) -> Transition {
+  let mut fanout = vec![Vec::new(); netlist.nodes().len()];
+  for (pos, node) in netlist.nodes().iter().enumerate() {
+    if let Node::Nand(a, b) = node {
+      fanout[a.0].push(pos);
+      if a != b {
+        fanout[b.0].push(pos);
+      }
+    }
+  }
+
+  let mut values = netlist.values(before);
+  // (time, order scheduled, node, value), earliest first
+  let mut events = BinaryHeap::new();
+  let mut scheduled = 0u64;
+  for (pos, (&id, &bit)) in netlist.inputs().iter().zip(after).enumerate() {
+    if before[pos] != bit & 1 {
+      events.push(Reverse((0, scheduled, id.0, bit & 1)));
+      scheduled += 1;
+    }
+  }
+
+  let mut changes = Vec::new();
+  while let Some(&Reverse((time, ..))) = events.peek() {
+    // every event of this instant first (the last one scheduled for a node
+    // wins), so simultaneous changes do not show as zero-width pulses
+    let mut pending: Vec<(usize, Bit)> = Vec::new();
+    while events.peek().is_some_and(|event| event.0 .0 == time) {
+      let Reverse((_, _, node, value)) = events.pop().unwrap();
+      match pending.iter_mut().find(|(pending, _)| *pending == node) {
+        Some(entry) => entry.1 = value,
+        None => pending.push((node, value)),
+      }
+    }
+    let mut gates: Vec<usize> = Vec::new();
+    for (node, value) in pending {
+      if values[node] != value {
+        values[node] = value;
+        changes.push(Change { time, node: NodeId(node), value });
+        gates.extend(&fanout[node]);
+      }
+    }
+    gates.sort_unstable();
+    gates.dedup();
+    for gate in gates {
+      let Node::Nand(a, b) = netlist.nodes()[gate] else { unreachable!() };
+      let out = 1 - (values[a.0] & values[b.0]);
+      events.push(Reverse((time + delays.of(NodeId(gate)), scheduled, gate, out)));
+      scheduled += 1;
+    }
+  }
+
+  let outputs: Vec<NodeId> = netlist.outputs().iter().map(|(_, id)| *id).collect();
+  let settle = changes.iter().filter(|change| outputs.contains(&change.node)).map(|change| change.time).max();
+  Transition { changes, settle: settle.unwrap_or(0) }
+}
+
+// settling time of the ALU for one change of its inputs (x, y, controls)
+pub fn alu_settling(adder: Adder, delays: &Delays, before: &[Bit], after: &[Bit]) -> u64 {
+  simulate(&alu::netlist(adder), delays, before, after).settle
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Ram {
+  RAM8,
+  RAM64,
+  RAM512,
+  RAM4K,
+  RAM16K,
+}
+
+impl Ram {
+  pub const ALL: [Ram; 5] = [Ram::RAM8, Ram::RAM64, Ram::RAM512, Ram::RAM4K, Ram::RAM16K];
+
+  pub fn name(self) -> &'static str {
+    match self {
+      Ram::RAM8 => "RAM8",
+      Ram::RAM64 => "RAM64",
+      Ram::RAM512 => "RAM512",
+      Ram::RAM4K => "RAM4K",
+      Ram::RAM16K => "RAM16K",
+    }
+  }
+
+  // 3 bits per 8-way level, 2 for the 4-way level of RAM16K
+  pub fn address_bits(self) -> usize {
+    match self {
+      Ram::RAM8 => 3,
+      Ram::RAM64 => 6,
+      Ram::RAM512 => 9,
+      Ram::RAM4K => 12,
+      Ram::RAM16K => 14,
+    }
+  }
+}
+
+// the read path of one output bit: inputs address[..] (MSB first) and every
+// register's bit cell[..], output out
+pub fn ram_read_netlist(ram: Ram) -> Netlist {
+  let mut netlist = Netlist::new();
+  let bits = ram.address_bits();
+  let address: Vec<NodeId> = (0..bits).map(|pos| netlist.input(&format!("address[{}]", pos))).collect();
+  let cells: Vec<NodeId> = (0..1 << bits).map(|pos| netlist.input(&format!("cell[{}]", pos))).collect();
+  let out = mux_tree(&mut netlist, &cells, &address);
+  netlist.output("out", out);
+  netlist
+}
+
+// selects data[sel] with sel MSB first, halving on each select bit
+fn mux_tree<L: Logic>(logic: &mut L, data: &[L::Signal], sel: &[L::Signal]) -> L::Signal {
+  match sel {
+    [] => data[0],
+    [first, rest @ ..] => {
+      let (low, high) = data.split_at(data.len() / 2);
+      let (low, high) = (mux_tree(logic, low, rest), mux_tree(logic, high, rest));
+      logic.mux(low, high, *first)
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Report {
+  pub chip: String,
+  pub nands: usize,
+  pub depth: usize,
+  pub critical: u64,
+  pub shortest: u64,
+  pub violations: Vec<Violation>,
+}
+
+pub fn report(chip: &str, netlist: &Netlist, delays: &Delays, clock: Clock) -> Report {
+  Report {
+    chip: chip.to_string(),
+    nands: netlist.nand_count(),
+    depth: netlist.depth(),
+    critical: critical_path(netlist, delays),
+    shortest: shortest_path(netlist, delays),
+    violations: check(netlist, delays, clock),
+  }
+}
+
+// the ALU with every adder and every RAM read path
+pub fn reports(delays: &Delays, clock: Clock) -> Vec<Report> {
+  let mut reports: Vec<Report> = Adder::ALL
+    .into_iter()
+    .map(|adder| report(&format!("ALU ({})", adder), &alu::netlist(adder), delays, clock))
+    .collect();
+  reports.extend(Ram::ALL.map(|ram| report(&format!("{} read", ram.name()), &ram_read_netlist(ram), delays, clock)));
+  reports
+}
+
+pub fn report_table(reports: &[Report], clock: Clock) -> String {
+  let mut out = format!(
+    "period {}, setup {}, hold {}\n{:<22} | {:>6} | {:>5} | {:>8} | {:>8} | {:>10}\n",
+    clock.period, clock.setup, clock.hold, "chip", "nands", "depth", "critical", "shortest", "violations"
+  );
+  out.push_str(&format!("{:-<22}-+-{:->6}-+-{:->5}-+-{:->8}-+-{:->8}-+-{:->10}\n", "", "", "", "", "", ""));
+  for report in reports {
+    out.push_str(&format!(
+      "{:<22} | {:>6} | {:>5} | {:>8} | {:>8} | {:>10}\n",
+      report.chip,
+      report.nands,
+      report.depth,
+      report.critical,
+      report.shortest,
+      report.violations.len()
+    ));
+  }
+  for report in reports {
+    for violation in &report.violations {
+      out.push_str(&format!("{}: {}\n", report.chip, violation));
+    }
+  }
+  out
+}
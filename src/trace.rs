@@ -0,0 +1,128 @@
+#![allow(dead_code)]
+
+use std::io::{self, Write};
+
+use crate::computer::{Access, Computer, Step};
+use crate::instruction::Instruction;
+
+/*
+ CYCLE TRACER
+
+ records every clock cycle of a `Computer` run, either as a human readable
+ log or as JSON Lines (one object per cycle) for tooling. both print A, D and
+ memory values as unsigned 16-bit words.
+
+ text:
+   cycle     pc  instruction          A       D  zx nx zy ny  f no  zr ng  memory
+       3      3  M=D                 16       5   0  0  1  1  0  0   0  0  RAM[16] <- 5
+
+ json:
+   {"cycle":3,"pc":3,"instruction":"1110001100001000","asm":"M=D","a":16,"d":5,
+    "alu":{"zx":0,"nx":0,"zy":1,"ny":1,"f":0,"no":0},"zr":0,"ng":0,
+    "read":null,"write":{"address":16,"value":5}}
+*/
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+  Text,
+  JsonLines,
+}
+
+pub struct Tracer<W: Write> {
+  out: W,
+  format: Format,
+  header: bool,
+}
+
+impl<W: Write> Tracer<W> {
+  pub fn new(out: W, format: Format) -> Self {
+    Self { out, format, header: false }
+  }
+
+  pub fn record(&mut self, step: &Step) -> io::Result<()> {
+    match self.format {
+      Format::Text => {
+        if !self.header {
+          writeln!(self.out, "{}", text_header())?;
+          self.header = true;
+        }
+        writeln!(self.out, "{}", format_text(step))
+      }
+      Format::JsonLines => writeln!(self.out, "{}", format_json(step)),
+    }
+  }
+
+  // step the computer `cycles` times, recording each cycle
+  pub fn run(&mut self, computer: &mut Computer, cycles: u64) -> io::Result<()> {
+    for _ in 0..cycles {
+      let step = computer.step();
+      self.record(&step)?;
+    }
+    Ok(())
+  }
+
+  pub fn into_inner(self) -> W {
+    self.out
+  }
+}
+
+pub fn text_header() -> String {
+  format!("{:>7} {:>6}  {:<16} {:>6} {:>7}  zx nx zy ny  f no  zr ng  memory", "cycle", "pc", "instruction", "A", "D")
+}
+
+pub fn format_text(step: &Step) -> String {
+  let controls = step.controls().map(|bit| format!("{:>2}", bit)).join(" ");
+  let mut memory = Vec::new();
+  if let Some(Access { address, value }) = step.read {
+    memory.push(format!("RAM[{}] -> {}", address, value));
+  }
+  if let Some(Access { address, value }) = step.write {
+    memory.push(format!("RAM[{}] <- {}", address, value));
+  }
+  format!(
+    "{:>7} {:>6}  {:<16} {:>6} {:>7}  {}  {:>2} {:>2}  {}",
+    step.cycle,
+    step.pc,
+    Instruction::decode(step.instruction, step.isa).to_string(),
+    step.a,
+    step.d,
+    controls,
+    step.zr,
+    step.ng,
+    memory.join(", ")
+  )
+  .trim_end()
+  .to_string()
+}
+
+pub fn format_json(step: &Step) -> String {
+  let [zx, nx, zy, ny, f, no] = step.controls();
+  format!(
+    "{{\"cycle\":{},\"pc\":{},\"instruction\":\"{}\",\"asm\":\"{}\",\"a\":{},\"d\":{},\
+     \"alu\":{{\"zx\":{},\"nx\":{},\"zy\":{},\"ny\":{},\"f\":{},\"no\":{}}},\"zr\":{},\"ng\":{},\
+     \"read\":{},\"write\":{}}}",
+    step.cycle,
+    step.pc,
+    step.instruction.map(|bit| bit.to_string()).join(""),
+    Instruction::decode(step.instruction, step.isa),
+    step.a,
+    step.d,
+    zx,
+    nx,
+    zy,
+    ny,
+    f,
+    no,
+    step.zr,
+    step.ng,
+    json_access(step.read),
+    json_access(step.write)
+  )
+}
+
+fn json_access(access: Option<Access>) -> String {
+  match access {
+    Some(Access { address, value }) => format!("{{\"address\":{},\"value\":{}}}", address, value),
+    None => "null".to_string(),
+  }
+}
@@ -0,0 +1,209 @@
+#![allow(dead_code)]
+
+use std::io::{self, Write};
+
+use crate::computer::Computer;
+use crate::gates;
+
+/*
+ VALUE CHANGE DUMP (IEEE 1364 `.vcd`)
+
+ $timescale 1ns $end
+ $scope module computer $end
+ $var wire 1 ! clk $end
+ ...
+ $upscope $end
+ $enddefinitions $end
+ #0
+ 0!
+ b101 "
+ #5
+ 1!
+
+ signals are declared with a dotted scope (`computer.cpu`) before the first
+ change, only values that actually change are written.
+*/
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SignalId(usize);
+
+struct Signal {
+  scope: Vec<String>,
+  name: String,
+  width: u32,
+  code: String,
+  value: Option<u64>,
+}
+
+pub struct Writer<W: Write> {
+  out: W,
+  timescale: String,
+  signals: Vec<Signal>,
+  started: bool,
+  time: Option<u64>,
+}
+
+impl<W: Write> Writer<W> {
+  pub fn new(out: W, timescale: &str) -> Self {
+    Self { out, timescale: timescale.to_string(), signals: Vec::new(), started: false, time: None }
+  }
+
+  pub fn signal(&mut self, scope: &str, name: &str, width: u32) -> SignalId {
+    assert!(!self.started, "error: signals must be declared before the first change");
+    assert!((1..=64).contains(&width), "error: signal width must be between 1 and 64");
+    let scope = scope.split('.').filter(|part| !part.is_empty()).map(str::to_string).collect();
+    let code = identifier(self.signals.len());
+    self.signals.push(Signal { scope, name: name.to_string(), width, code, value: None });
+    SignalId(self.signals.len() - 1)
+  }
+
+  pub fn change(&mut self, time: u64, id: SignalId, value: u64) -> io::Result<()> {
+    if !self.started {
+      self.header()?;
+    }
+    let signal = &self.signals[id.0];
+    let value = if signal.width == 64 { value } else { value & ((1 << signal.width) - 1) };
+    if signal.value == Some(value) {
+      return Ok(());
+    }
+    match self.time {
+      Some(current) if time < current => {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "vcd time must not go backwards"));
+      }
+      Some(current) if time == current => {}
+      _ => {
+        writeln!(self.out, "#{}", time)?;
+        self.time = Some(time);
+      }
+    }
+    let signal = &mut self.signals[id.0];
+    if signal.width == 1 {
+      writeln!(self.out, "{}{}", value, signal.code)?;
+    } else {
+      writeln!(self.out, "b{:b} {}", value, signal.code)?;
+    }
+    signal.value = Some(value);
+    Ok(())
+  }
+
+  // close the dump at `time`, so viewers show the last values up to there
+  pub fn finish(mut self, time: u64) -> io::Result<W> {
+    if !self.started {
+      self.header()?;
+    }
+    if self.time.is_none_or(|current| time > current) {
+      writeln!(self.out, "#{}", time)?;
+    }
+    self.out.flush()?;
+    Ok(self.out)
+  }
+
+  fn header(&mut self) -> io::Result<()> {
+    self.started = true;
+    writeln!(self.out, "$version nand-computer $end")?;
+    writeln!(self.out, "$timescale {} $end", self.timescale)?;
+    let mut open: Vec<String> = Vec::new();
+    for signal in &self.signals {
+      let common = open.iter().zip(&signal.scope).take_while(|(a, b)| a == b).count();
+      for _ in common..open.len() {
+        writeln!(self.out, "$upscope $end")?;
+      }
+      open.truncate(common);
+      for scope in &signal.scope[common..] {
+        writeln!(self.out, "$scope module {} $end", scope)?;
+        open.push(scope.clone());
+      }
+      let kind = if signal.width == 1 { "wire" } else { "reg" };
+      let range = if signal.width == 1 { String::new() } else { format!(" [{}:0]", signal.width - 1) };
+      writeln!(self.out, "$var {} {} {} {}{} $end", kind, signal.width, signal.code, signal.name, range)?;
+    }
+    for _ in 0..open.len() {
+      writeln!(self.out, "$upscope $end")?;
+    }
+    writeln!(self.out, "$enddefinitions $end")
+  }
+}
+
+// short identifier codes from the printable ASCII range ('!' .. '~')
+fn identifier(mut index: usize) -> String {
+  let mut code = String::new();
+  loop {
+    code.push((b'!' + (index % 94) as u8) as char);
+    index /= 94;
+    if index == 0 {
+      return code;
+    }
+    index -= 1;
+  }
+}
+
+/*
+ COMPUTER WAVEFORM
+
+ one clock period is 10 time units: combinational signals (instruction, ALU,
+ memory bus) of a cycle change at its start, the rising clock edge at +5 updates
+ the registers (A, D, PC) and commits the memory write.
+
+ the memory signals follow the Hack Memory chip: `in` is the data to write
+ (outM), `out` is M[address], read or not, and shows a write after the edge.
+
+   clk        ___/‾‾‾‾\____/‾‾‾‾\___
+   alu.out    ==X=========X=========
+   cpu.a      ======X=========X=====
+*/
+
+pub const PERIOD: u64 = 10;
+
+pub fn record<W: Write>(computer: &mut Computer, cycles: u64, out: W) -> io::Result<W> {
+  let mut vcd = Writer::new(out, "1ns");
+  let clk = vcd.signal("computer", "clk", 1);
+  let pc = vcd.signal("computer.cpu", "pc", 15);
+  let instruction = vcd.signal("computer.cpu", "instruction", 16);
+  let a = vcd.signal("computer.cpu", "a", 16);
+  let d = vcd.signal("computer.cpu", "d", 16);
+  let x = vcd.signal("computer.cpu.alu", "x", 16);
+  let y = vcd.signal("computer.cpu.alu", "y", 16);
+  let controls = ["zx", "nx", "zy", "ny", "f", "no"].map(|name| vcd.signal("computer.cpu.alu", name, 1));
+  let out = vcd.signal("computer.cpu.alu", "out", 16);
+  let zr = vcd.signal("computer.cpu.alu", "zr", 1);
+  let ng = vcd.signal("computer.cpu.alu", "ng", 1);
+  let address_m = vcd.signal("computer.memory", "address", 15);
+  let in_m = vcd.signal("computer.memory", "in", 16);
+  let out_m = vcd.signal("computer.memory", "out", 16);
+  let write_m = vcd.signal("computer.memory", "load", 1);
+
+  vcd.change(0, pc, computer.pc() as u64)?;
+  vcd.change(0, a, computer.a() as u64)?;
+  vcd.change(0, d, computer.d() as u64)?;
+
+  for _ in 0..cycles {
+    // the memory drives M[A] through the whole cycle until the edge commits a write
+    let m = computer.peek(computer.a() & 0x7fff);
+    let step = computer.step();
+    let start = step.cycle * PERIOD;
+
+    vcd.change(start, clk, 0)?;
+    vcd.change(start, instruction, gates::to_u16(step.instruction) as u64)?;
+    vcd.change(start, x, step.x as u64)?;
+    vcd.change(start, y, step.y as u64)?;
+    for (signal, bit) in controls.iter().zip(step.controls()) {
+      vcd.change(start, *signal, bit as u64)?;
+    }
+    vcd.change(start, out, step.out as u64)?;
+    vcd.change(start, zr, step.zr as u64)?;
+    vcd.change(start, ng, step.ng as u64)?;
+    vcd.change(start, address_m, step.address_m as u64)?;
+    vcd.change(start, in_m, step.out as u64)?;
+    vcd.change(start, out_m, m as u64)?;
+    vcd.change(start, write_m, step.write.is_some() as u64)?;
+
+    let edge = start + PERIOD / 2;
+    vcd.change(edge, clk, 1)?;
+    vcd.change(edge, pc, step.next_pc as u64)?;
+    vcd.change(edge, a, step.a as u64)?;
+    vcd.change(edge, d, step.d as u64)?;
+    vcd.change(edge, out_m, computer.peek(step.address_m) as u64)?;
+  }
+  let end = computer.cycle() * PERIOD;
+  vcd.finish(end)
+}
@@ -0,0 +1,377 @@
+#![allow(dead_code)]
+
+use std::fmt;
+
+use crate::adders::{self, Adder};
+use crate::alu;
+use crate::gates::{self, Bit};
+use crate::netlist::{self, LANES};
+
+/*
+ GATE VERIFICATION
+
+ exhaustive: every input combination of a gate with up to a few 1-bit inputs
+             (2^n vectors), compared with a reference written in plain rust
+ property:   random 16-bit operands from a seeded generator, checked against
+             a property (`not_16(not_16(x)) == x`, add_16 == wrapping_add...)
+
+ sliced:     `exhaustive` for circuits over `netlist::Sliced`, 64 vectors per
+             evaluation, which makes every 8-bit add and every 4-bit ALU
+             operation cheap enough to check in full
+
+ all stop at the first failing vector and report it exactly, `suite` runs
+ every check on the gates of gates.rs and the sliced adders and ALU.
+*/
+
+pub const DEFAULT_CASES: usize = 1000;
+pub const DEFAULT_SEED: u64 = 0x5eed_f00d;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Failure {
+  pub check: String,
+  // the failing input vector, as written by the check
+  pub inputs: String,
+  pub expected: String,
+  pub actual: String,
+}
+
+impl fmt::Display for Failure {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}: inputs {} expected {} got {}", self.check, self.inputs, self.expected, self.actual)
+  }
+}
+
+// every input combination of an `arity`-input gate, inputs in `bits` are
+// listed MSB first: for arity 2 the order is 00, 01, 10, 11
+pub fn exhaustive<O: PartialEq + fmt::Debug>(
+  check: &str,
+  arity: usize,
+  gate: impl Fn(&[Bit]) -> O,
+  reference: impl Fn(&[Bit]) -> O,
+) -> Result<usize, Failure> {
+  let vectors = 1usize << arity;
+  for vector in 0..vectors {
+    let bits: Vec<Bit> = (0..arity).map(|pos| ((vector >> (arity - 1 - pos)) & 1) as Bit).collect();
+    let (actual, expected) = (gate(&bits), reference(&bits));
+    if actual != expected {
+      return Err(Failure {
+        check: check.to_string(),
+        inputs: bits.iter().map(|bit| bit.to_string()).collect::<Vec<_>>().join(" "),
+        expected: format!("{:?}", expected),
+        actual: format!("{:?}", actual),
+      });
+    }
+  }
+  Ok(vectors)
+}
+
+// `exhaustive` with `circuit` evaluating 64 vectors per call, one word per
+// input (see `netlist::pack`) and one word per output
+pub fn exhaustive_sliced(
+  check: &str,
+  arity: usize,
+  circuit: impl Fn(&[u64]) -> Vec<u64>,
+  reference: impl Fn(&[Bit]) -> Vec<Bit>,
+) -> Result<usize, Failure> {
+  let vectors = 1usize << arity;
+  for start in (0..vectors).step_by(LANES) {
+    let chunk: Vec<Vec<Bit>> = (start..vectors.min(start + LANES))
+      .map(|vector| (0..arity).map(|pos| ((vector >> (arity - 1 - pos)) & 1) as Bit).collect())
+      .collect();
+    let actual = netlist::unpack(&circuit(&netlist::pack(&chunk)), chunk.len());
+    for (bits, actual) in chunk.iter().zip(actual) {
+      let expected = reference(bits);
+      if actual != expected {
+        return Err(Failure {
+          check: check.to_string(),
+          inputs: bits.iter().map(|bit| bit.to_string()).collect::<Vec<_>>().join(" "),
+          expected: format!("{:?}", expected),
+          actual: format!("{:?}", actual),
+        });
+      }
+    }
+  }
+  Ok(vectors)
+}
+
+// `cases` random inputs, `property` returns the expected and actual values
+pub fn property<T: fmt::Debug, O: PartialEq + fmt::Debug>(
+  check: &str,
+  cases: usize,
+  rng: &mut Rng,
+  generate: impl Fn(&mut Rng) -> T,
+  property: impl Fn(&T) -> (O, O),
+) -> Result<usize, Failure> {
+  for _ in 0..cases {
+    let input = generate(rng);
+    let (expected, actual) = property(&input);
+    if actual != expected {
+      return Err(Failure {
+        check: check.to_string(),
+        inputs: format!("{:?}", input),
+        expected: format!("{:?}", expected),
+        actual: format!("{:?}", actual),
+      });
+    }
+  }
+  Ok(cases)
+}
+
+// xorshift64*, deterministic for a seed
+#[derive(Clone, Debug)]
+pub struct Rng {
+  state: u64,
+}
+
+impl Rng {
+  pub fn new(seed: u64) -> Self {
+    // the state must never be 0
+    Self { state: seed | 1 }
+  }
+
+  pub fn next_u64(&mut self) -> u64 {
+    self.state ^= self.state >> 12;
+    self.state ^= self.state << 25;
+    self.state ^= self.state >> 27;
+    self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+  }
+
+  pub fn next_u16(&mut self) -> u16 {
+    (self.next_u64() >> 48) as u16
+  }
+
+  pub fn bit(&mut self) -> Bit {
+    (self.next_u64() >> 63) as Bit
+  }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Report {
+  // check name and number of vectors that passed
+  pub passed: Vec<(String, usize)>,
+  pub failures: Vec<Failure>,
+}
+
+impl Report {
+  pub fn record(&mut self, check: &str, result: Result<usize, Failure>) {
+    match result {
+      Ok(vectors) => self.passed.push((check.to_string(), vectors)),
+      Err(failure) => self.failures.push(failure),
+    }
+  }
+
+  pub fn ok(&self) -> bool {
+    self.failures.is_empty()
+  }
+}
+
+impl fmt::Display for Report {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    for (check, vectors) in &self.passed {
+      writeln!(f, "ok    {:<24} {} vectors", check, vectors)?;
+    }
+    for failure in &self.failures {
+      writeln!(f, "FAIL  {}", failure)?;
+    }
+    write!(f, "{} passed, {} failed", self.passed.len(), self.failures.len())
+  }
+}
+
+fn bit(value: bool) -> Bit {
+  value as Bit
+}
+
+// 1-bit gates, mux and dmux are exhaustive, 16-bit gates random
+pub fn suite(cases: usize, seed: u64) -> Report {
+  let mut report = Report::default();
+  exhaustive_gates(&mut report);
+  properties(&mut report, cases, &mut Rng::new(seed));
+  sliced(&mut report);
+  report
+}
+
+fn exhaustive_gates(report: &mut Report) {
+  let gate2 = |gate: fn(Bit, Bit) -> Bit| move |v: &[Bit]| gate(v[0], v[1]);
+  report.record("nand", exhaustive("nand", 2, gate2(gates::nand), |v| bit(!(v[0] == 1 && v[1] == 1))));
+  report.record("not", exhaustive("not", 1, |v| gates::not(v[0]), |v| bit(v[0] == 0)));
+  report.record("and", exhaustive("and", 2, gate2(gates::and), |v| v[0] & v[1]));
+  report.record("or", exhaustive("or", 2, gate2(gates::or), |v| v[0] | v[1]));
+  report.record("xor", exhaustive("xor", 2, gate2(gates::xor), |v| v[0] ^ v[1]));
+  report.record("half_adder", exhaustive("half_adder", 2, |v| gates::half_adder(v[0], v[1]), sum_bits));
+  report.record("full_adder", exhaustive("full_adder", 3, |v| gates::full_adder(v[0], v[1], v[2]), sum_bits));
+  report.record("mux", exhaustive("mux", 3, |v| gates::mux(v[0], v[1], v[2]), |v| v[v[2] as usize]));
+  report.record(
+    "dmux",
+    exhaustive("dmux", 2, |v| <[Bit; 2]>::from(gates::dmux(v[0], v[1])), |v| select::<2>(v[0], v[1] as usize)),
+  );
+  report.record(
+    "dmux4way",
+    // sel is [s0, s1] with s0 the low bit
+    exhaustive(
+      "dmux4way",
+      3,
+      |v| {
+        let (a, b, c, d) = gates::dmux4way(v[0], [v[1], v[2]]);
+        [a, b, c, d]
+      },
+      |v| select::<4>(v[0], (v[2] << 1 | v[1]) as usize),
+    ),
+  );
+  report.record(
+    "dmux8way",
+    // sel is MSB first
+    exhaustive(
+      "dmux8way",
+      4,
+      |v| {
+        let (a, b, c, d, e, f, g, h) = gates::dmux8way(v[0], [v[1], v[2], v[3]]);
+        [a, b, c, d, e, f, g, h]
+      },
+      |v| select::<8>(v[0], (v[1] << 2 | v[2] << 1 | v[3]) as usize),
+    ),
+  );
+  for width in 0..=3 {
+    let name = format!("and_n/or_n/xor_n[{}]", width);
+    report.record(
+      &name,
+      exhaustive(
+        &name,
+        width,
+        |v| (gates::and_n(v), gates::or_n(v), gates::xor_n(v)),
+        |v| (bit(v.iter().all(|&b| b == 1)), bit(v.contains(&1)), bit(v.iter().filter(|&&b| b == 1).count() % 2 == 1)),
+      ),
+    );
+  }
+}
+
+// (sum, carry) of the input bits
+fn sum_bits(bits: &[Bit]) -> (Bit, Bit) {
+  let total: Bit = bits.iter().sum();
+  (total & 1, total >> 1)
+}
+
+// `n` on output `index`, 0 on the others
+fn select<const N: usize>(n: Bit, index: usize) -> [Bit; N] {
+  let mut out = [0; N];
+  out[index] = n;
+  out
+}
+
+fn properties(report: &mut Report, cases: usize, rng: &mut Rng) {
+  let one = |rng: &mut Rng| rng.next_u16();
+  let two = |rng: &mut Rng| (rng.next_u16(), rng.next_u16());
+  let w = gates::from_u16;
+  let u = gates::to_u16;
+
+  report.record("not_16", property("not_16", cases, rng, one, |&x| (x, u(gates::not_16(gates::not_16(w(x)))))));
+  report.record("not_16 = !x", property("not_16 = !x", cases, rng, one, |&x| (!x, u(gates::not_16(w(x))))));
+  report.record("and_16", property("and_16", cases, rng, two, |&(x, y)| (x & y, u(gates::and_16(w(x), w(y))))));
+  report.record("or_16", property("or_16", cases, rng, two, |&(x, y)| (x | y, u(gates::or_16(w(x), w(y))))));
+  report
+    .record("add_16", property("add_16", cases, rng, two, |&(x, y)| (x.wrapping_add(y), u(gates::add_16(w(x), w(y))))));
+  report.record(
+    "add_16_flags",
+    property("add_16_flags", cases, rng, two, |&(x, y)| {
+      let (sum, carry) = x.overflowing_add(y);
+      let overflow = (x as i16).overflowing_add(y as i16).1;
+      ((sum, carry as Bit, overflow as Bit), {
+        let (out, carry, overflow) = gates::add_16_flags(w(x), w(y));
+        (u(out), carry, overflow)
+      })
+    }),
+  );
+  report.record("inc_16", property("inc_16", cases, rng, one, |&x| (x.wrapping_add(1), u(gates::inc_16(w(x))))));
+  report.record(
+    "mux16",
+    property(
+      "mux16",
+      cases,
+      rng,
+      |rng| (rng.next_u16(), rng.next_u16(), rng.bit()),
+      |&(x, y, sel)| (if sel == 1 { y } else { x }, u(gates::mux16(w(x), w(y), sel))),
+    ),
+  );
+  report.record(
+    "mux4way16",
+    property(
+      "mux4way16",
+      cases,
+      rng,
+      |rng| (std::array::from_fn::<u16, 4, _>(|_| rng.next_u16()), [rng.bit(), rng.bit()]),
+      |&(inputs, sel)| {
+        let [a, b, c, d] = inputs.map(w);
+        (inputs[(sel[1] << 1 | sel[0]) as usize], u(gates::mux4way16(a, b, c, d, sel)))
+      },
+    ),
+  );
+  report.record(
+    "mux8way16",
+    property(
+      "mux8way16",
+      cases,
+      rng,
+      |rng| (std::array::from_fn::<u16, 8, _>(|_| rng.next_u16()), [rng.bit(), rng.bit(), rng.bit()]),
+      |&(inputs, sel)| {
+        let [a, b, c, d, e, f, g, h] = inputs.map(w);
+        let index = (sel[0] << 2 | sel[1] << 1 | sel[2]) as usize;
+        (inputs[index], u(gates::mux8way16(a, b, c, d, e, f, g, h, sel)))
+      },
+    ),
+  );
+}
+
+// sliced words as the low bits of a bus, the high bits 0
+fn low_bits(words: &[u64]) -> [u64; 16] {
+  let mut bus = [0; 16];
+  bus[16 - words.len()..].copy_from_slice(words);
+  bus
+}
+
+fn value(bits: &[Bit]) -> u16 {
+  bits.iter().fold(0, |value, &bit| value << 1 | bit as u16)
+}
+
+// every pair of 8-bit operands through each adder, every 4-bit operand pair
+// through every ALU control combination
+fn sliced(report: &mut Report) {
+  for adder in Adder::ALL {
+    let name = format!("add_16[{}]", adder.name());
+    report.record(
+      &name,
+      exhaustive_sliced(
+        &name,
+        16,
+        |v| adders::batch_add_16(adder, low_bits(&v[..8]), low_bits(&v[8..])).to_vec(),
+        |v| gates::from_u16(value(&v[..8]).wrapping_add(value(&v[8..]))).to_vec(),
+      ),
+    );
+  }
+  report.record(
+    "alu",
+    exhaustive_sliced(
+      "alu",
+      14,
+      |v| {
+        let (out, zr, ng) =
+          alu::batch_eval(Adder::Ripple, low_bits(&v[..4]), low_bits(&v[4..8]), v[8..].try_into().unwrap());
+        out.into_iter().chain([zr, ng]).collect()
+      },
+      |v| {
+        let (x, y, c) = (value(&v[..4]), value(&v[4..8]), &v[8..]);
+        // zero, then negate
+        let input = |value: u16, zero: Bit, negate: Bit| {
+          let value = if zero == 1 { 0 } else { value };
+          if negate == 1 {
+            !value
+          } else {
+            value
+          }
+        };
+        let (x, y) = (input(x, c[0], c[1]), input(y, c[2], c[3]));
+        let out = if c[4] == 1 { x.wrapping_add(y) } else { x & y };
+        let out = if c[5] == 1 { !out } else { out };
+        gates::from_u16(out).into_iter().chain([bit(out == 0), bit(out & 0x8000 != 0)]).collect()
+      },
+    ),
+  );
+}
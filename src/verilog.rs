@@ -0,0 +1,1024 @@
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+
+use crate::adders::{self, Adder};
+use crate::alu;
+use crate::cpu;
+use crate::gates::Bit;
+use crate::netlist::{Logic, Netlist, Node, NodeId};
+
+/*
+ VERILOG EXPORT
+
+ `export` writes the whole design as structural Verilog, with the nand2tetris
+ chip names:
+
+ module               | written as
+ ---------------------+--------------------------------------------------
+ DFF                  | the only behavioural leaf: q <= d on posedge clk
+ Not .. Mux8Way16,    | `nand` primitive instances, one per NAND of the
+ HalfAdder, Inc16,    | chip's `Netlist` (CPULogic is `cpu::netlist`,
+ ALU, CPULogic        | the hack instruction set only)
+ FullAdder, Add16,    | instances of the chips above (two half adders and
+ Bit, Register,       | an Or make a full adder, like gates::full_adder)
+ RAM8 .. RAM16K, CPU  |
+ RAM8 .. RAM16K       | `reg` arrays instead, with `behavioural_memory`
+
+ buses are declared [0:15] so bit 0 is the MSB, like Bit16.
+
+ `parse` reads back the subset written here (ports, wires, assign, nand,
+ named-port instances, constants like 1'b0) and `Design::netlist` flattens a
+ module into a `Netlist`. DFF instances are cut open: their q becomes an
+ input "<instance path>.q" and their d an output "<instance path>.d", after
+ the module's own ports, so sequential chips can be stepped cycle by cycle.
+ modules with `reg` / `always` bodies (other than DFF) cannot be flattened.
+*/
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Options {
+  // RAM8 .. RAM16K as `reg` arrays instead of registers and muxes
+  pub behavioural_memory: bool,
+}
+
+const DFF: &str = "module DFF (input clk, input d, output reg q);
+  always @(posedge clk) q <= d;
+endmodule
+";
+
+pub fn export(options: Options) -> String {
+  let mut out = String::from("// nand-computer, structural verilog\n\n");
+  out.push_str(DFF);
+  for (name, netlist) in gate_chips() {
+    out.push('\n');
+    out.push_str(&netlist_module(name, &netlist));
+  }
+  out.push('\n');
+  out.push_str(&full_adder_module());
+  out.push('\n');
+  out.push_str(&add16_module());
+  out.push('\n');
+  out.push_str(&bit_module());
+  out.push('\n');
+  out.push_str(&register_module());
+  for (pos, &(name, ways, bits)) in RAMS.iter().enumerate() {
+    out.push('\n');
+    if options.behavioural_memory {
+      out.push_str(&behavioural_ram(name, bits));
+    } else {
+      let inner = if pos == 0 { None } else { Some(RAMS[pos - 1]) };
+      out.push_str(&ram_module(name, ways, bits, inner.map(|(name, _, bits)| (name, bits))));
+    }
+  }
+  out.push('\n');
+  out.push_str(&cpu_module());
+  out
+}
+
+// (name, mux ways, address bits) from the smallest RAM up
+const RAMS: [(&str, usize, usize); 5] =
+  [("RAM8", 8, 3), ("RAM64", 8, 6), ("RAM512", 8, 9), ("RAM4K", 8, 12), ("RAM16K", 4, 14)];
+
+// every chip written as NAND instances, with its module name
+pub fn gate_chips() -> Vec<(&'static str, Netlist)> {
+  vec![
+    (
+      "Not",
+      chip(|n| {
+        let a = n.input("in");
+        let out = n.not(a);
+        n.output("out", out);
+      }),
+    ),
+    ("And", gate2(|n, a, b| n.and(a, b))),
+    ("Or", gate2(|n, a, b| n.or(a, b))),
+    ("Xor", gate2(|n, a, b| n.xor(a, b))),
+    (
+      "Mux",
+      chip(|n| {
+        let (a, b, sel) = (n.input("a"), n.input("b"), n.input("sel"));
+        let out = n.mux(a, b, sel);
+        n.output("out", out);
+      }),
+    ),
+    (
+      "DMux",
+      chip(|n| {
+        let (input, sel) = (n.input("in"), n.input("sel"));
+        let [a, b] = dmux(n, input, &[sel]).try_into().unwrap();
+        n.output("a", a);
+        n.output("b", b);
+      }),
+    ),
+    (
+      "Not16",
+      chip(|n| {
+        let input = n.input_bus::<16>("in");
+        let out = input.map(|bit| n.not(bit));
+        n.output_bus("out", &out);
+      }),
+    ),
+    ("And16", bus2(|n, a, b| n.and(a, b))),
+    ("Or16", bus2(|n, a, b| n.or(a, b))),
+    (
+      "Mux16",
+      chip(|n| {
+        let (a, b, sel) = (n.input_bus::<16>("a"), n.input_bus::<16>("b"), n.input("sel"));
+        let out = mux16(n, &[a, b], &[sel]);
+        n.output_bus("out", &out);
+      }),
+    ),
+    (
+      "Mux4Way16",
+      chip(|n| {
+        let buses = ["a", "b", "c", "d"].map(|name| n.input_bus::<16>(name));
+        let sel = n.input_bus::<2>("sel");
+        // like gates::mux4way16, sel[1] is the high bit
+        let out = mux16(n, &buses, &[sel[1], sel[0]]);
+        n.output_bus("out", &out);
+      }),
+    ),
+    (
+      "Mux8Way16",
+      chip(|n| {
+        let buses = ["a", "b", "c", "d", "e", "f", "g", "h"].map(|name| n.input_bus::<16>(name));
+        let sel = n.input_bus::<3>("sel");
+        let out = mux16(n, &buses, &sel);
+        n.output_bus("out", &out);
+      }),
+    ),
+    (
+      "DMux4Way",
+      chip(|n| {
+        let (input, sel) = (n.input("in"), n.input_bus::<2>("sel"));
+        for (name, out) in ["a", "b", "c", "d"].into_iter().zip(dmux(n, input, &[sel[1], sel[0]])) {
+          n.output(name, out);
+        }
+      }),
+    ),
+    (
+      "DMux8Way",
+      chip(|n| {
+        let (input, sel) = (n.input("in"), n.input_bus::<3>("sel"));
+        for (name, out) in ["a", "b", "c", "d", "e", "f", "g", "h"].into_iter().zip(dmux(n, input, &sel)) {
+          n.output(name, out);
+        }
+      }),
+    ),
+    (
+      "HalfAdder",
+      chip(|n| {
+        let (a, b) = (n.input("a"), n.input("b"));
+        let (sum, carry) = (n.xor(a, b), n.and(a, b));
+        n.output("sum", sum);
+        n.output("carry", carry);
+      }),
+    ),
+    (
+      "Inc16",
+      chip(|n| {
+        let input = n.input_bus::<16>("in");
+        let (zero, one) = (n.constant(0), n.constant(1));
+        let mut step = [zero; 16];
+        step[15] = one;
+        let (out, _) = adders::add(n, Adder::Ripple, input, step, zero);
+        n.output_bus("out", &out);
+      }),
+    ),
+    ("ALU", alu::netlist(Adder::Ripple)),
+    ("CPULogic", cpu::netlist()),
+  ]
+}
+
+fn chip(build: impl FnOnce(&mut Netlist)) -> Netlist {
+  let mut netlist = Netlist::new();
+  build(&mut netlist);
+  netlist
+}
+
+fn gate2(gate: impl Fn(&mut Netlist, NodeId, NodeId) -> NodeId) -> Netlist {
+  chip(|n| {
+    let (a, b) = (n.input("a"), n.input("b"));
+    let out = gate(n, a, b);
+    n.output("out", out);
+  })
+}
+
+fn bus2(gate: impl Fn(&mut Netlist, NodeId, NodeId) -> NodeId) -> Netlist {
+  chip(|n| {
+    let (a, b) = (n.input_bus::<16>("a"), n.input_bus::<16>("b"));
+    let out: Vec<NodeId> = (0..16).map(|pos| gate(n, a[pos], b[pos])).collect();
+    n.output_bus("out", &out);
+  })
+}
+
+// buses[sel], sel MSB first
+fn mux16<L: Logic>(logic: &mut L, buses: &[[L::Signal; 16]], sel: &[L::Signal]) -> [L::Signal; 16] {
+  match sel {
+    [] => buses[0],
+    [first, rest @ ..] => {
+      let (low, high) = buses.split_at(buses.len() / 2);
+      let (low, high) = (mux16(logic, low, rest), mux16(logic, high, rest));
+      let mut out = low;
+      for pos in 0..16 {
+        out[pos] = logic.mux(low[pos], high[pos], *first);
+      }
+      out
+    }
+  }
+}
+
+// `input` on output sel (MSB first), 0 on the others
+fn dmux<L: Logic>(logic: &mut L, input: L::Signal, sel: &[L::Signal]) -> Vec<L::Signal> {
+  match sel {
+    [] => vec![input],
+    [first, rest @ ..] => {
+      let not_first = logic.not(*first);
+      let (low, high) = (logic.and(input, not_first), logic.and(input, *first));
+      let mut out = dmux(logic, low, rest);
+      out.extend(dmux(logic, high, rest));
+      out
+    }
+  }
+}
+
+// "x[3]" -> ("x", Some(3)), "zr" -> ("zr", None)
+fn split_index(name: &str) -> (&str, Option<usize>) {
+  match name.strip_suffix(']').and_then(|rest| rest.rsplit_once('[')) {
+    Some((base, index)) => (base, index.parse().ok()),
+    None => (name, None),
+  }
+}
+
+// ports in order of first appearance, buses as (name, width)
+fn ports<'a>(names: impl Iterator<Item = &'a str>) -> Vec<(String, Option<usize>)> {
+  let mut ports: Vec<(String, Option<usize>)> = Vec::new();
+  for name in names {
+    let (base, index) = split_index(name);
+    match (ports.last_mut(), index) {
+      (Some((last, Some(width))), Some(index)) if last == base => *width = (*width).max(index + 1),
+      _ => ports.push((base.to_string(), index.map(|index| index + 1))),
+    }
+  }
+  ports
+}
+
+fn declaration(direction: &str, name: &str, width: Option<usize>) -> String {
+  match width {
+    Some(width) => format!("{} [0:{}] {}", direction, width - 1, name),
+    None => format!("{} {}", direction, name),
+  }
+}
+
+// one `nand` instance per NAND node, wires are named after the node ids
+pub fn netlist_module(name: &str, netlist: &Netlist) -> String {
+  let input_name = |id: NodeId| match netlist.node(id) {
+    Node::Input(name) => name.as_str(),
+    _ => unreachable!(),
+  };
+  let net = |id: NodeId| match netlist.node(id) {
+    Node::Input(name) => name.clone(),
+    _ => format!("n{}", id.0),
+  };
+
+  let mut declarations: Vec<String> = ports(netlist.inputs().iter().map(|&id| input_name(id)))
+    .into_iter()
+    .map(|(port, width)| declaration("input", &port, width))
+    .collect();
+  declarations.extend(
+    ports(netlist.outputs().iter().map(|(name, _)| name.as_str()))
+      .into_iter()
+      .map(|(port, width)| declaration("output", &port, width)),
+  );
+  let mut out = format!("module {} (\n  {}\n);\n", name, declarations.join(",\n  "));
+
+  let wires: Vec<String> = (0..netlist.nodes().len())
+    .filter(|&pos| !matches!(netlist.nodes()[pos], Node::Input(_)))
+    .map(|pos| format!("n{}", pos))
+    .collect();
+  for chunk in wires.chunks(16) {
+    out.push_str(&format!("  wire {};\n", chunk.join(", ")));
+  }
+  for (pos, node) in netlist.nodes().iter().enumerate() {
+    match node {
+      Node::Input(_) => {}
+      Node::Const(bit) => out.push_str(&format!("  assign n{} = 1'b{};\n", pos, bit)),
+      Node::Nand(a, b) => out.push_str(&format!("  nand g{} (n{}, {}, {});\n", pos, pos, net(*a), net(*b))),
+    }
+  }
+  for (name, id) in netlist.outputs() {
+    out.push_str(&format!("  assign {} = {};\n", name, net(*id)));
+  }
+  out.push_str("endmodule\n");
+  out
+}
+
+fn full_adder_module() -> String {
+  "module FullAdder (input a, input b, input c, output sum, output carry);
+  wire sum_one, carry_one, carry_two;
+  HalfAdder low (.a(a), .b(b), .sum(sum_one), .carry(carry_one));
+  HalfAdder high (.a(sum_one), .b(c), .sum(sum), .carry(carry_two));
+  Or carry_or (.a(carry_one), .b(carry_two), .out(carry));
+endmodule
+"
+  .to_string()
+}
+
+// a ripple of full adders, a half adder for bit 15 (the LSB)
+fn add16_module() -> String {
+  let mut out =
+    String::from("module Add16 (input [0:15] a, input [0:15] b, output [0:15] out);\n  wire [0:15] carry;\n");
+  out.push_str("  HalfAdder adder15 (.a(a[15]), .b(b[15]), .sum(out[15]), .carry(carry[15]));\n");
+  for pos in (0..15).rev() {
+    out.push_str(&format!(
+      "  FullAdder adder{} (.a(a[{}]), .b(b[{}]), .c(carry[{}]), .sum(out[{}]), .carry(carry[{}]));\n",
+      pos,
+      pos,
+      pos,
+      pos + 1,
+      pos,
+      pos
+    ));
+  }
+  out.push_str("endmodule\n");
+  out
+}
+
+fn bit_module() -> String {
+  "module Bit (input clk, input in, input load, output out);
+  wire d;
+  Mux mux (.a(out), .b(in), .sel(load), .out(d));
+  DFF dff (.clk(clk), .d(d), .q(out));
+endmodule
+"
+  .to_string()
+}
+
+fn register_module() -> String {
+  let mut out = String::from("module Register (input clk, input [0:15] in, input load, output [0:15] out);\n");
+  for pos in 0..16 {
+    out.push_str(&format!("  Bit bit{} (.clk(clk), .in(in[{}]), .load(load), .out(out[{}]));\n", pos, pos, pos));
+  }
+  out.push_str("endmodule\n");
+  out
+}
+
+// a DMux picks the chip to load, a Mux the chip to read; `inner` is the
+// smaller RAM (RAM8 is made of registers)
+fn ram_module(name: &str, ways: usize, bits: usize, inner: Option<(&str, usize)>) -> String {
+  let select = bits - inner.map_or(0, |(_, bits)| bits);
+  let letters = &["a", "b", "c", "d", "e", "f", "g", "h"][..ways];
+  let mut out = format!(
+    "module {} (input clk, input [0:15] in, input load, input [0:{}] address, output [0:15] out);\n",
+    name,
+    bits - 1
+  );
+  let loads: Vec<String> = (0..ways).map(|pos| format!("load{}", pos)).collect();
+  out.push_str(&format!("  wire {};\n", loads.join(", ")));
+  let outs: Vec<String> = (0..ways).map(|pos| format!("out{}", pos)).collect();
+  out.push_str(&format!("  wire [0:15] {};\n", outs.join(", ")));
+  let sel = if select == bits { "address".to_string() } else { format!("address[0:{}]", select - 1) };
+  let dmux: Vec<String> = letters.iter().zip(&loads).map(|(letter, load)| format!(".{}({})", letter, load)).collect();
+  out.push_str(&format!("  DMux{}Way dmux (.in(load), .sel({}), {});\n", ways, sel, dmux.join(", ")));
+  for pos in 0..ways {
+    match inner {
+      Some((chip, _)) => out.push_str(&format!(
+        "  {} ram{} (.clk(clk), .in(in), .load(load{}), .address(address[{}:{}]), .out(out{}));\n",
+        chip,
+        pos,
+        pos,
+        select,
+        bits - 1,
+        pos
+      )),
+      None => out
+        .push_str(&format!("  Register register{} (.clk(clk), .in(in), .load(load{}), .out(out{}));\n", pos, pos, pos)),
+    }
+  }
+  let mux: Vec<String> = letters.iter().zip(&outs).map(|(letter, chip)| format!(".{}({})", letter, chip)).collect();
+  out.push_str(&format!("  Mux{}Way16 mux ({}, .sel({}), .out(out));\n", ways, mux.join(", "), sel));
+  out.push_str("endmodule\n");
+  out
+}
+
+fn behavioural_ram(name: &str, bits: usize) -> String {
+  format!(
+    "module {} (input clk, input [0:15] in, input load, input [0:{}] address, output [0:15] out);
+  reg [0:15] memory [0:{}];
+  always @(posedge clk) if (load) memory[address] <= in;
+  assign out = memory[address];
+endmodule
+",
+    name,
+    bits - 1,
+    (1 << bits) - 1
+  )
+}
+
+fn cpu_module() -> String {
+  "module CPU (
+  input clk,
+  input [0:15] in_m,
+  input [0:15] instruction,
+  input reset,
+  output [0:15] out_m,
+  output write_m,
+  output [0:14] address_m,
+  output [0:14] pc
+);
+  wire [0:15] a, d, pc_out, a_in, pc_next;
+  wire load_a, load_d;
+  CPULogic core (.in_m(in_m), .instruction(instruction), .reset(reset), .a(a), .d(d), .pc(pc_out),
+    .out_m(out_m), .write_m(write_m), .a_in(a_in), .load_a(load_a), .load_d(load_d), .pc_next(pc_next));
+  Register a_register (.clk(clk), .in(a_in), .load(load_a), .out(a));
+  Register d_register (.clk(clk), .in(out_m), .load(load_d), .out(d));
+  Register pc_register (.clk(clk), .in(pc_next), .load(1'b1), .out(pc_out));
+  assign address_m = a[1:15];
+  assign pc = pc_out[1:15];
+endmodule
+"
+  .to_string()
+}
+
+// PARSER
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+  Ident(String),
+  Number(i64),
+  // sized binary constant, MSB first
+  Bits(Vec<Bit>),
+  Symbol(char),
+}
+
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, String> {
+  let mut tokens = Vec::new();
+  let chars: Vec<char> = source.chars().collect();
+  let (mut pos, mut line) = (0, 1);
+  while pos < chars.len() {
+    let c = chars[pos];
+    if c == '\n' {
+      line += 1;
+      pos += 1;
+    } else if c.is_whitespace() {
+      pos += 1;
+    } else if c == '/' && chars.get(pos + 1) == Some(&'/') {
+      while pos < chars.len() && chars[pos] != '\n' {
+        pos += 1;
+      }
+    } else if c == '/' && chars.get(pos + 1) == Some(&'*') {
+      pos += 2;
+      while pos < chars.len() && !(chars[pos] == '*' && chars.get(pos + 1) == Some(&'/')) {
+        line += (chars[pos] == '\n') as usize;
+        pos += 1;
+      }
+      pos += 2;
+    } else if c.is_ascii_alphabetic() || c == '_' {
+      let start = pos;
+      while pos < chars.len() && (chars[pos].is_ascii_alphanumeric() || chars[pos] == '_' || chars[pos] == '$') {
+        pos += 1;
+      }
+      tokens.push((Token::Ident(chars[start..pos].iter().collect()), line));
+    } else if c.is_ascii_digit() {
+      let start = pos;
+      while pos < chars.len() && chars[pos].is_ascii_digit() {
+        pos += 1;
+      }
+      let number: String = chars[start..pos].iter().collect();
+      if chars.get(pos) == Some(&'\'') && chars.get(pos + 1).is_some_and(|c| c.eq_ignore_ascii_case(&'b')) {
+        pos += 2;
+        let start = pos;
+        while pos < chars.len() && matches!(chars[pos], '0' | '1' | '_') {
+          pos += 1;
+        }
+        let bits: Vec<Bit> = chars[start..pos].iter().filter(|&&c| c != '_').map(|&c| (c == '1') as Bit).collect();
+        let width: usize = number.parse().map_err(|_| format!("line {}: bad constant width", line))?;
+        if bits.len() != width {
+          return Err(format!("line {}: constant has {} bits, expected {}", line, bits.len(), width));
+        }
+        tokens.push((Token::Bits(bits), line));
+      } else {
+        tokens.push((Token::Number(number.parse().map_err(|_| format!("line {}: bad number", line))?), line));
+      }
+    } else {
+      tokens.push((Token::Symbol(c), line));
+      pos += 1;
+    }
+  }
+  Ok(tokens)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+  Input,
+  Output,
+}
+
+// a port or wire, `range` as declared ([0:15] -> (0, 15)), None for 1 bit
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Declaration {
+  pub name: String,
+  pub range: Option<(i64, i64)>,
+}
+
+impl Declaration {
+  pub fn width(&self) -> usize {
+    self.range.map_or(1, |(left, right)| ((left - right).unsigned_abs() + 1) as usize)
+  }
+
+  // bit position (0 = leftmost) of a declared index
+  fn position(&self, index: i64) -> Option<usize> {
+    let (left, right) = self.range?;
+    let pos = if left <= right { index - left } else { left - index };
+    (0..self.width() as i64).contains(&pos).then_some(pos as usize)
+  }
+
+  // bit positions of the whole signal or of [left:right], the first index
+  // out of range as the error
+  pub fn select(&self, select: Option<(i64, i64)>) -> Result<Vec<usize>, i64> {
+    let Some((left, right)) = select else {
+      return Ok((0..self.width()).collect());
+    };
+    let step = if left <= right { 1 } else { -1 };
+    let mut out = Vec::new();
+    let mut index = left;
+    loop {
+      out.push(self.position(index).ok_or(index)?);
+      if index == right {
+        return Ok(out);
+      }
+      index += step;
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expr {
+  // a signal, a bit of it or a part ([left:right] as written)
+  Signal { name: String, select: Option<(i64, i64)> },
+  Bits(Vec<Bit>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Item {
+  Assign(Expr, Expr),
+  Nand(Expr, Expr, Expr),
+  Instance { module: String, name: String, connections: Vec<(String, Expr)> },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Module {
+  pub name: String,
+  pub ports: Vec<(Direction, Declaration)>,
+  pub wires: Vec<Declaration>,
+  pub items: Vec<Item>,
+  // had `reg` / `always` / `initial`, the body is not kept
+  pub behavioural: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Design {
+  pub modules: Vec<Module>,
+}
+
+struct Parser {
+  tokens: Vec<(Token, usize)>,
+  pos: usize,
+}
+
+pub fn parse(source: &str) -> Result<Design, String> {
+  let mut parser = Parser { tokens: tokenize(source)?, pos: 0 };
+  let mut design = Design::default();
+  while parser.pos < parser.tokens.len() {
+    design.modules.push(parser.module()?);
+  }
+  Ok(design)
+}
+
+impl Parser {
+  fn line(&self) -> usize {
+    self.tokens.get(self.pos).or(self.tokens.last()).map_or(1, |(_, line)| *line)
+  }
+
+  fn error<T>(&self, message: &str) -> Result<T, String> {
+    Err(format!("line {}: {}", self.line(), message))
+  }
+
+  fn peek(&self) -> Option<&Token> {
+    self.tokens.get(self.pos).map(|(token, _)| token)
+  }
+
+  fn next(&mut self) -> Option<Token> {
+    let token = self.tokens.get(self.pos).map(|(token, _)| token.clone());
+    self.pos += 1;
+    token
+  }
+
+  fn symbol(&mut self, symbol: char) -> Result<(), String> {
+    match self.peek() {
+      Some(Token::Symbol(c)) if *c == symbol => {
+        self.pos += 1;
+        Ok(())
+      }
+      _ => self.error(&format!("expected '{}'", symbol)),
+    }
+  }
+
+  fn at_symbol(&self, symbol: char) -> bool {
+    self.peek() == Some(&Token::Symbol(symbol))
+  }
+
+  fn at_keyword(&self, keyword: &str) -> bool {
+    matches!(self.peek(), Some(Token::Ident(ident)) if ident == keyword)
+  }
+
+  fn ident(&mut self) -> Result<String, String> {
+    match self.peek() {
+      Some(Token::Ident(ident)) => {
+        let ident = ident.clone();
+        self.pos += 1;
+        Ok(ident)
+      }
+      _ => self.error("expected a name"),
+    }
+  }
+
+  fn keyword(&mut self, keyword: &str) -> Result<(), String> {
+    if self.at_keyword(keyword) {
+      self.pos += 1;
+      Ok(())
+    } else {
+      self.error(&format!("expected '{}'", keyword))
+    }
+  }
+
+  fn number(&mut self) -> Result<i64, String> {
+    match self.peek() {
+      Some(Token::Number(number)) => {
+        let number = *number;
+        self.pos += 1;
+        Ok(number)
+      }
+      _ => self.error("expected a number"),
+    }
+  }
+
+  // [left:right]
+  fn range(&mut self) -> Result<Option<(i64, i64)>, String> {
+    if !self.at_symbol('[') {
+      return Ok(None);
+    }
+    self.symbol('[')?;
+    let left = self.number()?;
+    self.symbol(':')?;
+    let right = self.number()?;
+    self.symbol(']')?;
+    Ok(Some((left, right)))
+  }
+
+  fn module(&mut self) -> Result<Module, String> {
+    self.keyword("module")?;
+    let name = self.ident()?;
+    let mut module = Module { name, ports: Vec::new(), wires: Vec::new(), items: Vec::new(), behavioural: false };
+    self.symbol('(')?;
+    while !self.at_symbol(')') {
+      let direction = match self.ident()?.as_str() {
+        "input" => Direction::Input,
+        "output" => Direction::Output,
+        _ => return self.error("expected 'input' or 'output'"),
+      };
+      if self.at_keyword("wire") || self.at_keyword("reg") {
+        self.pos += 1;
+      }
+      let range = self.range()?;
+      module.ports.push((direction, Declaration { name: self.ident()?, range }));
+      if !self.at_symbol(')') {
+        self.symbol(',')?;
+      }
+    }
+    self.symbol(')')?;
+    self.symbol(';')?;
+
+    loop {
+      let Some(Token::Ident(word)) = self.peek().cloned() else {
+        return self.error("expected an item or 'endmodule'");
+      };
+      match word.as_str() {
+        "endmodule" => {
+          self.pos += 1;
+          return Ok(module);
+        }
+        "reg" | "always" | "initial" => {
+          // behavioural: keep the ports only
+          module.behavioural = true;
+          while !self.at_keyword("endmodule") {
+            if self.next().is_none() {
+              return self.error("expected 'endmodule'");
+            }
+          }
+        }
+        "wire" => {
+          self.pos += 1;
+          let range = self.range()?;
+          loop {
+            module.wires.push(Declaration { name: self.ident()?, range });
+            if self.at_symbol(';') {
+              break;
+            }
+            self.symbol(',')?;
+          }
+          self.symbol(';')?;
+        }
+        "assign" => {
+          self.pos += 1;
+          let target = self.expr()?;
+          self.symbol('=')?;
+          let value = self.expr()?;
+          self.symbol(';')?;
+          module.items.push(Item::Assign(target, value));
+        }
+        "nand" => {
+          self.pos += 1;
+          if !self.at_symbol('(') {
+            self.ident()?;
+          }
+          self.symbol('(')?;
+          let out = self.expr()?;
+          self.symbol(',')?;
+          let a = self.expr()?;
+          self.symbol(',')?;
+          let b = self.expr()?;
+          self.symbol(')')?;
+          self.symbol(';')?;
+          module.items.push(Item::Nand(out, a, b));
+        }
+        _ => {
+          self.pos += 1;
+          let name = self.ident()?;
+          let mut connections = Vec::new();
+          self.symbol('(')?;
+          while !self.at_symbol(')') {
+            self.symbol('.')?;
+            let port = self.ident()?;
+            self.symbol('(')?;
+            connections.push((port, self.expr()?));
+            self.symbol(')')?;
+            if !self.at_symbol(')') {
+              self.symbol(',')?;
+            }
+          }
+          self.symbol(')')?;
+          self.symbol(';')?;
+          module.items.push(Item::Instance { module: word, name, connections });
+        }
+      }
+    }
+  }
+
+  fn expr(&mut self) -> Result<Expr, String> {
+    if let Some(Token::Bits(bits)) = self.peek() {
+      let bits = bits.clone();
+      self.pos += 1;
+      return Ok(Expr::Bits(bits));
+    }
+    let name = self.ident()?;
+    if !self.at_symbol('[') {
+      return Ok(Expr::Signal { name, select: None });
+    }
+    self.symbol('[')?;
+    let left = self.number()?;
+    let right = if self.at_symbol(':') {
+      self.symbol(':')?;
+      self.number()?
+    } else {
+      left
+    };
+    self.symbol(']')?;
+    Ok(Expr::Signal { name, select: Some((left, right)) })
+  }
+}
+
+// ELABORATION
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Driver {
+  Undriven,
+  Const(Bit),
+  Nand(usize, usize),
+  Alias(usize),
+  // q of the n-th DFF
+  Dff(usize),
+}
+
+struct Elaborator<'a> {
+  design: &'a Design,
+  drivers: Vec<Driver>,
+  names: Vec<String>,
+  // instance path and d net of every DFF
+  dffs: Vec<(String, usize)>,
+}
+
+// the nets of every signal of one module instance
+type Scope = HashMap<String, (Declaration, Vec<usize>)>;
+
+impl Design {
+  pub fn module(&self, name: &str) -> Option<&Module> {
+    self.modules.iter().find(|module| module.name == name)
+  }
+
+  // flatten `top` into NAND nodes, see the header for the DFF ports
+  pub fn netlist(&self, top: &str) -> Result<Netlist, String> {
+    let module = self.module(top).ok_or_else(|| format!("unknown module '{}'", top))?;
+    let mut elaborator = Elaborator { design: self, drivers: Vec::new(), names: Vec::new(), dffs: Vec::new() };
+    let scope = elaborator.instantiate(module, "", 0)?;
+
+    let mut netlist = Netlist::new();
+    let mut nodes: Vec<Option<NodeId>> = vec![None; elaborator.drivers.len()];
+    for (direction, port) in &module.ports {
+      if *direction == Direction::Input {
+        for (pos, &net) in scope[&port.name].1.iter().enumerate() {
+          nodes[net] = Some(netlist.input(&bit_name(port, pos)));
+        }
+      }
+    }
+    let dff_inputs: Vec<NodeId> =
+      elaborator.dffs.iter().map(|(path, _)| netlist.input(&format!("{}.q", path))).collect();
+
+    for (direction, port) in &module.ports {
+      if *direction == Direction::Output {
+        for (pos, &net) in scope[&port.name].1.iter().enumerate() {
+          let id = elaborator.node(net, &mut netlist, &mut nodes, &dff_inputs, &mut Vec::new())?;
+          netlist.output(&bit_name(port, pos), id);
+        }
+      }
+    }
+    for (path, d) in elaborator.dffs.clone() {
+      let id = elaborator.node(d, &mut netlist, &mut nodes, &dff_inputs, &mut Vec::new())?;
+      netlist.output(&format!("{}.d", path), id);
+    }
+    Ok(netlist)
+  }
+}
+
+// "x[3]" for buses, "zr" for single bits, like `Netlist::input_bus`
+fn bit_name(declaration: &Declaration, pos: usize) -> String {
+  match declaration.range {
+    Some((left, right)) => {
+      let index = if left <= right { left + pos as i64 } else { left - pos as i64 };
+      format!("{}[{}]", declaration.name, index)
+    }
+    None => declaration.name.clone(),
+  }
+}
+
+impl Elaborator<'_> {
+  fn net(&mut self, name: String, driver: Driver) -> usize {
+    self.drivers.push(driver);
+    self.names.push(name);
+    self.drivers.len() - 1
+  }
+
+  fn instantiate(&mut self, module: &Module, path: &str, depth: usize) -> Result<Scope, String> {
+    if module.behavioural {
+      return Err(format!("behavioural module '{}' cannot be flattened", module.name));
+    }
+    if depth > 64 {
+      return Err(format!("module '{}' instantiates itself", module.name));
+    }
+    let mut scope = Scope::new();
+    let declarations = module.ports.iter().map(|(_, declaration)| declaration).chain(&module.wires);
+    for declaration in declarations {
+      let nets = (0..declaration.width())
+        .map(|pos| self.net(format!("{}{}", path, bit_name(declaration, pos)), Driver::Undriven))
+        .collect();
+      if scope.insert(declaration.name.clone(), (declaration.clone(), nets)).is_some() {
+        return Err(format!("{}: '{}' is declared twice", module.name, declaration.name));
+      }
+    }
+
+    for item in &module.items {
+      match item {
+        Item::Assign(target, value) => {
+          let (target, value) = (self.resolve(&scope, target, module)?, self.resolve(&scope, value, module)?);
+          if target.len() != value.len() {
+            return Err(format!("{}: assign of {} bits to {} bits", module.name, value.len(), target.len()));
+          }
+          for (target, value) in target.into_iter().zip(value) {
+            self.drive(target, Driver::Alias(value))?;
+          }
+        }
+        Item::Nand(out, a, b) => {
+          let [out, a, b] = [out, a, b].map(|expr| self.resolve(&scope, expr, module));
+          let (out, a, b) = (out?, a?, b?);
+          if out.len() != 1 || a.len() != 1 || b.len() != 1 {
+            return Err(format!("{}: nand connects single bits only", module.name));
+          }
+          self.drive(out[0], Driver::Nand(a[0], b[0]))?;
+        }
+        Item::Instance { module: chip, name, connections } => {
+          let path = format!("{}{}.", path, name);
+          if chip == "DFF" {
+            self.dff(&scope, module, &path, connections)?;
+            continue;
+          }
+          let child = self.design.module(chip).ok_or_else(|| format!("{}: unknown module '{}'", module.name, chip))?;
+          let child_scope = self.instantiate(child, &path, depth + 1)?;
+          for (port, expr) in connections {
+            let Some((direction, declaration)) = child.ports.iter().find(|(_, declaration)| &declaration.name == port)
+            else {
+              return Err(format!("{}: module '{}' has no port '{}'", module.name, chip, port));
+            };
+            let outer = self.resolve(&scope, expr, module)?;
+            let inner = child_scope[&declaration.name].1.clone();
+            if outer.len() != inner.len() {
+              return Err(format!(
+                "{}: port '{}' of '{}' is {} bits, got {}",
+                module.name,
+                port,
+                name,
+                inner.len(),
+                outer.len()
+              ));
+            }
+            for (outer, inner) in outer.into_iter().zip(inner) {
+              match direction {
+                Direction::Input => self.drive(inner, Driver::Alias(outer))?,
+                Direction::Output => self.drive(outer, Driver::Alias(inner))?,
+              }
+            }
+          }
+        }
+      }
+    }
+    Ok(scope)
+  }
+
+  fn dff(&mut self, scope: &Scope, module: &Module, path: &str, connections: &[(String, Expr)]) -> Result<(), String> {
+    let port = |name: &str| connections.iter().find(|(port, _)| port == name).map(|(_, expr)| expr);
+    let (Some(d), Some(q)) = (port("d"), port("q")) else {
+      return Err(format!("{}: DFF needs d and q", module.name));
+    };
+    let (d, q) = (self.resolve(scope, d, module)?, self.resolve(scope, q, module)?);
+    if d.len() != 1 || q.len() != 1 {
+      return Err(format!("{}: DFF connects single bits only", module.name));
+    }
+    self.dffs.push((path.trim_end_matches('.').to_string(), d[0]));
+    self.drive(q[0], Driver::Dff(self.dffs.len() - 1))
+  }
+
+  fn drive(&mut self, net: usize, driver: Driver) -> Result<(), String> {
+    match self.drivers[net] {
+      Driver::Undriven => {
+        self.drivers[net] = driver;
+        Ok(())
+      }
+      _ => Err(format!("'{}' has more than one driver", self.names[net])),
+    }
+  }
+
+  fn resolve(&mut self, scope: &Scope, expr: &Expr, module: &Module) -> Result<Vec<usize>, String> {
+    match expr {
+      Expr::Bits(bits) => Ok(bits.iter().map(|&bit| self.net(format!("1'b{}", bit), Driver::Const(bit))).collect()),
+      Expr::Signal { name, select } => {
+        let (declaration, nets) =
+          scope.get(name).ok_or_else(|| format!("{}: unknown signal '{}'", module.name, name))?;
+        let positions = declaration
+          .select(*select)
+          .map_err(|index| format!("{}: '{}[{}]' is out of range", module.name, name, index))?;
+        Ok(positions.into_iter().map(|pos| nets[pos]).collect())
+      }
+    }
+  }
+
+  // the netlist node of a net, following aliases; `path` catches loops
+  fn node(
+    &self,
+    net: usize,
+    netlist: &mut Netlist,
+    nodes: &mut Vec<Option<NodeId>>,
+    dff_inputs: &[NodeId],
+    path: &mut Vec<usize>,
+  ) -> Result<NodeId, String> {
+    if let Some(id) = nodes[net] {
+      return Ok(id);
+    }
+    if path.contains(&net) {
+      return Err(format!("combinational loop through '{}'", self.names[net]));
+    }
+    path.push(net);
+    let id = match self.drivers[net] {
+      Driver::Undriven => return Err(format!("'{}' is not driven", self.names[net])),
+      Driver::Const(bit) => netlist.constant(bit),
+      Driver::Alias(other) => self.node(other, netlist, nodes, dff_inputs, path)?,
+      Driver::Dff(index) => dff_inputs[index],
+      Driver::Nand(a, b) => {
+        let a = self.node(a, netlist, nodes, dff_inputs, path)?;
+        let b = self.node(b, netlist, nodes, dff_inputs, path)?;
+        netlist.nand(a, b)
+      }
+    };
+    path.pop();
+    nodes[net] = Some(id);
+    Ok(id)
+  }
+}
@@ -0,0 +1,85 @@
+#[cfg(test)]
+mod tests {
+  use nand_computer::{
+    adders::{self, Adder},
+    alu::ALU,
+    gates,
+  };
+
+  // a spread of operands: every corner plus a walk through the 16-bit space
+  fn operands() -> Vec<u16> {
+    let mut values = vec![0, 1, 2, 0x7fff, 0x8000, 0xfffe, 0xffff, 0x00ff, 0xff00, 0x5555, 0xaaaa];
+    values.extend((0..64u32).map(|i| (i * 0x9e37 + 0x1234) as u16));
+    values
+  }
+
+  #[test]
+  fn test_every_adder_matches_add_16() {
+    for adder in Adder::ALL {
+      for &a in &operands() {
+        for &b in &operands() {
+          let (a, b) = (gates::from_u16(a), gates::from_u16(b));
+          assert_eq!(adders::add_16(adder, a, b), gates::add_16(a, b), "{} {:?} {:?}", adder, a, b);
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn test_netlists_compute_the_sum() {
+    for adder in Adder::ALL {
+      let netlist = adders::netlist(adder);
+      for (a, b) in [(0u16, 0u16), (1, 0xffff), (0x1234, 0x4321), (0x8000, 0x8000), (0x7fff, 1)] {
+        let inputs: Vec<u8> = gates::from_u16(a).into_iter().chain(gates::from_u16(b)).collect();
+        let sum: [u8; 16] = netlist.eval(&inputs).try_into().unwrap();
+        assert_eq!(gates::to_u16(sum), a.wrapping_add(b), "{}", adder);
+      }
+    }
+  }
+
+  #[test]
+  fn test_reports() {
+    let [ripple, lookahead, kogge_stone, select] = Adder::ALL.map(adders::report);
+
+    // 16 full adders of 2 xor (4 nands), 2 and (2), 1 or (3)
+    assert_eq!(ripple.nands, 16 * 15);
+    assert!(lookahead.depth < ripple.depth);
+    assert!(kogge_stone.depth < lookahead.depth);
+    assert!(select.depth < ripple.depth);
+    assert!(select.nands > ripple.nands);
+
+    let table = adders::report_table();
+    assert!(table.starts_with("adder"));
+    assert!(table.contains(&format!("kogge-stone      | {:>5} | {:>5}", kogge_stone.nands, kogge_stone.depth)));
+  }
+
+  #[test]
+  fn test_parse_adder() {
+    assert_eq!("carry-select".parse::<Adder>(), Ok(Adder::CarrySelect));
+    assert!("bogus".parse::<Adder>().unwrap_err().contains("bogus"));
+  }
+
+  #[test]
+  fn test_alu_uses_selected_adder() {
+    for adder in Adder::ALL {
+      // x - y: nx, f, no
+      let mut alu = ALU::new(gates::from_u16(1000), gates::from_u16(1234));
+      alu.set_adder(adder);
+      alu.set_controls([0, 1, 0, 0, 1, 1]);
+      let (out, zr, ng) = alu.execute();
+      assert_eq!((gates::to_u16(out) as i16, zr, ng), (-234, 0, 1), "{}", adder);
+    }
+  }
+
+  #[test]
+  fn test_every_adder_reports_flags() {
+    for adder in Adder::ALL {
+      for &a in &operands() {
+        for &b in &operands() {
+          let (a, b) = (gates::from_u16(a), gates::from_u16(b));
+          assert_eq!(adders::add_16_flags(adder, a, b), gates::add_16_flags(a, b), "{}", adder);
+        }
+      }
+    }
+  }
+}
@@ -1,7 +1,8 @@
 #[cfg(test)]
 mod tests {
   use nand_computer::{
-    alu::ALU,
+    adders::Adder,
+    alu::{ExtOp, ALU},
     gates::{self, Bit16},
   };
 
@@ -183,4 +184,50 @@ mod tests {
     assert_eq!(zr, 0);
     assert_eq!(ng, 1);
   }
+
+  #[test]
+  fn test_carry_and_overflow_flags() {
+    // (x, y, controls, out, c, v)
+    let cases: [(u16, u16, [u8; 6], u16, u8, u8); 5] = [
+      // x+y
+      (0x7fff, 1, [0, 0, 0, 0, 1, 0], 0x8000, 0, 1),
+      (0xffff, 1, [0, 0, 0, 0, 1, 0], 0, 1, 0),
+      // x-y
+      (0x8000, 1, [0, 1, 0, 0, 1, 1], 0x7fff, 0, 1),
+      (5, 3, [0, 1, 0, 0, 1, 1], 2, 0, 0),
+      // x&y never sets them
+      (0xffff, 0xffff, [0, 0, 0, 0, 0, 0], 0xffff, 0, 0),
+    ];
+    for (x, y, controls, out, c, v) in cases {
+      let mut alu = ALU::new(gates::from_u16(x), gates::from_u16(y));
+      alu.set_controls(controls);
+      let (result, flags) = alu.execute_flags();
+      assert_eq!(gates::to_u16(result), out);
+      assert_eq!((flags.c, flags.v), (c, v), "{:#x} {:?} {:#x}", x, controls, y);
+      assert_eq!((flags.zr, flags.ng), ((out == 0) as u8, (out >> 15) as u8));
+    }
+  }
+
+  #[test]
+  fn test_flag_ops_on_every_adder() {
+    let pairs: [(u16, u16); 5] = [(0x7fff, 1), (0xffff, 1), (0x8000, 1), (5, 3), (3, 5)];
+    for adder in Adder::ALL {
+      for (x, y) in pairs {
+        let expected = [
+          (ExtOp::AddCarry, x.overflowing_add(y).1),
+          (ExtOp::SubBorrow, x < y),
+          (ExtOp::AddOverflow, (x as i16).overflowing_add(y as i16).1),
+          (ExtOp::SubOverflow, (x as i16).overflowing_sub(y as i16).1),
+        ];
+        for (op, flag) in expected {
+          let mut alu = ALU::new(gates::from_u16(x), gates::from_u16(y));
+          alu.set_adder(adder);
+          alu.set_controls([0, 0, 0, 0, 0, 0]);
+          alu.set_ext(Some(op));
+          let (out, _, _) = alu.execute();
+          assert_eq!(gates::to_u16(out), flag as u16, "{} {:?} {:#x} {:#x}", adder.name(), op, x, y);
+        }
+      }
+    }
+  }
 }
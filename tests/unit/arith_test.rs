@@ -0,0 +1,75 @@
+#[cfg(test)]
+mod tests {
+  use nand_computer::{
+    alu::{ExtOp, ALU},
+    arith, gates,
+  };
+
+  // xorshift, so the random operands are the same on every run
+  fn operands(count: usize) -> Vec<(u16, u16)> {
+    let mut state: u32 = 0x2545_f491;
+    let mut next = move || {
+      state ^= state << 13;
+      state ^= state >> 17;
+      state ^= state << 5;
+      state as u16
+    };
+    let mut pairs = vec![(0, 0), (0xffff, 0xffff), (0x8000, 2), (1234, 1), (7, 0xffff), (100, 7)];
+    pairs.extend((0..count).map(|_| (next(), next())));
+    pairs
+  }
+
+  #[test]
+  fn test_mul_16() {
+    for (a, b) in operands(300) {
+      let out = arith::mul_16(gates::from_u16(a), gates::from_u16(b));
+      assert_eq!(gates::to_u16(out), a.wrapping_mul(b), "{} * {}", a, b);
+      // signed operands give the same low 16 bits
+      assert_eq!(gates::to_u16(out) as i16, (a as i16).wrapping_mul(b as i16));
+    }
+  }
+
+  #[test]
+  fn test_div_16() {
+    for (a, b) in operands(300) {
+      // small divisors too, random ones are mostly larger than a
+      for b in [b, b >> 8, b >> 12] {
+        if b == 0 {
+          continue;
+        }
+        let (quotient, remainder) = arith::div_16(gates::from_u16(a), gates::from_u16(b));
+        assert_eq!((gates::to_u16(quotient), gates::to_u16(remainder)), (a / b, a % b), "{} / {}", a, b);
+      }
+    }
+  }
+
+  #[test]
+  fn test_div_by_zero() {
+    let (quotient, remainder) = arith::div_16(gates::from_u16(1234), [0; 16]);
+    assert_eq!((gates::to_u16(quotient), gates::to_u16(remainder)), (0xffff, 1234));
+  }
+
+  #[test]
+  fn test_extended_alu() {
+    for (op, expected) in [(ExtOp::Mul, 600u16), (ExtOp::Div, 6), (ExtOp::Rem, 0)] {
+      let mut alu = ALU::new(gates::from_u16(60), gates::from_u16(10));
+      alu.set_ext(Some(op));
+      let (out, zr, _) = alu.execute();
+      assert_eq!(gates::to_u16(out), expected, "{:?}", op);
+      assert_eq!(zr, (expected == 0) as u8);
+    }
+
+    // no still negates the product
+    let mut alu = ALU::new(gates::from_u16(6), gates::from_u16(7));
+    alu.set_ext(Some(ExtOp::Mul));
+    alu.set_no(1);
+    let (out, _, ng) = alu.execute();
+    assert_eq!((gates::to_u16(out), ng), (!42, 1));
+
+    // back to the stock ALU
+    alu.set_ext(None);
+    alu.set_no(0);
+    alu.set_f(1);
+    assert_eq!(gates::to_u16(alu.execute().0), 13);
+  }
+}
@@ -0,0 +1,150 @@
+#[cfg(test)]
+mod tests {
+  use nand_computer::{
+    asm, gates,
+    instruction::{Group, Instruction, Isa},
+    mem::rom32k,
+  };
+
+  const MAX_ASM: &str = "
+    // RAM[2] = max(RAM[0], RAM[1])
+    @R0
+    D=M
+    @R1
+    D=D-M
+    @OUTPUT_FIRST
+    D;JGT
+    @R1
+    D=M
+    @OUTPUT_D
+    0;JMP
+  (OUTPUT_FIRST)
+    @R0
+    D=M
+  (OUTPUT_D)
+    @R2
+    M=D
+  (INFINITE_LOOP)
+    @INFINITE_LOOP
+    0;JMP
+  ";
+
+  const MAX_HACK: &str = "
+    0000000000000000
+    1111110000010000
+    0000000000000001
+    1111010011010000
+    0000000000001010
+    1110001100000001
+    0000000000000001
+    1111110000010000
+    0000000000001100
+    1110101010000111
+    0000000000000000
+    1111110000010000
+    0000000000000010
+    1110001100001000
+    0000000000001110
+    1110101010000111
+  ";
+
+  #[test]
+  fn test_assemble_program() {
+    let program = asm::assemble(MAX_ASM).unwrap();
+
+    assert_eq!(program.words, rom32k::parse_hack(MAX_HACK).unwrap());
+    assert_eq!(program.symbols.label("OUTPUT_FIRST"), Some(10));
+    assert_eq!(program.symbols.label("INFINITE_LOOP"), Some(14));
+    assert_eq!(program.symbols.label_at(12), Some("OUTPUT_D"));
+  }
+
+  #[test]
+  fn test_variables() {
+    let program = asm::assemble("@i\nM=1\n@sum\nM=0\n@i\nD=M\n@SCREEN\n").unwrap();
+
+    assert_eq!(program.symbols.variable("i"), Some(16));
+    assert_eq!(program.symbols.variable("sum"), Some(17));
+    assert_eq!(program.words[4], program.words[0]);
+    assert_eq!(program.symbols.variable("SCREEN"), Some(16384));
+  }
+
+  #[test]
+  fn test_comp_forms() {
+    // commuted operands encode like their canonical form
+    assert_eq!(asm::encode_comp("A+D"), asm::encode_comp("D+A"));
+    assert_eq!(asm::encode_comp("M|D"), Some((1, [0, 1, 0, 1, 0, 1])));
+    assert_eq!(asm::encode_comp("D+M"), Some((1, [0, 0, 0, 0, 1, 0])));
+    assert_eq!(asm::encode_comp("A+M"), None);
+  }
+
+  #[test]
+  fn test_errors() {
+    assert!(asm::assemble("@32768").is_err());
+    assert!(asm::assemble("D=D*A").is_err());
+    assert!(asm::assemble("X=D").is_err());
+    assert!(asm::assemble("0;JUMP").is_err());
+    assert!(asm::assemble("(LOOP)\n(LOOP)\n").is_err());
+    assert!(asm::assemble("(1LOOP)").is_err());
+  }
+
+  #[test]
+  fn test_program_fits_in_rom() {
+    assert_eq!(asm::assemble(&"D=0\n".repeat(32768)).unwrap().words.len(), 32768);
+    assert_eq!(
+      asm::assemble(&"D=0\n".repeat(32769)).unwrap_err(),
+      "line 32769: program does not fit in ROM32K (32768 words)"
+    );
+  }
+
+  #[test]
+  fn test_shift_instructions() {
+    let source = "D=D<<\nA=A>>\nM=M<<\nD=D<<A\nAM=M>>>D\nD=ROL(D,M);JEQ";
+    let program = asm::assemble_isa(source, Isa::HackExt).unwrap();
+    let words: Vec<String> = program.words.iter().map(|word| word.map(|bit| bit.to_string()).join("")).collect();
+
+    // the by-1 forms match the common nand2tetris shift extension
+    assert_eq!(words[0], "1010110000010000");
+    assert_eq!(words[1], "1010000000100000");
+    assert_eq!(words[2], "1011100000001000");
+    assert_eq!(words[3], "1010110010010000");
+    assert_eq!(words[4], "1011000110101000");
+    assert_eq!(words[5], "1011111010010010");
+
+    assert_eq!(asm::encode_ext("ROL(D,A)"), Some((Group::Shift, 0, [1, 1, 1, 0, 1, 0])));
+    assert_eq!(asm::encode_ext("D<<M"), Some((Group::Shift, 1, [1, 1, 0, 0, 1, 0])));
+    assert_eq!(asm::encode_ext("A<<M"), None);
+    assert!(asm::assemble_isa("D=D<<<A", Isa::HackExt).is_err());
+  }
+
+  #[test]
+  fn test_ext_needs_profile() {
+    let error = asm::assemble("@2\nD=D*A").unwrap_err();
+    assert_eq!(error, "line 2: 'D*A' needs the hack-ext instruction set");
+    assert!(asm::assemble("D=C(D+A)").is_err());
+
+    let program = asm::assemble_isa("D=D*M\nD=C(D+A)", Isa::HackExt).unwrap();
+    assert_eq!(program.words[0][..10], [1, 1, 0, 1, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(program.words[1][..10], [1, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+
+    // stock programs encode the same for both profiles
+    let stock = "@5\nD=A\n(LOOP)\nD=D-1;JGT\nM=D|M\n0;JMP";
+    assert_eq!(asm::assemble_isa(stock, Isa::HackExt), asm::assemble(stock));
+  }
+
+  #[test]
+  fn test_disassemble_round_trip() {
+    for group in [Group::Alu, Group::Shift, Group::MulDiv, Group::Flag] {
+      for (_, mnemonic) in group.table() {
+        for comp in [mnemonic.to_string(), mnemonic.replace('A', "M")] {
+          let program = asm::assemble_isa(&format!("D={}", comp), Isa::HackExt).unwrap();
+          assert_eq!(Instruction::decode(program.words[0], Isa::HackExt).to_string(), format!("D={}", comp));
+        }
+      }
+    }
+
+    // the stock profile ignores bits 14-13: D<< runs as D=A
+    let program = asm::assemble_isa("D=D<<", Isa::HackExt).unwrap();
+    assert_eq!(Instruction::decode(program.words[0], Isa::Hack).to_string(), "D=A");
+    assert_eq!(Instruction::decode(gates::from_u16(0xa7f0), Isa::HackExt).to_string(), "AD=?010011111");
+  }
+}
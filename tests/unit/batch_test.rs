@@ -0,0 +1,86 @@
+#[cfg(test)]
+mod tests {
+  use std::time::Instant;
+
+  use nand_computer::{
+    asm,
+    batch::{self, Job, Outcome},
+    computer::Computer,
+    history::History,
+  };
+
+  // sums n..1 into R1
+  fn sum_to(n: u16) -> Job {
+    let source = format!(
+      "@{}\nD=A\n@R0\nM=D\n(LOOP)\n@R0\nD=M\n@END\nD;JEQ\n@R1\nM=D+M\n@R0\nM=M-1\n@LOOP\n0;JMP\n(END)\n@END\n0;JMP\n",
+      n
+    );
+    Job::new(asm::assemble(&source).unwrap().words, 40)
+  }
+
+  #[test]
+  fn test_map_keeps_item_order() {
+    let items: Vec<u64> = (0..37).collect();
+    let expected: Vec<u64> = items.iter().map(|item| item * item).collect();
+    for threads in [0, 1, 2, 3, 8, 64] {
+      assert_eq!(batch::map(&items, threads, |item| item * item), expected, "{} threads", threads);
+    }
+    assert!(batch::map(&[] as &[u64], 4, |item| *item).is_empty());
+    assert!(batch::threads() >= 1);
+  }
+
+  #[test]
+  fn test_batch_matches_single_machines() {
+    let jobs: Vec<Job> = (0..6).map(sum_to).collect();
+    let outcomes = batch::run(&jobs, 1);
+    assert_eq!(batch::run(&jobs, 4), outcomes);
+    for (job, outcome) in jobs.iter().zip(&outcomes) {
+      let mut computer = Computer::new(job.program.clone());
+      computer.run(job.cycles);
+      assert_eq!(*outcome, Outcome::of(&mut computer, 0..16));
+      assert_eq!(outcome.cycle, 40);
+      assert_eq!(outcome.memory.len(), 16);
+    }
+    // 3 + 2 + 1 finishes within 40 cycles
+    assert_eq!(outcomes[3].memory[..2], [0, 6]);
+  }
+
+  #[test]
+  fn test_spare_threads_tick_the_ram() {
+    // one job on four threads: its RAM4Ks tick on three workers
+    let jobs = vec![sum_to(3)];
+    assert_eq!(batch::run(&jobs, 4), batch::run(&jobs, 1));
+
+    // stores to one word in each RAM4K, then rewinds over them
+    let source = "@7\nD=A\n@100\nM=D\n@4200\nM=D+1\n@8300\nM=D-1\n@12400\nM=-D\n@4200\nD=M\n";
+    let program = asm::assemble(source).unwrap().words;
+    let run = |threads| {
+      let mut computer = Computer::new(program.clone());
+      computer.set_ram_threads(threads);
+      computer.record_history(History::new(4, 4));
+      computer.run(12);
+      let ram = [100, 4200, 8300, 12400].map(|address| computer.peek(address));
+      computer.rewind(9).unwrap();
+      (ram, computer.peek(8300), computer.peek(12400), computer.d())
+    };
+    let serial = run(1);
+    assert_eq!(serial, ([7, 8, 6, 65529], 6, 0, 7));
+    for threads in 2..=4 {
+      assert_eq!(run(threads), serial, "{} threads", threads);
+    }
+  }
+
+  #[test]
+  fn test_threads_are_not_slower() {
+    let jobs: Vec<Job> = (0..4).map(|n| Job { cycles: 5, ..sum_to(n) }).collect();
+    let start = Instant::now();
+    let serial = batch::run(&jobs, 1);
+    let serial_time = start.elapsed();
+    let start = Instant::now();
+    let parallel = batch::run(&jobs, 4);
+    let parallel_time = start.elapsed();
+    assert_eq!(parallel, serial);
+    // even on a single core the workers only add a few thread spawns
+    assert!(parallel_time <= serial_time * 3 / 2, "{:?} with 4 threads, {:?} with 1", parallel_time, serial_time);
+  }
+}
@@ -0,0 +1,90 @@
+#[cfg(test)]
+mod tests {
+  use nand_computer::{
+    adders::{self, Adder},
+    alu::{self, ALU},
+    bdd::{self, Bdd, Equivalence},
+    gates,
+    netlist::{Logic, Netlist},
+  };
+
+  #[test]
+  fn test_equal_functions_share_a_node() {
+    let mut bdd = Bdd::new();
+    let (a, b) = (bdd.var(0), bdd.var(1));
+    let xor = bdd.xor(a, b);
+    let (not_a, not_b) = (bdd.not(a), bdd.not(b));
+    let (left, right) = (bdd.and(a, not_b), bdd.and(not_a, b));
+    assert_eq!(bdd.or(left, right), xor);
+    assert_eq!(bdd.and(a, not_a), bdd::FALSE);
+    assert_eq!(bdd.or(a, not_a), bdd::TRUE);
+    assert_eq!(bdd.satisfy(xor, 2).map(|assignment| bdd.eval(xor, &assignment)), Some(1));
+    assert_eq!(bdd.satisfy(bdd::FALSE, 2), None);
+  }
+
+  #[test]
+  fn test_every_adder_is_equivalent_to_ripple() {
+    let ripple = adders::netlist(Adder::Ripple);
+    for adder in Adder::ALL {
+      assert_eq!(bdd::equivalent(&ripple, &adders::netlist(adder)), Ok(Equivalence::Equivalent), "{}", adder);
+    }
+  }
+
+  #[test]
+  fn test_alu_is_equivalent_with_every_adder() {
+    let ripple = alu::netlist(Adder::Ripple);
+    for adder in Adder::ALL {
+      assert_eq!(bdd::equivalent(&ripple, &alu::netlist(adder)), Ok(Equivalence::Equivalent), "{}", adder);
+    }
+  }
+
+  #[test]
+  fn test_alu_netlist_matches_the_alu() {
+    let netlist = alu::netlist(Adder::Ripple);
+    for controls in [[0, 0, 0, 0, 1, 0], [0, 1, 0, 0, 1, 1], [0, 1, 0, 1, 0, 1], [1, 1, 1, 0, 1, 0]] {
+      for (x, y) in [(0u16, 0u16), (5, 3), (0x8000, 1), (0x1234, 0xfedc)] {
+        let (x, y) = (gates::from_u16(x), gates::from_u16(y));
+        let mut chip = ALU::new(x, y);
+        chip.set_controls(controls);
+        let (out, zr, ng) = chip.execute();
+        let inputs: Vec<u8> = x.into_iter().chain(y).chain(controls).collect();
+        let mut expected = out.to_vec();
+        expected.extend([zr, ng]);
+        assert_eq!(netlist.eval(&inputs), expected, "{:?}", controls);
+      }
+    }
+  }
+
+  // a ripple adder whose bit 7 forgets the carry coming in
+  fn broken_adder() -> Netlist {
+    let mut netlist = Netlist::new();
+    let a = netlist.input_bus::<16>("a");
+    let b = netlist.input_bus::<16>("b");
+    let carry = netlist.constant(0);
+    let (mut sum, _) = adders::add(&mut netlist, Adder::Ripple, a, b, carry);
+    sum[8] = netlist.xor(a[8], b[8]);
+    netlist.output_bus("sum", &sum);
+    netlist
+  }
+
+  #[test]
+  fn test_counterexample_shows_the_difference() {
+    let (good, bad) = (adders::netlist(Adder::KoggeStone), broken_adder());
+    let Ok(Equivalence::Different { inputs, output, left, right }) = bdd::equivalent(&good, &bad) else {
+      panic!("the broken adder should differ");
+    };
+    assert_eq!(output, "sum[8]");
+    assert_ne!(left, right);
+    assert_eq!(good.eval(&inputs)[8], left);
+    assert_eq!(bad.eval(&inputs)[8], right);
+  }
+
+  #[test]
+  fn test_shapes_must_match() {
+    let mut netlist = Netlist::new();
+    let a = netlist.input("a");
+    netlist.output("a", a);
+    assert!(bdd::equivalent(&netlist, &adders::netlist(Adder::Ripple)).is_err());
+    assert!(bdd::equivalent_with_order(&netlist, &netlist, &[0, 0]).is_err());
+  }
+}
@@ -0,0 +1,134 @@
+#[cfg(test)]
+mod tests {
+  use nand_computer::{asm, computer::Computer, instruction::Isa, mem::rom32k};
+
+  // RAM[0] = 2 + 3
+  const ADD: &str = "
+    0000000000000010
+    1110110000010000
+    0000000000000011
+    1110000010010000
+    0000000000000000
+    1110001100001000
+  ";
+
+  // RAM[2] = max(RAM[0], RAM[1])
+  const MAX: &str = "
+    0000000000000000
+    1111110000010000
+    0000000000000001
+    1111010011010000
+    0000000000001010
+    1110001100000001
+    0000000000000001
+    1111110000010000
+    0000000000001100
+    1110101010000111
+    0000000000000000
+    1111110000010000
+    0000000000000010
+    1110001100001000
+    0000000000001110
+    1110101010000111
+  ";
+
+  #[test]
+  fn test_add_program() {
+    let mut computer = Computer::new(rom32k::parse_hack(ADD).unwrap());
+
+    computer.run(6);
+
+    assert_eq!(computer.peek(0), 5);
+    assert_eq!(computer.pc(), 6);
+  }
+
+  #[test]
+  fn test_max_program() {
+    let program = rom32k::parse_hack(MAX).unwrap();
+
+    let mut computer = Computer::new(program.clone());
+    computer.poke(0, 3);
+    computer.poke(1, 9);
+    computer.run(14);
+    assert_eq!(computer.peek(2), 9);
+
+    let mut computer = Computer::new(program);
+    computer.poke(0, 12);
+    computer.poke(1, 9);
+    computer.run(14);
+    assert_eq!(computer.peek(2), 12);
+  }
+
+  #[test]
+  fn test_step_reports_memory_access() {
+    let mut computer = Computer::new(rom32k::parse_hack(MAX).unwrap());
+    computer.poke(0, 7);
+
+    computer.step(); // @0
+    let step = computer.step(); // D=M
+
+    let read = step.read.expect("D=M should read RAM[0]");
+    assert_eq!((read.address, read.value), (0, 7));
+    assert_eq!(step.write, None);
+    assert_eq!(step.d, 7);
+  }
+
+  #[test]
+  fn test_reset_keeps_memory() {
+    let mut computer = Computer::new(rom32k::parse_hack(ADD).unwrap());
+
+    computer.run(6);
+    computer.reset();
+
+    assert_eq!(computer.pc(), 0);
+    assert_eq!(computer.peek(0), 5);
+  }
+
+  #[test]
+  fn test_parse_hack_rejects_invalid_words() {
+    assert!(rom32k::parse_hack("0101").is_err());
+    assert!(rom32k::parse_hack("000000000000000x").is_err());
+  }
+
+  #[test]
+  fn test_stock_programs_run_the_same_on_both_profiles() {
+    let program = rom32k::parse_hack(MAX).unwrap();
+    let mut hack = Computer::new(program.clone());
+    let mut ext = Computer::new(program);
+    ext.set_isa(Isa::HackExt);
+    for computer in [&mut hack, &mut ext] {
+      computer.poke(0, 17);
+      computer.poke(1, 42);
+    }
+
+    for _ in 0..16 {
+      let (stock, extended) = (hack.step(), ext.step());
+      assert_eq!(extended, nand_computer::computer::Step { isa: Isa::HackExt, ..stock });
+    }
+    assert_eq!((hack.peek(2), ext.peek(2)), (42, 42));
+  }
+
+  #[test]
+  fn test_32_bit_add_with_carry() {
+    // R2:R3 = R0:R1 + 1 (high:low words)
+    let source = "
+      @R1
+      D=M
+      @R3
+      M=D+1
+      @1
+      D=C(D+A)
+      @R0
+      D=D+M
+      @R2
+      M=D
+    ";
+    let mut computer = Computer::new(asm::assemble_isa(source, Isa::HackExt).unwrap().words);
+    computer.set_isa(Isa::HackExt);
+    computer.poke(0, 0x0001);
+    computer.poke(1, 0xffff);
+    computer.run(10);
+
+    assert_eq!((computer.peek(2), computer.peek(3)), (0x0002, 0x0000));
+  }
+}
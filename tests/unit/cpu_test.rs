@@ -0,0 +1,163 @@
+#[cfg(test)]
+mod tests {
+  use nand_computer::{cpu::CPU, gates, instruction::Isa};
+
+  const NO_M: [u8; 16] = [0; 16];
+
+  fn word(bits: &str) -> [u8; 16] {
+    let mut out = [0; 16];
+    for (pos, c) in bits.chars().enumerate() {
+      out[pos] = if c == '1' { 1 } else { 0 };
+    }
+    out
+  }
+
+  #[test]
+  fn test_a_instruction() {
+    let mut cpu = CPU::new();
+
+    // @12345
+    let out = cpu.tick(NO_M, gates::from_u16(12345), 0);
+
+    assert_eq!(gates::to_u16(cpu.a()), 12345);
+    assert_eq!(out.write_m, 0);
+    assert_eq!(gates::to_u16(cpu.pc()), 1);
+  }
+
+  #[test]
+  fn test_c_instruction_dest() {
+    let mut cpu = CPU::new();
+
+    // @17, D=A+1, M=D
+    cpu.tick(NO_M, gates::from_u16(17), 0);
+    cpu.tick(NO_M, word("1110110111010000"), 0);
+    assert_eq!(gates::to_u16(cpu.d()), 18);
+
+    let out = cpu.tick(NO_M, word("1110001100001000"), 0);
+    assert_eq!(out.write_m, 1);
+    assert_eq!(gates::to_u16(out.out_m), 18);
+    assert_eq!(out.address_m, gates::from_u16(17)[1..16]);
+  }
+
+  #[test]
+  fn test_reads_m() {
+    let mut cpu = CPU::new();
+
+    // @3, D=M (M = 42)
+    cpu.tick(NO_M, gates::from_u16(3), 0);
+    cpu.tick(gates::from_u16(42), word("1111110000010000"), 0);
+
+    assert_eq!(gates::to_u16(cpu.d()), 42);
+  }
+
+  #[test]
+  fn test_jump() {
+    let mut cpu = CPU::new();
+
+    // @100, 0;JMP
+    cpu.tick(NO_M, gates::from_u16(100), 0);
+    let out = cpu.tick(NO_M, word("1110101010000111"), 0);
+    assert_eq!(out.pc, gates::from_u16(100)[1..16]);
+
+    // @7, D;JGT with D = 0 does not jump
+    cpu.tick(NO_M, gates::from_u16(7), 0);
+    cpu.tick(NO_M, word("1110001100000001"), 0);
+    assert_eq!(gates::to_u16(cpu.pc()), 102);
+  }
+
+  #[test]
+  fn test_reset() {
+    let mut cpu = CPU::new();
+
+    cpu.tick(NO_M, gates::from_u16(100), 0);
+    cpu.tick(NO_M, word("1110101010000111"), 0);
+    cpu.tick(NO_M, gates::from_u16(1), 1);
+
+    assert_eq!(gates::to_u16(cpu.pc()), 0);
+  }
+
+  #[test]
+  fn test_shift_instructions() {
+    let mut cpu = CPU::new();
+    cpu.set_isa(Isa::HackExt);
+
+    // @3, D=A, @0x1234
+    cpu.tick(NO_M, gates::from_u16(3), 0);
+    cpu.tick(NO_M, word("1110110000010000"), 0);
+    cpu.tick(NO_M, gates::from_u16(0x1234), 0);
+
+    // A=A<<D: 0x1234 << 3
+    cpu.tick(NO_M, word("1010100010100000"), 0);
+    assert_eq!(gates::to_u16(cpu.a()), 0x91a0);
+
+    // D=D<< (by 1)
+    cpu.tick(NO_M, word("1010110000010000"), 0);
+    assert_eq!(gates::to_u16(cpu.d()), 6);
+
+    // D=M>> with M = -8 (arithmetic)
+    let out = cpu.tick(gates::from_u16(0xfff8), word("1011000000010000"), 0);
+    assert_eq!(gates::to_u16(cpu.d()), 0xfffc);
+    assert_eq!(out.ng, 1);
+  }
+
+  #[test]
+  fn test_hack_profile_ignores_bits_14_13() {
+    // 101 prefix runs as a stock instruction: D<< encodes like D=A there
+    let mut cpu = CPU::new();
+    cpu.tick(NO_M, gates::from_u16(5), 0);
+    cpu.tick(NO_M, word("1010110000010000"), 0);
+    assert_eq!(gates::to_u16(cpu.d()), 5);
+  }
+
+  #[test]
+  fn test_muldiv_instructions() {
+    let mut cpu = CPU::new();
+    cpu.set_isa(Isa::HackExt);
+
+    // @7, D=A, @100
+    cpu.tick(NO_M, gates::from_u16(7), 0);
+    cpu.tick(NO_M, word("1110110000010000"), 0);
+    cpu.tick(NO_M, gates::from_u16(100), 0);
+
+    // D=D*A, D=A/D (100 / 700 = 0), D=A%D with D = 7 again
+    cpu.tick(NO_M, word("1100000000010000"), 0);
+    assert_eq!(gates::to_u16(cpu.d()), 700);
+    cpu.tick(NO_M, word("1100011000010000"), 0);
+    assert_eq!(gates::to_u16(cpu.d()), 0);
+
+    // M=M/D: 100 / 7 with M = 100 after @7, D=A
+    cpu.tick(NO_M, gates::from_u16(7), 0);
+    cpu.tick(NO_M, word("1110110000010000"), 0);
+    let out = cpu.tick(gates::from_u16(100), word("1101011000001000"), 0);
+    assert_eq!((gates::to_u16(out.out_m), out.write_m), (14, 1));
+    let out = cpu.tick(gates::from_u16(100), word("1101101000001000"), 0);
+    assert_eq!(gates::to_u16(out.out_m), 2);
+  }
+
+  #[test]
+  fn test_flag_instructions() {
+    let mut cpu = CPU::new();
+    cpu.set_isa(Isa::HackExt);
+
+    // D = 0xffff (D=-1), @1
+    cpu.tick(NO_M, word("1110111010010000"), 0);
+    cpu.tick(NO_M, gates::from_u16(1), 0);
+
+    // D=C(D+A): 0xffff + 1 carries
+    let out = cpu.tick(NO_M, word("1000000000000000"), 0);
+    assert_eq!(gates::to_u16(out.out_m), 1);
+    // V(D+A): -1 + 1 does not overflow
+    let out = cpu.tick(NO_M, word("1000010000000000"), 0);
+    assert_eq!(gates::to_u16(out.out_m), 0);
+    // C(A-D): 1 - 0xffff borrows
+    let out = cpu.tick(NO_M, word("1000101000000000"), 0);
+    assert_eq!(gates::to_u16(out.out_m), 1);
+
+    // V(A-1) with A = 0x8000 overflows: @32767, A=A+1
+    cpu.tick(NO_M, gates::from_u16(32767), 0);
+    cpu.tick(NO_M, word("1110110111100000"), 0);
+    assert_eq!(gates::to_u16(cpu.a()), 0x8000);
+    let out = cpu.tick(NO_M, word("1000111100000000"), 0);
+    assert_eq!(gates::to_u16(out.out_m), 1);
+  }
+}
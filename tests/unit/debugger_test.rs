@@ -0,0 +1,197 @@
+#[cfg(test)]
+mod tests {
+  use nand_computer::{
+    asm,
+    computer::Computer,
+    debugger::{Debugger, Stop},
+  };
+
+  const COUNTDOWN: &str = "
+    @3
+    D=A
+    @counter
+    M=D
+  (LOOP)
+    @counter
+    D=M
+    @END
+    D;JEQ
+    @counter
+    M=M-1
+    @LOOP
+    0;JMP
+  (END)
+    @END
+    0;JMP
+  ";
+
+  fn debugger() -> Debugger {
+    let program = asm::assemble(COUNTDOWN).unwrap();
+    Debugger::new(Computer::new(program.words), program.symbols)
+  }
+
+  #[test]
+  fn test_step() {
+    let mut debugger = debugger();
+
+    let output = debugger.command("step 2").unwrap();
+
+    assert_eq!(output, "=> 2  @16");
+    assert_eq!(debugger.computer().d(), 3);
+  }
+
+  #[test]
+  fn test_breakpoint_on_label() {
+    let mut debugger = debugger();
+
+    assert_eq!(debugger.command("break LOOP").unwrap(), "breakpoint at 4 (LOOP)");
+    let output = debugger.command("continue").unwrap();
+
+    assert_eq!(output, "breakpoint at 4 (LOOP)\n=> 4 (LOOP)  @16");
+    assert_eq!(debugger.command("info breakpoints").unwrap(), "4 (LOOP)");
+  }
+
+  #[test]
+  fn test_watchpoint() {
+    let mut debugger = debugger();
+
+    debugger.command("break LOOP").unwrap();
+    debugger.command("continue").unwrap();
+    assert_eq!(debugger.command("watch counter").unwrap(), "watchpoint on RAM[16] = 3");
+    debugger.command("delete LOOP").unwrap();
+
+    let output = debugger.command("continue").unwrap();
+
+    assert!(output.starts_with("watchpoint RAM[16]: 3 -> 2\n"), "{}", output);
+    assert_eq!(debugger.computer().pc(), 10);
+  }
+
+  #[test]
+  fn test_continue_until_halt() {
+    let mut debugger = debugger();
+
+    assert_eq!(debugger.resume(), Stop::Halted(12));
+    assert_eq!(debugger.command("print counter").unwrap(), "counter = 0");
+  }
+
+  #[test]
+  fn test_next_runs_over_jumps() {
+    let mut debugger = debugger();
+
+    // `next` on `D;JEQ` at 7 only comes back to 8 once counter is non-zero
+    debugger.step(7);
+    assert_eq!(debugger.next(1), Stop::Done);
+    assert_eq!(debugger.computer().pc(), 8);
+  }
+
+  #[test]
+  fn test_print_and_set() {
+    let mut debugger = debugger();
+
+    debugger.command("set D=-1").unwrap();
+    debugger.command("set RAM[counter]=0x2a").unwrap();
+
+    assert_eq!(debugger.command("print D").unwrap(), "D = -1");
+    assert_eq!(debugger.command("print/x D").unwrap(), "D = 0xffff");
+    assert_eq!(debugger.command("print/b RAM[16]").unwrap(), "RAM[16] = 0b0000000000101010");
+    assert_eq!(debugger.command("p counter").unwrap(), "counter = 42");
+
+    debugger.command("set PC=4").unwrap();
+    assert_eq!(debugger.computer().pc(), 4);
+  }
+
+  #[test]
+  fn test_errors() {
+    let mut debugger = debugger();
+
+    assert!(debugger.command("break NOWHERE").is_err());
+    assert!(debugger.command("print/q D").is_err());
+    assert!(debugger.command("set D").is_err());
+    assert!(debugger.command("jump 3").is_err());
+    assert!(debugger.command("set PC=0x8000").is_err());
+    assert!(debugger.command("set PC=65535").is_err());
+  }
+
+  #[test]
+  fn test_next_from_the_last_rom_word() {
+    let mut debugger = debugger();
+
+    debugger.command("set PC=32767").unwrap();
+
+    assert_eq!(debugger.next(1), Stop::Done);
+    assert_eq!(debugger.computer().pc() & 0x7fff, 0);
+  }
+
+  #[test]
+  fn test_repl() {
+    let mut debugger = debugger();
+    let mut output = Vec::new();
+
+    debugger.run("break END\ncontinue\nquit\nstep\n".as_bytes(), &mut output).unwrap();
+
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("breakpoint at 12 (END)\n=> 12 (END)  @12"), "{}", output);
+    assert_eq!(debugger.computer().pc(), 12);
+  }
+
+  #[test]
+  fn test_reverse_step() {
+    let mut debugger = debugger();
+
+    debugger.step(6);
+    let output = debugger.command("reverse-step 2").unwrap();
+
+    assert_eq!(output, "=> 4 (LOOP)  @16");
+    assert_eq!(debugger.computer().cycle(), 4);
+    assert_eq!(debugger.reverse_step(10), Stop::HistoryStart);
+    assert_eq!(debugger.computer().pc(), 0);
+  }
+
+  #[test]
+  fn test_reverse_continue_to_watchpoint() {
+    let mut debugger = debugger();
+
+    debugger.command("watch counter").unwrap();
+    assert_eq!(debugger.resume(), Stop::Watchpoint { address: 16, old: 0, new: 3 });
+    assert_eq!(debugger.resume(), Stop::Watchpoint { address: 16, old: 3, new: 2 });
+    assert_eq!(debugger.resume(), Stop::Watchpoint { address: 16, old: 2, new: 1 });
+
+    // back to just before `M=M-1` wrote 1, then before it wrote 2
+    let output = debugger.command("reverse-continue").unwrap();
+    assert_eq!(output, "watchpoint RAM[16]: 2 -> 1\n=> 9  M=M-1");
+    assert_eq!(debugger.reverse_continue(), Stop::Watchpoint { address: 16, old: 3, new: 2 });
+    assert_eq!(debugger.command("print counter").unwrap(), "counter = 3");
+
+    // forward again reaches the same write
+    assert_eq!(debugger.resume(), Stop::Watchpoint { address: 16, old: 3, new: 2 });
+  }
+
+  #[test]
+  fn test_reverse_continue_skips_unchanged_writes() {
+    // RAM[16] = 5, then two stores of the same 5
+    let program = asm::assemble("@5\nD=A\n@16\nM=D\nM=D\nM=D\n(END)\n@END\n0;JMP\n").unwrap();
+    let mut debugger = Debugger::new(Computer::new(program.words), program.symbols);
+
+    debugger.command("watch 16").unwrap();
+    assert_eq!(debugger.resume(), Stop::Watchpoint { address: 16, old: 0, new: 5 });
+    assert_eq!(debugger.resume(), Stop::Halted(6));
+
+    assert_eq!(debugger.reverse_continue(), Stop::Watchpoint { address: 16, old: 0, new: 5 });
+    assert_eq!(debugger.computer().pc(), 3);
+  }
+
+  #[test]
+  fn test_reverse_continue_to_breakpoint() {
+    let mut debugger = debugger();
+
+    debugger.command("break LOOP").unwrap();
+    debugger.resume();
+    debugger.resume();
+    debugger.resume();
+    let cycle = debugger.computer().cycle();
+
+    assert_eq!(debugger.reverse_continue(), Stop::Breakpoint(4));
+    assert_eq!(debugger.computer().pc(), 4);
+    assert!(debugger.computer().cycle() < cycle);
+  }
+}
@@ -0,0 +1,63 @@
+#[cfg(test)]
+mod tests {
+  use nand_computer::{
+    adders::{self, Adder},
+    dot,
+    verilog::{self, Design, Options},
+  };
+
+  fn design() -> Design {
+    verilog::parse(&verilog::export(Options::default())).unwrap()
+  }
+
+  #[test]
+  fn test_ram4k_fans_out_to_ram512s() {
+    let dot = dot::hierarchy(&design(), "RAM4K", 0).unwrap();
+    assert!(dot.starts_with("digraph \"RAM4K\" {\n"));
+    assert_eq!(dot.matches(": RAM512\"").count(), 8);
+    assert!(dot.contains("b0 [label=\"dmux: DMux8Way\", shape=box];"));
+    assert!(dot.contains("b0 -> b1 [label=\"load0\"];"));
+    assert!(dot.contains("b8 -> b9 [label=\"out7/16\"];"));
+    assert!(!dot.contains("subgraph"));
+  }
+
+  #[test]
+  fn test_full_adder_composes_half_adders() {
+    let design = design();
+    let dot = dot::hierarchy(&design, "FullAdder", 0).unwrap();
+    for edge in ["b0 -> b1 [label=\"sum_one\"];", "b0 -> b2 [label=\"carry_one\"];", "b1 -> b2 [label=\"carry_two\"];"]
+    {
+      assert!(dot.contains(edge), "{}", edge);
+    }
+
+    // one level down the half adders and the or are opened up to NANDs
+    let dot = dot::hierarchy(&design, "FullAdder", 1).unwrap();
+    assert!(dot.contains("subgraph cluster_1 {\n    label=\"low: HalfAdder\";"));
+    assert_eq!(dot.matches("label=\"nand\"").count(), design.netlist("FullAdder").unwrap().nand_count());
+  }
+
+  #[test]
+  fn test_depth_expands_one_level_at_a_time() {
+    let design = design();
+    let clusters = |depth| dot::hierarchy(&design, "RAM512", depth).unwrap().matches("subgraph").count();
+    // the DMux, 8 RAM64s and the Mux, then the parts of each RAM64 (the
+    // NAND-level DMux and Mux have no parts)
+    assert_eq!(clusters(0), 0);
+    assert_eq!(clusters(1), 10);
+    assert_eq!(clusters(2), 10 + 8 * 10);
+  }
+
+  #[test]
+  fn test_netlist_draws_every_nand() {
+    let netlist = adders::netlist(Adder::Ripple);
+    let dot = dot::netlist("ripple", &netlist);
+    assert_eq!(dot.matches("label=\"nand\"").count(), netlist.nand_count());
+    assert_eq!(dot.matches(" -> ").count(), 2 * netlist.nand_count() + netlist.outputs().len());
+    assert!(dot.contains("[label=\"sum[15]\", shape=plaintext];"));
+  }
+
+  #[test]
+  fn test_unknown_chip_is_an_error() {
+    assert_eq!(dot::hierarchy(&design(), "RAM1M", 0).err(), Some("unknown module 'RAM1M'".to_string()));
+  }
+}
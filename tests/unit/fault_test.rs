@@ -0,0 +1,123 @@
+#[cfg(test)]
+mod tests {
+  use nand_computer::{
+    adders::Adder,
+    alu, asm,
+    computer::{Computer, STATE_BITS},
+    fault::{self, RamOp, StateFault, StuckAt},
+    netlist::{Logic, Netlist, NodeId},
+  };
+
+  // out = a & b, input c is never used
+  fn and_gate() -> (Netlist, NodeId) {
+    let mut netlist = Netlist::new();
+    let (a, b, c) = (netlist.input("a"), netlist.input("b"), netlist.input("c"));
+    let out = netlist.and(a, b);
+    netlist.output("out", out);
+    (netlist, c)
+  }
+
+  #[test]
+  fn test_stuck_at_forces_the_node() {
+    let (netlist, _) = and_gate();
+    let a = netlist.inputs()[0];
+    assert_eq!(fault::eval(&netlist, &[1, 1, 0], &[]), vec![1]);
+    assert_eq!(fault::eval(&netlist, &[1, 1, 0], &[StuckAt { node: a, value: 0 }]), vec![0]);
+    assert_eq!(fault::eval(&netlist, &[0, 1, 0], &[StuckAt { node: a, value: 1 }]), vec![1]);
+  }
+
+  #[test]
+  fn test_coverage_lists_detecting_vectors() {
+    let (netlist, unused) = and_gate();
+    let vectors = vec![vec![0, 0, 0], vec![0, 1, 0], vec![1, 0, 0], vec![1, 1, 0]];
+    let coverage = fault::coverage(&netlist, &vectors, &fault::stuck_at_faults(&netlist));
+    let a = netlist.inputs()[0];
+    // a stuck at 1 only shows when b is 1 and a is 0
+    let (_, detecting) = coverage.faults.iter().find(|(f, _)| *f == StuckAt { node: a, value: 1 }).unwrap();
+    assert_eq!(detecting, &vec![1]);
+    let undetected: Vec<StuckAt> = coverage.undetected().copied().collect();
+    assert_eq!(undetected, vec![StuckAt { node: unused, value: 0 }, StuckAt { node: unused, value: 1 }]);
+  }
+
+  #[test]
+  fn test_alu_coverage() {
+    let coverage = fault::alu_coverage(Adder::Ripple);
+    assert_eq!((coverage.detected(), coverage.faults.len()), (1436, 1484));
+    assert!(coverage.to_string().ends_with("\n1436 of 1484 faults detected (96.8%)"));
+    // nodes whose value the 72 vectors never carry to an output
+    let undetected = |value| -> Vec<usize> {
+      coverage.undetected().filter(|fault| fault.value == value).map(|fault| fault.node.0).collect()
+    };
+    assert_eq!(undetected(0), vec![245, 463, 464, 469, 470, 471, 472, 473, 703, 709, 712, 715, 721, 724, 727, 730]);
+    assert_eq!(
+      undetected(1),
+      vec![
+        240, 242, 244, 247, 463, 464, 469, 470, 471, 472, 473, 699, 701, 702, 705, 707, 708, 710, 711, 713, 714, 717,
+        719, 720, 722, 723, 725, 726, 728, 729, 734, 737
+      ]
+    );
+  }
+
+  #[test]
+  fn test_sliced_coverage_matches_one_vector_at_a_time() {
+    // 72 vectors, so detections in the second pass of 64 count too
+    let netlist = alu::netlist(Adder::Ripple);
+    let vectors = fault::alu_vectors();
+    let faults: Vec<StuckAt> = fault::stuck_at_faults(&netlist).into_iter().step_by(37).collect();
+    let coverage = fault::coverage(&netlist, &vectors, &faults);
+    for (fault, detecting) in &coverage.faults {
+      let expected: Vec<usize> = (0..vectors.len())
+        .filter(|&pos| fault::eval(&netlist, &vectors[pos], &[*fault]) != netlist.eval(&vectors[pos]))
+        .collect();
+      assert_eq!(detecting, &expected, "{}", fault);
+    }
+    assert!(coverage.faults.iter().any(|(_, detecting)| detecting.iter().any(|&pos| pos >= 64)));
+  }
+
+  #[test]
+  fn test_ram8_reads_back_without_faults() {
+    let tests = fault::ram8_tests();
+    let outputs = fault::run_ram8(&tests[2], &[]);
+    assert_eq!(&outputs[8..], &[0x5555, 0xaaaa, 0x5555, 0xaaaa, 0x5555, 0xaaaa, 0x5555, 0xaaaa]);
+  }
+
+  #[test]
+  fn test_ram8_coverage() {
+    let coverage = fault::ram8_coverage(&fault::ram8_tests(), &fault::ram8_faults());
+    assert_eq!(coverage.detected(), 8 * 16 * 3);
+    // register 3 bit 0 (the MSB) stuck at 1 is only seen when writing a 0 there
+    let (_, detecting) =
+      coverage.faults.iter().find(|(f, _)| *f == StateFault::StuckAt { bit: 3 * 16, value: 1 }).unwrap();
+    assert_eq!(detecting, &vec![0, 3]);
+  }
+
+  #[test]
+  fn test_flip_before_a_write_is_masked() {
+    let ops = [RamOp { address: 0, load: 1, input: 7 }, RamOp { address: 0, load: 0, input: 0 }];
+    let flip = |cycle| [StateFault::Flip { bit: 15, cycle }];
+    assert_eq!(fault::run_ram8(&ops, &flip(0)), vec![7, 7]);
+    assert_eq!(fault::run_ram8(&ops, &flip(1)), vec![7, 6]);
+  }
+
+  #[test]
+  fn test_faults_in_a_running_computer() {
+    let program = asm::assemble("@5\nD=A\n@16\nM=D\n@16\nD=M\n(END)\n@END\n0;JMP\n").unwrap().words;
+    let run = |faults: Vec<StateFault>| {
+      let mut computer = Computer::new(program.clone());
+      computer.set_faults(faults).unwrap();
+      computer.run(6);
+      (computer.d(), computer.peek(16))
+    };
+    assert_eq!(run(vec![]), (5, 5));
+    // the LSB of D stuck at 0: 5 is stored as 4
+    assert_eq!(run(vec![StateFault::StuckAt { bit: 16 + 15, value: 0 }]), (4, 4));
+    // the LSB of RAM[16] flipped after the write, before it is read back
+    let ram_lsb = 48 + 16 * 16 + 15;
+    assert_eq!(run(vec![StateFault::Flip { bit: ram_lsb, cycle: 4 }]), (4, 4));
+    // flipped before the write, which overwrites it
+    assert_eq!(run(vec![StateFault::Flip { bit: ram_lsb, cycle: 2 }]), (5, 5));
+
+    let mut computer = Computer::new(program.clone());
+    assert!(computer.set_faults(vec![StateFault::StuckAt { bit: STATE_BITS, value: 1 }]).is_err());
+  }
+}
@@ -148,4 +148,119 @@ mod gates_test {
     let expected: Bit16 = [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0]; // 2
     assert_eq!(gates::inc_16(a), expected);
   }
+
+  #[test]
+  fn test_or_8_way() {
+    assert_eq!(gates::or_8_way([0; 8]), 0);
+    assert_eq!(gates::or_8_way([0, 0, 0, 0, 0, 0, 0, 1]), 1);
+    assert_eq!(gates::or_8_way([1, 0, 0, 0, 0, 0, 0, 0]), 1);
+    assert_eq!(gates::or_8_way([1; 8]), 1);
+  }
+
+  #[test]
+  fn test_n_way_reductions() {
+    assert_eq!(gates::and_n(&[]), 1);
+    assert_eq!(gates::and_n(&[1, 1, 1]), 1);
+    assert_eq!(gates::and_n(&[1, 0, 1, 1, 1]), 0);
+
+    assert_eq!(gates::or_n(&[]), 0);
+    assert_eq!(gates::or_n(&[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]), 1);
+    assert_eq!(gates::or_n(&[0; 16]), 0);
+
+    assert_eq!(gates::xor_n(&[]), 0);
+    assert_eq!(gates::xor_n(&[1, 1, 1]), 1);
+    assert_eq!(gates::xor_n(&[1, 0, 1, 0]), 0);
+  }
+
+  #[test]
+  fn test_mux16way16() {
+    let inputs: [Bit16; 16] = std::array::from_fn(|i| gates::from_u16(i as u16 * 1000));
+    for i in 0..16u16 {
+      let sel = gates::from_u16(i)[12..16].try_into().unwrap();
+      assert_eq!(gates::mux16way16(inputs, sel), inputs[i as usize]);
+    }
+  }
+
+  #[test]
+  fn test_dmux16way() {
+    for i in 0..16 {
+      let sel = gates::from_u16(i as u16)[12..16].try_into().unwrap();
+      let mut expected = [0; 16];
+      expected[i] = 1;
+      assert_eq!(gates::dmux16way(1, sel), expected);
+      assert_eq!(gates::dmux16way(0, sel), [0; 16]);
+    }
+  }
+
+  #[test]
+  fn test_n_way_matches_8_way() {
+    let inputs: [Bit16; 8] = std::array::from_fn(|i| gates::from_u16(i as u16 + 1));
+    let [a, b, c, d, e, f, g, h] = inputs;
+    for i in 0..8u16 {
+      let sel: [u8; 3] = gates::from_u16(i)[13..16].try_into().unwrap();
+      assert_eq!(gates::mux_n_way16(&inputs, &sel), gates::mux8way16(a, b, c, d, e, f, g, h, sel));
+
+      let (o0, o1, o2, o3, o4, o5, o6, o7) = gates::dmux8way(1, sel);
+      assert_eq!(gates::dmux_n_way(1, &sel), vec![o0, o1, o2, o3, o4, o5, o6, o7]);
+    }
+  }
+
+  #[test]
+  #[should_panic]
+  fn test_mux_n_way16_checks_width() {
+    gates::mux_n_way16(&[[0; 16]; 3], &[0, 1]);
+  }
+
+  #[test]
+  fn test_structural_mux_matches_shortcut() {
+    use gates::{shortcut, structural};
+    for (a, b, sel) in (0..8).map(|i| (i >> 2 & 1, i >> 1 & 1, i & 1)) {
+      assert_eq!(structural::mux(a, b, sel), shortcut::mux(a, b, sel));
+    }
+    for (n, sel) in (0..4).map(|i| (i >> 1 & 1, i & 1)) {
+      assert_eq!(structural::dmux(n, sel), shortcut::dmux(n, sel));
+    }
+
+    let inputs: [Bit16; 8] = std::array::from_fn(|i| gates::from_u16(0x1111 * i as u16 + 7));
+    let [a, b, c, d, e, f, g, h] = inputs;
+    for i in 0..4u16 {
+      let sel: [u8; 2] = [(i & 1) as u8, (i >> 1) as u8];
+      assert_eq!(structural::mux4way16(a, b, c, d, sel), shortcut::mux4way16(a, b, c, d, sel));
+      for n in 0..2 {
+        assert_eq!(structural::dmux4way(n, sel), shortcut::dmux4way(n, sel));
+      }
+    }
+    for i in 0..8u16 {
+      let sel: [u8; 3] = gates::from_u16(i)[13..16].try_into().unwrap();
+      assert_eq!(structural::mux8way16(a, b, c, d, e, f, g, h, sel), shortcut::mux8way16(a, b, c, d, e, f, g, h, sel));
+      for n in 0..2 {
+        assert_eq!(structural::dmux8way(n, sel), shortcut::dmux8way(n, sel));
+      }
+    }
+  }
+
+  #[test]
+  fn test_add_16_flags() {
+    for (a, b) in
+      [(0u16, 0u16), (1, 0xffff), (0x7fff, 1), (0x8000, 0x8000), (0x1234, 0x4321), (0xffff, 0xffff), (0x8000, 0x7fff)]
+    {
+      let (sum, carry, overflow) = gates::add_16_flags(gates::from_u16(a), gates::from_u16(b));
+      let (expected, expected_carry) = a.overflowing_add(b);
+      assert_eq!(gates::to_u16(sum), expected);
+      assert_eq!(carry, expected_carry as u8, "{:#x} + {:#x}", a, b);
+      assert_eq!(overflow, (a as i16).overflowing_add(b as i16).1 as u8, "{:#x} + {:#x}", a, b);
+    }
+
+    // multi-word: 0x0001_ffff + 0x0000_0001 = 0x0002_0000
+    let (low, carry, _) = gates::add_16_flags(gates::from_u16(0xffff), gates::from_u16(1));
+    let (high, _, _) = gates::add_16_carry(gates::from_u16(1), [0; 16], carry);
+    assert_eq!((gates::to_u16(high), gates::to_u16(low)), (2, 0));
+  }
+
+  #[test]
+  fn test_inc_16_flags() {
+    assert_eq!(gates::inc_16_flags(gates::from_u16(0xffff)), ([0; 16], 1, 0));
+    assert_eq!(gates::inc_16_flags(gates::from_u16(0x7fff)), (gates::from_u16(0x8000), 0, 1));
+    assert_eq!(gates::inc_16_flags(gates::from_u16(41)), (gates::from_u16(42), 0, 0));
+  }
 }
@@ -0,0 +1,74 @@
+#[cfg(test)]
+mod tests {
+  use nand_computer::{
+    adders::Adder,
+    hazard::{self, Kind},
+    netlist::{Logic, Netlist, Node, NodeId},
+    timing::Delays,
+  };
+
+  #[test]
+  fn test_mux_glitches_when_sel_drops() {
+    let hazards = hazard::mux_hazards(&Delays::default());
+    assert_eq!(hazards.len(), 1);
+    let glitch = &hazards[0];
+    assert_eq!(glitch.kind, Kind::Static1);
+    assert_eq!(glitch.switched, vec!["sel 1->0".to_string()]);
+    assert_eq!(glitch.before, vec![1, 1, 1]);
+    assert_eq!(glitch.changes, vec![(40, 0), (50, 1)]);
+    assert_eq!(glitch.to_string(), "out: static-1 hazard when sel 1->0 (inputs 111): 0 at 40, 1 at 50");
+  }
+
+  #[test]
+  fn test_balanced_paths_do_not_glitch() {
+    // when both and gates switch at the same time there is no dip
+    let netlist = hazard::mux_netlist();
+    let mut delays = Delays::default();
+    // `Logic::not` builds !sel as sel NAND 1
+    let sel = netlist.inputs()[2];
+    let not_sel = (0..netlist.nodes().len())
+      .map(NodeId)
+      .find(|&id| match *netlist.node(id) {
+        Node::Nand(x, y) => x == sel && matches!(netlist.node(y), Node::Const(1)),
+        _ => false,
+      })
+      .unwrap();
+    delays.set(not_sel, 0);
+    assert_eq!(hazard::scan(&netlist, &delays, &hazard::all_vectors(3)), vec![]);
+  }
+
+  #[test]
+  fn test_single_gates_are_hazard_free() {
+    let mut netlist = Netlist::new();
+    let (a, b) = (netlist.input("a"), netlist.input("b"));
+    let out = netlist.and(a, b);
+    netlist.output("out", out);
+    assert_eq!(hazard::scan(&netlist, &Delays::default(), &hazard::all_vectors(2)), vec![]);
+  }
+
+  #[test]
+  fn test_dynamic_hazard() {
+    // out = a xor (delayed a xor a): rises with a, then the slow path makes
+    // it fall and rise again
+    let mut netlist = Netlist::new();
+    let a = netlist.input("a");
+    let fast = netlist.not(a);
+    let slow = {
+      let once = netlist.not(a);
+      let twice = netlist.not(once);
+      netlist.not(twice)
+    };
+    let pulse = netlist.xor(fast, slow);
+    let out = netlist.xor(pulse, a);
+    netlist.output("out", out);
+    let hazards = hazard::detect(&netlist, &Delays::default(), &[0], &[1]);
+    assert_eq!(hazards.iter().map(|hazard| hazard.kind).collect::<Vec<_>>(), vec![Kind::Dynamic]);
+  }
+
+  #[test]
+  fn test_alu_hazards_name_the_switched_input() {
+    let hazards = hazard::alu_hazards(Adder::Ripple, &Delays::default());
+    assert!(!hazards.is_empty());
+    assert!(hazards.iter().all(|hazard| hazard.switched.len() == 1 && hazard.changes.len() >= 2));
+  }
+}
@@ -0,0 +1,109 @@
+#[cfg(test)]
+mod tests {
+  use nand_computer::{asm, computer::Computer, fault::StateFault, history::History};
+
+  // RAM[counter] counts up forever
+  const COUNTER: &str = "
+    @counter
+    M=0
+  (LOOP)
+    @counter
+    M=M+1
+    @LOOP
+    0;JMP
+  ";
+
+  fn computer(interval: u64, segments: usize) -> Computer {
+    let mut computer = Computer::new(asm::assemble(COUNTER).unwrap().words);
+    computer.record_history(History::new(interval, segments));
+    computer
+  }
+
+  #[test]
+  fn test_rewind_restores_registers_and_memory() {
+    let mut computer = computer(8, 4);
+    let steps: Vec<_> = (0..20).map(|_| computer.step()).collect();
+
+    computer.rewind(11).unwrap();
+
+    assert_eq!(computer.cycle(), 11);
+    assert_eq!(computer.pc(), steps[10].next_pc);
+    assert_eq!((computer.a(), computer.d()), (steps[10].a, steps[10].d));
+    assert_eq!(computer.peek(16), 2);
+
+    // execution after the rewind repeats the recorded cycles
+    for step in &steps[11..20] {
+      assert_eq!(&computer.step(), step);
+    }
+  }
+
+  #[test]
+  fn test_rewind_keeps_faults() {
+    let mut computer = computer(8, 4);
+    // the LSB of the counter stuck at 1: it only takes odd values
+    let stuck = StateFault::StuckAt { bit: 48 + 16 * 16 + 15, value: 1 };
+    // flips after the checkpoint at cycle 8: one bumps the counter by 4, one sets RAM[100]
+    let flips =
+      [StateFault::Flip { bit: 48 + 16 * 16 + 13, cycle: 9 }, StateFault::Flip { bit: 48 + 16 * 100 + 15, cycle: 10 }];
+    computer.set_faults([stuck].into_iter().chain(flips).collect()).unwrap();
+    let steps: Vec<_> = (0..20).map(|_| computer.step()).collect();
+    let (counter, flipped) = (computer.peek(16), computer.peek(100));
+
+    computer.rewind(11).unwrap();
+
+    assert_eq!(computer.faults().len(), 3);
+    assert_eq!(computer.peek(16) % 2, 1);
+    assert_eq!(computer.peek(100), 1);
+    for step in &steps[11..20] {
+      assert_eq!(&computer.step(), step);
+    }
+    assert_eq!((computer.peek(16), computer.peek(100)), (counter, flipped));
+  }
+
+  #[test]
+  fn test_rewind_to_checkpoint_boundary() {
+    let mut computer = computer(4, 4);
+    computer.run(10);
+
+    computer.rewind(8).unwrap();
+    assert_eq!(computer.cycle(), 8);
+    computer.rewind(0).unwrap();
+    assert_eq!((computer.pc(), computer.peek(16)), (0, 0));
+  }
+
+  #[test]
+  fn test_history_is_bounded() {
+    let mut computer = computer(4, 3);
+    computer.run(30);
+
+    let history = computer.history().unwrap();
+    assert_eq!(history.segments(), 3);
+    assert_eq!(history.oldest(), Some(20));
+    assert_eq!(history.deltas().count(), 10);
+
+    assert!(computer.rewind(19).is_err());
+    assert!(computer.rewind(31).is_err());
+    assert!(computer.rewind(20).is_ok());
+  }
+
+  #[test]
+  fn test_out_of_band_changes_restart_history() {
+    let mut computer = computer(4, 4);
+    computer.run(6);
+
+    computer.poke(16, 100);
+    assert_eq!(computer.history().unwrap().oldest(), None);
+    assert!(computer.rewind(3).is_err());
+
+    computer.run(4);
+    assert_eq!(computer.history().unwrap().oldest(), Some(6));
+  }
+
+  #[test]
+  fn test_rewind_without_history() {
+    let mut computer = Computer::new(asm::assemble(COUNTER).unwrap().words);
+    computer.run(2);
+
+    assert!(computer.rewind(0).is_err());
+  }
+}
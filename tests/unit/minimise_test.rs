@@ -0,0 +1,117 @@
+#[cfg(test)]
+mod tests {
+  use nand_computer::{
+    bdd::{self, Equivalence},
+    minimise::{self, Cube, TruthTable},
+    verilog,
+  };
+
+  // the ALU control table of alu.rs, with whether the output reads x and y
+  const ALU_TABLE: &str = "
+    zx nx zy ny f  no | x y
+    1  0  1  0  1  0  | 0 0
+    1  1  1  1  1  1  | 0 0
+    1  1  1  0  1  0  | 0 0
+    0  0  1  1  0  0  | 1 0
+    1  1  0  0  0  0  | 0 1
+    0  0  1  1  0  1  | 1 0
+    1  1  0  0  0  1  | 0 1
+    0  0  1  1  1  1  | 1 0
+    1  1  0  0  1  1  | 0 1
+    0  1  1  1  1  1  | 1 0
+    1  1  0  1  1  1  | 0 1
+    0  0  1  1  1  0  | 1 0
+    1  1  0  0  1  0  | 0 1
+    0  0  0  0  1  0  | 1 1
+    0  1  0  0  1  1  | 1 1
+    0  0  0  1  1  1  | 1 1
+    0  0  0  0  0  0  | 1 1
+    0  1  0  1  0  1  | 1 1
+  ";
+
+  #[test]
+  fn test_prime_implicants_merge_adjacent_minterms() {
+    // f(a, b, c, d) = m(4, 8, 10, 11, 12, 15) + d(9, 14)
+    let (on, dont_care) = ([4, 8, 10, 11, 12, 15], [9, 14]);
+    let cube = |value, mask| Cube { value, mask };
+    assert_eq!(
+      minimise::prime_implicants(&on, &dont_care),
+      vec![cube(0b0100, 0b1000), cube(0b1000, 0b0011), cube(0b1000, 0b0110), cube(0b1010, 0b0101)]
+    );
+    let cover = minimise::minimise(&on, &dont_care);
+    assert_eq!(cover.len(), 3);
+    assert!(cover.contains(&cube(0b0100, 0b1000)) && cover.contains(&cube(0b1010, 0b0101)));
+    for row in 0..16 {
+      let covered = cover.iter().any(|cube| cube.covers(row));
+      if on.contains(&row) {
+        assert!(covered, "{}", row);
+      } else if !dont_care.contains(&row) {
+        assert!(!covered, "{}", row);
+      }
+    }
+  }
+
+  #[test]
+  fn test_unlisted_alu_controls_are_dont_cares() {
+    let table = TruthTable::parse(ALU_TABLE).unwrap();
+    assert_eq!(table.rows.len(), 64);
+    assert_eq!(table.dont_care(0).len(), 64 - 18);
+    assert_eq!(minimise::equations(&table), "x = !zx\ny = !zy\n");
+    assert_eq!(minimise::synthesise(&table).nand_count(), 2);
+  }
+
+  #[test]
+  fn test_jump_condition() {
+    let table = TruthTable::parse(
+      "j1 j2 j3 zr ng | jump
+       -  -  -  1  1  | x
+       1  -  -  0  1  | 1
+       0  -  -  0  1  | 0
+       -  1  -  1  0  | 1
+       -  0  -  1  0  | 0
+       -  -  1  0  0  | 1
+       -  -  0  0  0  | 0",
+    )
+    .unwrap();
+    assert_eq!(minimise::equations(&table), "jump = j3 & !zr & !ng | j2 & zr | j1 & ng\n");
+  }
+
+  #[test]
+  fn test_synthesised_gates_are_equivalent() {
+    for (chip, netlist) in verilog::gate_chips().into_iter().filter(|(_, netlist)| netlist.inputs().len() <= 4) {
+      let table = TruthTable::of(&netlist).unwrap();
+      let synthesised = minimise::synthesise(&table);
+      assert_eq!(bdd::equivalent(&netlist, &synthesised), Ok(Equivalence::Equivalent), "{}", chip);
+    }
+  }
+
+  #[test]
+  fn test_mux_minimises_below_the_hand_written_gate() {
+    let mux = verilog::gate_chips().into_iter().find(|(chip, _)| *chip == "Mux").unwrap().1;
+    let table = TruthTable::of(&mux).unwrap();
+    assert_eq!(minimise::equations(&table), "out = b & sel | a & !sel\n");
+
+    let comparisons = minimise::compare_gates(4);
+    let row = |chip: &str| comparisons.iter().find(|comparison| comparison.chip == chip).unwrap().clone();
+    assert_eq!((row("Mux").hand, row("Mux").minimised), (8, 4));
+    assert_eq!((row("Xor").hand, row("Xor").minimised), (4, 5));
+    assert!(minimise::comparison_table(&comparisons).contains("Mux        |      3 |    8 |         4 |     2"));
+  }
+
+  #[test]
+  fn test_constant_outputs() {
+    let table = TruthTable::parse("a b | zero one\n- - | 0 1").unwrap();
+    assert_eq!(minimise::equations(&table), "zero = 0\none = 1\n");
+    assert_eq!(minimise::synthesise(&table).eval(&[1, 0]), vec![0, 1]);
+  }
+
+  #[test]
+  fn test_table_errors() {
+    assert_eq!(TruthTable::parse("a | out\n2 | 1").err(), Some("line 2: '2' is not 0, 1 or -".to_string()));
+    assert_eq!(
+      TruthTable::parse("a | out\n1 | 1\n1 | 0").err(),
+      Some("line 3: row 1 is given twice with different outputs".to_string())
+    );
+    assert_eq!(TruthTable::parse("a b\n").err(), Some("line 1: the header needs inputs | outputs".to_string()));
+  }
+}
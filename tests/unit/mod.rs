@@ -1,9 +1,34 @@
+#![allow(clippy::module_inception)]
+
+pub mod adders_test;
 pub mod alu_test;
+pub mod arith_test;
+pub mod asm_test;
+pub mod batch_test;
+pub mod bdd_test;
+pub mod computer_test;
 pub mod cpu_test;
+pub mod debugger_test;
+pub mod dot_test;
+pub mod fault_test;
 pub mod gates_test;
+pub mod hazard_test;
+pub mod history_test;
+pub mod latch_test;
+pub mod minimise_test;
+pub mod netlist_test;
+pub mod power_test;
 pub mod ram16k_test;
 pub mod ram4k_test;
 pub mod ram512_test;
 pub mod ram64_test;
 pub mod ram8_test;
 pub mod register_test;
+pub mod shifter_test;
+pub mod snapshot_test;
+pub mod techmap_test;
+pub mod timing_test;
+pub mod trace_test;
+pub mod vcd_test;
+pub mod verify_test;
+pub mod verilog_test;
@@ -0,0 +1,103 @@
+#[cfg(test)]
+mod tests {
+  use nand_computer::{
+    adders::{self, Adder},
+    alu, fault, gates,
+    netlist::{self, Logic, Netlist, Node, Sliced},
+    verify::Rng,
+  };
+
+  fn random_16(rng: &mut Rng, count: usize) -> Vec<u16> {
+    (0..count).map(|_| rng.next_u16()).collect()
+  }
+
+  #[test]
+  fn test_constants_are_shared() {
+    let mut netlist = Netlist::new();
+    let mut signal = netlist.input("a");
+    for _ in 0..10 {
+      signal = netlist.not(signal);
+    }
+    let zero = netlist.constant(0);
+    assert_eq!(netlist.constant(0), zero);
+    let consts = netlist.nodes().iter().filter(|node| matches!(node, Node::Const(_))).count();
+    assert_eq!(consts, 2);
+    assert_eq!(netlist.nodes().len(), 1 + 2 + 10);
+    assert_eq!(alu::netlist(Adder::Ripple).nodes().iter().filter(|node| matches!(node, Node::Const(_))).count(), 2);
+  }
+
+  #[test]
+  fn test_pack_round_trips() {
+    let vectors = vec![vec![1, 0, 1], vec![0, 0, 1], vec![1, 1, 0]];
+    let words = netlist::pack(&vectors);
+    assert_eq!(words, vec![0b101, 0b100, 0b011]);
+    assert_eq!(netlist::unpack(&words, 3), vectors);
+
+    let values = random_16(&mut Rng::new(7), 64);
+    assert_eq!(netlist::unpack_16(&netlist::pack_16(&values), 64), values);
+    assert_eq!(netlist::pack_16(&[0x8001])[0], 1);
+    assert_eq!(netlist::lane_mask(3), 0b111);
+    assert_eq!(netlist::lane_mask(64), !0);
+    assert_eq!(netlist::lanes(1), !0);
+  }
+
+  #[test]
+  fn test_sliced_gates_work_lane_by_lane() {
+    let (a, b, sel) = (0b0011u64, 0b0101, 0b1111_0000);
+    let mut logic = Sliced;
+    assert_eq!(logic.nand(a, b) & 0b1111, 0b1110);
+    assert_eq!(logic.xor(a, b), 0b0110);
+    assert_eq!(logic.mux(a, b, sel), 0b0011);
+    assert_eq!(logic.or_n(&[a, b, sel]), 0b1111_0111);
+  }
+
+  #[test]
+  fn test_batch_add_16_matches_add_16() {
+    let mut rng = Rng::new(3);
+    let (a, b) = (random_16(&mut rng, 64), random_16(&mut rng, 64));
+    for adder in Adder::ALL {
+      let sums = netlist::unpack_16(&adders::batch_add_16(adder, netlist::pack_16(&a), netlist::pack_16(&b)), 64);
+      let expected: Vec<u16> = a.iter().zip(&b).map(|(a, b)| a.wrapping_add(*b)).collect();
+      assert_eq!(sums, expected, "{}", adder.name());
+    }
+  }
+
+  #[test]
+  fn test_batch_eval_matches_eval() {
+    // the ALU vectors are 72 vectors, one full pass and a partial one
+    let netlist = alu::netlist(Adder::KoggeStone);
+    let vectors = fault::alu_vectors();
+    for chunk in vectors.chunks(netlist::LANES) {
+      let outputs = netlist.batch_eval(&netlist::pack(chunk));
+      let expected: Vec<Vec<u8>> = chunk.iter().map(|vector| netlist.eval(vector)).collect();
+      assert_eq!(netlist::unpack(&outputs, chunk.len()), expected);
+    }
+
+    let mut rng = Rng::new(11);
+    let (x, y) = (random_16(&mut rng, 64), random_16(&mut rng, 64));
+    let controls = [[1, 1, 1, 1, 1, 1], [0, 0, 0, 0, 1, 0], [0, 1, 0, 0, 1, 1]];
+    for bits in controls {
+      let (out, zr, ng) =
+        alu::batch_eval(Adder::Ripple, netlist::pack_16(&x), netlist::pack_16(&y), bits.map(netlist::lanes));
+      for (lane, value) in netlist::unpack_16(&out, 64).into_iter().enumerate() {
+        let mut alu = alu::ALU::new(gates::from_u16(x[lane]), gates::from_u16(y[lane]));
+        alu.set_controls(bits);
+        let (expected, expected_zr, expected_ng) = alu.execute();
+        assert_eq!(value, gates::to_u16(expected));
+        assert_eq!(((zr >> lane) & 1) as u8, expected_zr);
+        assert_eq!(((ng >> lane) & 1) as u8, expected_ng);
+      }
+    }
+  }
+
+  #[test]
+  fn test_batch_values_forced_holds_every_lane() {
+    let mut netlist = Netlist::new();
+    let (a, b) = (netlist.input("a"), netlist.input("b"));
+    let out = netlist.and(a, b);
+    netlist.output("out", out);
+    assert_eq!(netlist.batch_eval(&[0b1100, 0b1010]), vec![0b1000]);
+    let values = netlist.batch_values_forced(&[0b1100, 0b1010], &[(a, 1)]);
+    assert_eq!(values[out.0], 0b1010);
+  }
+}
@@ -0,0 +1,70 @@
+#[cfg(test)]
+mod tests {
+  use nand_computer::{
+    adders::Adder,
+    asm,
+    computer::Computer,
+    power::{Activity, Chip},
+  };
+
+  fn computer(source: &str) -> Computer {
+    Computer::new(asm::assemble(source).unwrap().words)
+  }
+
+  #[test]
+  fn test_register_and_memory_toggles() {
+    let mut computer = computer("@5\nD=A\n@16\nM=D\n");
+    let mut activity = Activity::new(Adder::Ripple);
+    activity.run(&mut computer, 4);
+
+    // A: 0 -> 5 -> 16, D: 0 -> 5, PC: 0 -> 1 -> 2 -> 3 -> 4, RAM[16]: 0 -> 5
+    assert_eq!(activity.toggles(Chip::A), 2 + 3);
+    assert_eq!(activity.toggles(Chip::D), 2);
+    assert_eq!(activity.toggles(Chip::Pc), 1 + 2 + 1 + 3);
+    assert_eq!(activity.toggles(Chip::Ram), 2);
+    assert_eq!(activity.toggles(Chip::Screen), 0);
+    assert!(activity.toggles(Chip::Alu) > 0);
+    assert_eq!(activity.cycles(), 4);
+    assert_eq!(activity.total(), Chip::ALL.iter().map(|&chip| activity.toggles(chip)).sum::<u64>());
+    assert_eq!(activity.alu_toggles().iter().sum::<u64>(), activity.toggles(Chip::Alu));
+  }
+
+  #[test]
+  fn test_writes_count_changed_bits_only() {
+    let mut computer = computer("@5\nD=A\n@16\nM=D\n@SCREEN\nM=-1\n");
+    computer.poke(16, 7);
+    let mut activity = Activity::new(Adder::Ripple);
+    activity.run(&mut computer, 6);
+    assert_eq!(activity.toggles(Chip::Ram), 1, "7 -> 5 changes one bit");
+    assert_eq!(activity.toggles(Chip::Screen), 16);
+  }
+
+  #[test]
+  fn test_repeated_instruction_leaves_the_alu_quiet() {
+    let mut computer = computer("D=0\nD=0\nD=0\n");
+    let mut activity = Activity::new(Adder::Ripple);
+    activity.run(&mut computer, 3);
+    // the controls switch once, then the ALU sees the same inputs every cycle
+    assert!(activity.per_cycle()[0] > 0);
+    assert_eq!(activity.toggles(Chip::Alu), activity.per_cycle()[0] - 1);
+    assert_eq!(&activity.per_cycle()[1..], &[2, 1], "only the PC still counts");
+  }
+
+  #[test]
+  fn test_report_and_heatmap() {
+    let mut computer = computer("@5\nD=A\n@16\nM=D\n@SCREEN\nM=-1\n");
+    let mut activity = Activity::new(Adder::KoggeStone);
+    activity.run(&mut computer, 6);
+    let report = activity.to_string();
+    assert!(report.contains("RAM16K"));
+    assert!(report.lines().last().unwrap().starts_with("total"));
+
+    let heatmap = activity.heatmap();
+    let depth = activity.alu().depth();
+    assert!(heatmap.contains(&format!("{:>4} |", depth)));
+    let ram = heatmap.lines().find(|line| line.starts_with(" RAM")).unwrap();
+    assert_ne!(&ram[6..7], " ", "page 0 was written");
+    let screen = heatmap.lines().find(|line| line.starts_with(" SCR")).unwrap();
+    assert_eq!(&screen[6..7], "@", "16 toggles in one of 6 cycles");
+  }
+}
@@ -1,6 +1,6 @@
 pub mod ram16k_test {
   use nand_computer::{
-    gates::Bit16,
+    gates::{self, Bit16},
     mem::{self},
   };
 
@@ -90,4 +90,34 @@ pub mod ram16k_test {
     // verify the output matches the second input
     assert_eq!(output, input2, "value at address 9742 should match input2");
   }
+
+  /// test that ticking on several threads gives the same outputs as one
+  #[test]
+  fn test_ram16k_threads_match_serial() {
+    let mut serial = mem::ram16k::RAM16K::default();
+    let mut rams: Vec<_> = (2..=4)
+      .map(|threads| {
+        let mut ram = mem::ram16k::RAM16K::default();
+        ram.set_threads(threads);
+        ram
+      })
+      .collect();
+    assert_eq!(rams.iter().map(|ram| ram.threads()).collect::<Vec<_>>(), vec![2, 3, 4]);
+
+    // writes and reads spread over the four RAM4Ks
+    for step in 0..64u16 {
+      let address = gates::from_u16(step.wrapping_mul(4099) & 0x3fff)[2..16].try_into().unwrap();
+      let (load, input) = ((step % 3 == 0) as u8, gates::from_u16(step.wrapping_mul(40503)));
+      let expected = serial.tick(address, load, input);
+      for ram in &mut rams {
+        assert_eq!(ram.tick(address, load, input), expected, "{} threads, step {}", ram.threads(), step);
+      }
+    }
+
+    // a clone keeps its threads and contents
+    let mut copy = rams[2].clone();
+    assert_eq!(copy.threads(), 4);
+    let address = gates::from_u16(63u16.wrapping_mul(4099) & 0x3fff)[2..16].try_into().unwrap();
+    assert_eq!(copy.tick(address, 0, [0; 16]), serial.tick(address, 0, [0; 16]));
+  }
 }
@@ -0,0 +1,36 @@
+#[cfg(test)]
+mod tests {
+  use nand_computer::{
+    gates,
+    shifter::{self, Shift},
+  };
+
+  fn expected(value: u16, amount: u32, op: Shift) -> u16 {
+    match op {
+      Shift::Shl => value << amount,
+      Shift::Shr => value >> amount,
+      Shift::Sar => ((value as i16) >> amount) as u16,
+      Shift::Rol => value.rotate_left(amount),
+      Shift::Ror => value.rotate_right(amount),
+    }
+  }
+
+  #[test]
+  fn test_every_amount_and_op() {
+    for value in [0u16, 1, 0x8000, 0xffff, 0x1234, 0xbeef, 0x7f01, 0xa5a5] {
+      for amount in 0..16u32 {
+        let bits: [u8; 4] = gates::from_u16(amount as u16)[12..16].try_into().unwrap();
+        for op in Shift::ALL {
+          let out = shifter::shift(gates::from_u16(value), bits, op);
+          assert_eq!(gates::to_u16(out), expected(value, amount, op), "{:#06x} {:?} {}", value, op, amount);
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn test_arithmetic_left_is_logical_left() {
+    let value = gates::from_u16(0xc003);
+    assert_eq!(shifter::barrel_shift(value, [0, 0, 1, 1], 0, 1, 0), shifter::shift(value, [0, 0, 1, 1], Shift::Shl));
+  }
+}
@@ -0,0 +1,160 @@
+#[cfg(test)]
+mod tests {
+  use nand_computer::{asm, computer::Computer, gates, instruction::Isa, mem::register::Register, snapshot};
+
+  // RAM[100 + i] = i for i in 0..3, then halt
+  const FILL: &str = "
+    @i
+    M=0
+  (LOOP)
+    @i
+    D=M
+    @3
+    D=D-A
+    @END
+    D;JEQ
+    @i
+    D=M
+    @100
+    D=D+A
+    @ptr
+    M=D
+    @i
+    D=M
+    @ptr
+    A=M
+    M=D
+    @i
+    M=M+1
+    @LOOP
+    0;JMP
+  (END)
+    @END
+    0;JMP
+  ";
+  fn load_error(bytes: &[u8]) -> String {
+    snapshot::load(bytes).err().expect("snapshot should be rejected")
+  }
+
+  fn computer() -> Computer {
+    Computer::new(asm::assemble(FILL).unwrap().words)
+  }
+
+  #[test]
+  fn test_register_save_restore() {
+    let mut register = Register::new();
+    register.tick(gates::from_u16(0xbeef), 1);
+
+    let mut bits = Vec::new();
+    register.save(&mut bits);
+    let mut restored = Register::new();
+    restored.restore(&mut bits.iter());
+
+    assert_eq!(bits.len(), 16);
+    assert_eq!(restored.output(), register.output());
+  }
+
+  #[test]
+  fn test_round_trip_resumes_identically() {
+    let mut original = computer();
+    original.poke(5000, 1234);
+    original.poke(16384, 0xffff);
+    original.set_key(75);
+    original.run(20);
+
+    let bytes = snapshot::save(&original);
+    let mut restored = snapshot::load(&bytes).unwrap();
+
+    assert_eq!((restored.pc(), restored.a(), restored.d()), (original.pc(), original.a(), original.d()));
+    assert_eq!(restored.cycle(), 20);
+    assert_eq!(restored.peek(5000), 1234);
+    assert_eq!(restored.peek(16384), 0xffff);
+    assert_eq!(restored.peek(24576), 75);
+    assert_eq!(restored.rom().words(), original.rom().words());
+
+    // both machines keep running in lock step
+    for _ in 0..60 {
+      assert_eq!(restored.step(), original.step());
+    }
+    assert_eq!(restored.peek(102), 2);
+    assert_eq!(snapshot::save(&restored), snapshot::save(&original));
+  }
+
+  #[test]
+  fn test_format_is_compact() {
+    let bytes = snapshot::save(&computer());
+
+    // one bit per DFF: RAM16K + screen dominate the size
+    assert!(bytes.len() < 16 * 1024 * 2 + 8 * 1024 * 2 + 256, "{} bytes", bytes.len());
+    assert_eq!(&bytes[0..8], snapshot::MAGIC);
+    assert_eq!(u16::from_le_bytes([bytes[8], bytes[9]]), snapshot::VERSION);
+  }
+
+  #[test]
+  fn test_load_validates() {
+    let bytes = snapshot::save(&computer());
+
+    let mut bad_magic = bytes.clone();
+    bad_magic[0] = b'X';
+    assert!(load_error(&bad_magic).contains("magic"));
+
+    let mut bad_version = bytes.clone();
+    bad_version[8] = 99;
+    assert!(load_error(&bad_version).contains("version 99"));
+
+    assert!(load_error(&bytes[..bytes.len() - 1]).contains("truncated"));
+
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert!(load_error(&trailing).contains("trailing"));
+
+    let mut bad_isa = bytes.clone();
+    bad_isa[48] = 7;
+    assert!(load_error(&bad_isa).contains("unknown instruction set 7"));
+
+    // shrink the CPU section by one byte
+    let mut bad_size = bytes[..14].to_vec();
+    bad_size.extend_from_slice(&5u32.to_le_bytes());
+    bad_size.extend_from_slice(&bytes[18..23]);
+    assert!(load_error(&bad_size).contains("section 'CPU ' has 5 bytes, expected 6"));
+  }
+
+  #[test]
+  fn test_file_round_trip() {
+    let path = std::env::temp_dir().join(format!("nand-computer-{}.snap", std::process::id()));
+    let mut original = computer();
+    original.run(10);
+
+    snapshot::save_file(&original, &path).unwrap();
+    let restored = snapshot::load_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(restored.pc(), original.pc());
+    assert!(snapshot::load_file(&path).is_err());
+  }
+
+  #[test]
+  fn test_instruction_set_round_trip() {
+    let mut original = computer();
+    original.set_isa(Isa::HackExt);
+    let restored = snapshot::load(&snapshot::save(&original)).unwrap();
+    assert_eq!(restored.isa(), Isa::HackExt);
+  }
+
+  #[test]
+  fn test_loads_version_1() {
+    let mut original = computer();
+    original.run(10);
+    let bytes = snapshot::save(&original);
+
+    // version 1: no ISA section (tag, length and one byte after CPU and CYCL)
+    let isa_at = 10 + (8 + 6) + (8 + 8);
+    assert_eq!(&bytes[isa_at..isa_at + 4], b"ISA ");
+    let mut old = bytes[..isa_at].to_vec();
+    old.extend_from_slice(&bytes[isa_at + 9..]);
+    old[8..10].copy_from_slice(&1u16.to_le_bytes());
+
+    let restored = snapshot::load(&old).unwrap();
+    assert_eq!((restored.isa(), restored.pc(), restored.cycle()), (Isa::Hack, original.pc(), 10));
+  }
+}
@@ -0,0 +1,89 @@
+#[cfg(test)]
+mod tests {
+  use nand_computer::{
+    adders::Adder,
+    alu,
+    bdd::{self, Equivalence},
+    hazard,
+    netlist::Logic,
+    techmap::{self, Gates},
+  };
+
+  fn mux() -> Gates {
+    let mut gates = Gates::new();
+    let (a, b, sel) = (gates.input("a"), gates.input("b"), gates.input("sel"));
+    let out = gates.mux(a, b, sel);
+    gates.output("out", out);
+    gates
+  }
+
+  #[test]
+  fn test_mux_maps_to_four_nands() {
+    let gates = mux();
+    let (naive, mapped) = (techmap::naive(&gates), techmap::map(&gates));
+    assert_eq!((naive.nand_count(), mapped.nand_count()), (8, 4));
+    for inputs in hazard::all_vectors(3) {
+      assert_eq!(mapped.eval(&inputs), gates.eval(&inputs), "{:?}", inputs);
+    }
+  }
+
+  #[test]
+  fn test_double_inversions_cancel() {
+    let mut gates = Gates::new();
+    let (a, b) = (gates.input("a"), gates.input("b"));
+    let not_a = gates.not(a);
+    let same = gates.not(not_a);
+    // and(a, b) | and(b, a): one nand each, shared, then nand(x, x) = not
+    let (left, right) = (gates.and(a, b), gates.and(b, a));
+    let or = gates.or(left, right);
+    gates.output("a", same);
+    gates.output("and", or);
+    let mapped = techmap::map(&gates);
+    assert_eq!(techmap::naive(&gates).nand_count(), 2 + 2 + 2 + 3);
+    assert_eq!(mapped.nand_count(), 2);
+    assert_eq!(mapped.outputs()[0].1, mapped.inputs()[0]);
+    for inputs in hazard::all_vectors(2) {
+      assert_eq!(mapped.eval(&inputs), gates.eval(&inputs));
+    }
+  }
+
+  #[test]
+  fn test_constants_fold() {
+    let mut gates = Gates::new();
+    let a = gates.input("a");
+    let (zero, one) = (gates.constant(0), gates.constant(1));
+    let (and, or) = (gates.and(a, zero), gates.or(a, one));
+    let xor = gates.xor(a, one);
+    gates.output("and", and);
+    gates.output("or", or);
+    gates.output("xor", xor);
+    let mapped = techmap::map(&gates);
+    assert_eq!(mapped.nand_count(), 1);
+    assert_eq!(mapped.eval(&[0]), vec![0, 1, 1]);
+    assert_eq!(mapped.eval(&[1]), vec![0, 1, 0]);
+  }
+
+  #[test]
+  fn test_full_adder_needs_nine_nands() {
+    let (_, full_adder) = techmap::chips().into_iter().find(|(chip, _)| chip == "FullAdder").unwrap();
+    assert_eq!(techmap::map(&full_adder).nand_count(), 9);
+  }
+
+  #[test]
+  fn test_every_chip_shrinks_and_stays_equivalent() {
+    for report in techmap::reports() {
+      assert!(report.mapped < report.naive, "{:?}", report);
+      assert!(report.equivalent, "{:?}", report);
+    }
+  }
+
+  #[test]
+  fn test_optimise_shrinks_nand_netlists() {
+    let netlist = alu::netlist(Adder::Ripple);
+    let optimised = techmap::optimise(&netlist);
+    assert!(optimised.nand_count() < netlist.nand_count());
+    assert_eq!(bdd::equivalent(&netlist, &optimised), Ok(Equivalence::Equivalent));
+    // nothing left to rewrite the second time
+    assert_eq!(techmap::optimise(&optimised).nand_count(), optimised.nand_count());
+  }
+}
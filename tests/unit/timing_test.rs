@@ -0,0 +1,126 @@
+#[cfg(test)]
+mod tests {
+  use nand_computer::{
+    adders::Adder,
+    alu, gates,
+    netlist::{Logic, Netlist},
+    timing::{self, Clock, Delays, Ram, Violation},
+  };
+
+  // x, y and the controls of `alu::netlist`
+  fn alu_inputs(x: u16, y: u16, controls: [u8; 6]) -> Vec<u8> {
+    gates::from_u16(x).into_iter().chain(gates::from_u16(y)).chain(controls).collect()
+  }
+
+  #[test]
+  fn test_critical_path_follows_the_depth() {
+    let delays = Delays::uniform(7);
+    for adder in Adder::ALL {
+      let netlist = alu::netlist(adder);
+      assert_eq!(timing::critical_path(&netlist, &delays), 7 * netlist.depth() as u64, "{}", adder);
+    }
+  }
+
+  #[test]
+  fn test_slower_gate_lengthens_the_path() {
+    let mut netlist = Netlist::new();
+    let (a, b) = (netlist.input("a"), netlist.input("b"));
+    let nand = netlist.nand(a, b);
+    let out = netlist.not(nand);
+    netlist.output("out", out);
+    let mut delays = Delays::uniform(10);
+    assert_eq!(timing::critical_path(&netlist, &delays), 20);
+    delays.set(nand, 35);
+    assert_eq!(timing::critical_path(&netlist, &delays), 45);
+    // the not's constant input never switches
+    assert_eq!(timing::shortest_path(&netlist, &delays), 45);
+  }
+
+  #[test]
+  fn test_not_chain_shortest_path_is_its_depth() {
+    let mut netlist = Netlist::new();
+    let mut signal = netlist.input("a");
+    for _ in 0..10 {
+      signal = netlist.not(signal);
+    }
+    netlist.output("out", signal);
+    let delays = Delays::uniform(10);
+    assert_eq!(timing::critical_path(&netlist, &delays), 10 * netlist.depth() as u64);
+    assert_eq!(timing::shortest_path(&netlist, &delays), 10 * netlist.depth() as u64);
+    assert_eq!(netlist.depth(), 10);
+    let clock = Clock { period: 1000, setup: 0, hold: 100 };
+    assert_eq!(timing::check(&netlist, &delays, clock), vec![]);
+  }
+
+  #[test]
+  fn test_simulation_settles_on_the_new_value() {
+    let netlist = alu::netlist(Adder::Ripple);
+    let delays = Delays::default();
+    // x + y: 0x7fff + 1 ripples the carry through every bit
+    let add = [0, 0, 0, 0, 1, 0];
+    let (before, after) = (alu_inputs(0x7fff, 0, add), alu_inputs(0x7fff, 1, add));
+    let transition = timing::simulate(&netlist, &delays, &before, &after);
+    assert!(transition.settle > 0);
+    assert!(transition.settle <= timing::critical_path(&netlist, &delays));
+    assert_eq!(timing::alu_settling(Adder::Ripple, &delays, &before, &after), transition.settle);
+
+    let mut values = netlist.values(&before);
+    for change in &transition.changes {
+      values[change.node.0] = change.value;
+    }
+    let outputs: Vec<u8> = netlist.outputs().iter().map(|(_, id)| values[id.0]).collect();
+    assert_eq!(outputs, netlist.eval(&after));
+  }
+
+  #[test]
+  fn test_faster_adder_settles_sooner() {
+    let delays = Delays::default();
+    let add = [0, 0, 0, 0, 1, 0];
+    let (before, after) = (alu_inputs(0xffff, 0, add), alu_inputs(0xffff, 1, add));
+    let ripple = timing::alu_settling(Adder::Ripple, &delays, &before, &after);
+    let kogge_stone = timing::alu_settling(Adder::KoggeStone, &delays, &before, &after);
+    assert!(kogge_stone < ripple, "{} {}", kogge_stone, ripple);
+  }
+
+  #[test]
+  fn test_setup_and_hold_violations() {
+    let netlist = alu::netlist(Adder::Ripple);
+    let delays = Delays::default();
+    let critical = timing::critical_path(&netlist, &delays);
+    let fits = Clock { period: critical + 20, setup: 20, hold: 0 };
+    assert_eq!(timing::check(&netlist, &delays, fits), vec![]);
+
+    let short = Clock { period: critical, ..fits };
+    let violations = timing::check(&netlist, &delays, short);
+    assert!(!violations.is_empty());
+    assert!(violations.iter().all(|violation| matches!(violation, Violation::Setup { .. })));
+
+    let hold = Clock { hold: 1000, ..fits };
+    assert!(timing::check(&netlist, &delays, hold).iter().any(|violation| matches!(violation, Violation::Hold { .. })));
+  }
+
+  #[test]
+  fn test_ram_reads_get_slower_with_size() {
+    let delays = Delays::default();
+    let times: Vec<u64> =
+      Ram::ALL.iter().map(|&ram| timing::critical_path(&timing::ram_read_netlist(ram), &delays)).collect();
+    assert!(times.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", times);
+
+    // the read path selects the addressed cell
+    let netlist = timing::ram_read_netlist(Ram::RAM8);
+    let mut inputs = vec![1, 0, 1];
+    inputs.extend((0..8).map(|cell| (cell == 5) as u8));
+    assert_eq!(netlist.eval(&inputs), vec![1]);
+  }
+
+  #[test]
+  fn test_report_table() {
+    let clock = Clock { period: 500, setup: 20, hold: 0 };
+    let reports = timing::reports(&Delays::default(), clock);
+    assert_eq!(reports.len(), Adder::ALL.len() + Ram::ALL.len());
+    let table = timing::report_table(&reports, clock);
+    assert!(table.contains("ALU (ripple)"));
+    assert!(table.contains("RAM16K read"));
+    assert!(table.contains("ALU (ripple): setup violation: out[0]"), "{}", table);
+  }
+}
@@ -0,0 +1,60 @@
+#[cfg(test)]
+mod tests {
+  use nand_computer::{
+    computer::Computer,
+    mem::rom32k,
+    trace::{self, Format, Tracer},
+  };
+
+  // @21, D=A, @16, M=D
+  const STORE: &str = "
+    0000000000010101
+    1110110000010000
+    0000000000010000
+    1110001100001000
+  ";
+
+  fn traced(format: Format) -> String {
+    let mut computer = Computer::new(rom32k::parse_hack(STORE).unwrap());
+    let mut tracer = Tracer::new(Vec::new(), format);
+    tracer.run(&mut computer, 4).unwrap();
+    String::from_utf8(tracer.into_inner()).unwrap()
+  }
+
+  #[test]
+  fn test_text_trace() {
+    let log = traced(Format::Text);
+    let lines: Vec<&str> = log.lines().collect();
+
+    assert_eq!(lines.len(), 5);
+    assert_eq!(lines[0], trace::text_header());
+    assert!(lines[1].contains("@21"));
+    assert!(lines[2].contains("D=A"));
+    assert!(lines[4].contains("M=D"));
+    assert!(lines[4].ends_with("RAM[16] <- 21"));
+  }
+
+  #[test]
+  fn test_json_lines_trace() {
+    let log = traced(Format::JsonLines);
+    let lines: Vec<&str> = log.lines().collect();
+
+    assert_eq!(lines.len(), 4);
+    assert_eq!(
+      lines[1],
+      "{\"cycle\":1,\"pc\":1,\"instruction\":\"1110110000010000\",\"asm\":\"D=A\",\"a\":21,\"d\":21,\
+       \"alu\":{\"zx\":1,\"nx\":1,\"zy\":0,\"ny\":0,\"f\":0,\"no\":0},\"zr\":0,\"ng\":0,\"read\":null,\"write\":null}"
+    );
+    assert!(lines[3].ends_with("\"read\":null,\"write\":{\"address\":16,\"value\":21}}"));
+  }
+
+  #[test]
+  fn test_formats_print_the_same_words() {
+    // D=-1
+    let mut computer = Computer::new(rom32k::parse_hack("1110111010010000").unwrap());
+    let step = computer.step();
+
+    assert!(trace::format_text(&step).contains(" 65535 "));
+    assert!(trace::format_json(&step).contains("\"d\":65535,"));
+  }
+}
@@ -0,0 +1,81 @@
+#[cfg(test)]
+mod tests {
+  use nand_computer::{
+    computer::Computer,
+    gates,
+    mem::{register::Register, rom32k},
+    vcd::{self, Writer},
+  };
+
+  #[test]
+  fn test_writer_header_and_changes() {
+    let mut vcd = Writer::new(Vec::new(), "1ns");
+    let clk = vcd.signal("top", "clk", 1);
+    let out = vcd.signal("top.reg", "out", 16);
+
+    // a register dump: load 5 on the first edge, hold it on the second
+    let mut register = Register::new();
+    for (cycle, (input, load)) in [(5, 1), (9, 0)].into_iter().enumerate() {
+      let time = cycle as u64 * 10;
+      vcd.change(time, clk, 0).unwrap();
+      vcd.change(time, out, gates::to_u16(register.output()) as u64).unwrap();
+      register.tick(gates::from_u16(input), load);
+      vcd.change(time + 5, clk, 1).unwrap();
+      vcd.change(time + 5, out, gates::to_u16(register.output()) as u64).unwrap();
+    }
+    let dump = String::from_utf8(vcd.finish(20).unwrap()).unwrap();
+
+    assert_eq!(
+      dump,
+      "$version nand-computer $end\n\
+       $timescale 1ns $end\n\
+       $scope module top $end\n\
+       $var wire 1 ! clk $end\n\
+       $scope module reg $end\n\
+       $var reg 16 \" out [15:0] $end\n\
+       $upscope $end\n\
+       $upscope $end\n\
+       $enddefinitions $end\n\
+       #0\n0!\nb0 \"\n\
+       #5\n1!\nb101 \"\n\
+       #10\n0!\n\
+       #15\n1!\n\
+       #20\n"
+    );
+  }
+
+  #[test]
+  fn test_time_must_not_go_backwards() {
+    let mut vcd = Writer::new(Vec::new(), "1ns");
+    let clk = vcd.signal("top", "clk", 1);
+
+    vcd.change(10, clk, 1).unwrap();
+    assert!(vcd.change(5, clk, 0).is_err());
+  }
+
+  #[test]
+  fn test_record_computer() {
+    // @21, D=A, @16, M=D
+    let program =
+      rom32k::parse_hack("0000000000010101\n1110110000010000\n0000000000010000\n1110001100001000\n").unwrap();
+    let mut computer = Computer::new(program);
+
+    let dump = String::from_utf8(vcd::record(&mut computer, 4, Vec::new()).unwrap()).unwrap();
+
+    assert!(dump.contains("$scope module alu $end"));
+    assert!(dump.contains("$var reg 15 \" pc [14:0] $end"));
+    assert!(dump.contains("$var wire 1 / zr $end"));
+    // A is loaded with 21 on the first rising edge
+    assert!(dump.contains("#5\n1!\nb1 \"\nb10101 $\n"), "{}", dump);
+    // M=D drives the memory bus in the fourth cycle
+    assert!(dump.contains("#30\n0!\nb1110001100001000 #\nb10000 '\n"));
+    assert!(dump.contains("b10000 1\n14\n"));
+    // `in` already carries outM = D = 21, `out` stays at RAM[16] = 0 until the edge
+    let before_edge = &dump[..dump.find("#35").unwrap()];
+    assert!(before_edge.contains("b10101 2\n"));
+    assert!(!before_edge.contains("b10101 3\n"));
+    // and RAM[16] shows the write after the rising edge
+    assert!(dump.contains("#35\n1!\nb100 \"\nb10101 3\n"), "{}", dump);
+    assert!(dump.ends_with("#40\n"));
+  }
+}
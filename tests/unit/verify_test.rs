@@ -0,0 +1,68 @@
+#[cfg(test)]
+mod tests {
+  use nand_computer::{
+    gates,
+    verify::{self, Rng},
+  };
+
+  #[test]
+  fn test_suite_passes() {
+    let report = verify::suite(verify::DEFAULT_CASES, verify::DEFAULT_SEED);
+    assert!(report.ok(), "{}", report);
+    assert!(report.passed.iter().any(|(check, vectors)| check == "full_adder" && *vectors == 8));
+    assert!(report.passed.iter().any(|(check, vectors)| check == "dmux8way" && *vectors == 16));
+    assert!(report.passed.iter().any(|(check, vectors)| check == "add_16[kogge-stone]" && *vectors == 1 << 16));
+    assert!(report.passed.iter().any(|(check, vectors)| check == "alu" && *vectors == 1 << 14));
+    assert!(report.to_string().ends_with(&format!("{} passed, 0 failed", report.passed.len())));
+  }
+
+  #[test]
+  fn test_exhaustive_reports_the_failing_vector() {
+    // an `and` that is wrong for a = 1, b = 0
+    let broken = |v: &[u8]| if v == [1, 0] { 1 } else { gates::and(v[0], v[1]) };
+    let failure = verify::exhaustive("and", 2, broken, |v| v[0] & v[1]).unwrap_err();
+
+    assert_eq!(failure.inputs, "1 0");
+    assert_eq!(failure.to_string(), "and: inputs 1 0 expected 0 got 1");
+  }
+
+  #[test]
+  fn test_exhaustive_sliced_reports_the_failing_vector() {
+    // a sliced `or` that is wrong in lane 6, first hit by vector 0 0 0 0 1 1 0
+    let broken = |v: &[u64]| vec![v.iter().fold(0, |or, word| or | word) & !(1 << 6)];
+    let or = |v: &[u8]| vec![v.iter().fold(0, |or, bit| or | bit)];
+    assert_eq!(verify::exhaustive_sliced("or", 6, |v| vec![v.iter().fold(0, |or, word| or | word)], or), Ok(64));
+    let failure = verify::exhaustive_sliced("or", 7, broken, or).unwrap_err();
+
+    assert_eq!(failure.to_string(), "or: inputs 0 0 0 0 1 1 0 expected [1] got [0]");
+  }
+
+  #[test]
+  fn test_property_reports_the_failing_vector() {
+    let mut rng = Rng::new(1);
+    // saturating instead of wrapping add: fails as soon as a sum overflows
+    let failure = verify::property(
+      "add",
+      1000,
+      &mut rng,
+      |rng| (rng.next_u16(), rng.next_u16()),
+      |&(x, y): &(u16, u16)| (x.wrapping_add(y), x.saturating_add(y)),
+    )
+    .unwrap_err();
+
+    let (x, y) = failure.inputs.trim_matches(|c| c == '(' || c == ')').split_once(", ").unwrap();
+    let (x, y): (u16, u16) = (x.parse().unwrap(), y.parse().unwrap());
+    assert!(x.checked_add(y).is_none());
+    assert_eq!(failure.expected, x.wrapping_add(y).to_string());
+    assert_eq!(failure.actual, "65535");
+  }
+
+  #[test]
+  fn test_rng_is_deterministic() {
+    let (mut a, mut b) = (Rng::new(42), Rng::new(42));
+    for _ in 0..100 {
+      assert_eq!(a.next_u64(), b.next_u64());
+    }
+    assert_ne!(Rng::new(1).next_u64(), Rng::new(2).next_u64());
+  }
+}
@@ -0,0 +1,189 @@
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use nand_computer::{
+    adders::{self, Adder},
+    asm,
+    bdd::{self, Equivalence},
+    computer::Computer,
+    fault, gates,
+    netlist::{Netlist, Node},
+    verilog::{self, Design, Options},
+  };
+
+  fn design(options: Options) -> Design {
+    verilog::parse(&verilog::export(options)).unwrap()
+  }
+
+  fn input_names(netlist: &Netlist) -> Vec<String> {
+    let name = |id| match netlist.node(id) {
+      Node::Input(name) => name.clone(),
+      _ => unreachable!(),
+    };
+    netlist.inputs().iter().map(|&id| name(id)).collect()
+  }
+
+  // one clock edge of a flattened chip: the port outputs before the edge and
+  // the DFF state after it
+  fn clock(netlist: &Netlist, ports: &[u8], state: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let inputs: Vec<u8> = ports.iter().chain(state).copied().collect();
+    let mut outputs = netlist.eval(&inputs);
+    let state = outputs.split_off(outputs.len() - state.len());
+    (outputs, state)
+  }
+
+  #[test]
+  fn test_every_gate_chip_round_trips() {
+    let design = design(Options::default());
+    for (name, netlist) in verilog::gate_chips() {
+      let parsed = design.netlist(name).unwrap();
+      assert_eq!(input_names(&parsed), input_names(&netlist), "{}", name);
+      // NANDs that reach no output (the adders' carry out) are dropped
+      assert!(parsed.nand_count() <= netlist.nand_count(), "{}", name);
+      if name == "CPULogic" {
+        // too many inputs for the BDDs to stay quick, see the CPU test
+        continue;
+      }
+      assert_eq!(bdd::equivalent(&netlist, &parsed), Ok(Equivalence::Equivalent), "{}", name);
+    }
+  }
+
+  #[test]
+  fn test_add16_is_a_ripple_of_full_adders() {
+    let design = design(Options::default());
+    let add16 = design.netlist("Add16").unwrap();
+    assert_eq!(bdd::equivalent(&adders::netlist(Adder::Ripple), &add16), Ok(Equivalence::Equivalent));
+    let full_adder = design.netlist("FullAdder").unwrap();
+    for (a, b, c) in [(0, 0, 0), (0, 1, 1), (1, 0, 1), (1, 1, 0), (1, 1, 1)] {
+      let (sum, carry) = gates::full_adder(a, b, c);
+      assert_eq!(full_adder.eval(&[a, b, c]), vec![sum, carry]);
+    }
+  }
+
+  #[test]
+  fn test_muxes_match_the_gates() {
+    let design = design(Options::default());
+    let (mux, dmux) = (design.netlist("Mux4Way16").unwrap(), design.netlist("DMux8Way").unwrap());
+    let buses = [0x1111u16, 0x2222, 0x3333, 0x4444].map(gates::from_u16);
+    for sel in [[0, 0], [1, 0], [0, 1], [1, 1]] {
+      let inputs: Vec<u8> = buses.iter().flatten().copied().chain(sel).collect();
+      let expected = gates::mux4way16(buses[0], buses[1], buses[2], buses[3], sel);
+      assert_eq!(mux.eval(&inputs), expected.to_vec(), "{:?}", sel);
+    }
+    for sel in 0..8u8 {
+      let sel = [(sel >> 2) & 1, (sel >> 1) & 1, sel & 1];
+      let (a, b, c, d, e, f, g, h) = gates::dmux8way(1, sel);
+      assert_eq!(dmux.eval(&[1, sel[0], sel[1], sel[2]]), vec![a, b, c, d, e, f, g, h], "{:?}", sel);
+    }
+  }
+
+  #[test]
+  fn test_ram8_matches_the_chip() {
+    let netlist = design(Options::default()).netlist("RAM8").unwrap();
+    let names = input_names(&netlist);
+    assert_eq!(&names[..3], ["clk", "in[0]", "in[1]"]);
+    assert_eq!(names[21], "register0.bit0.dff.q");
+    assert_eq!(netlist.inputs().len(), 21 + 8 * 16);
+
+    for ops in fault::ram8_tests() {
+      let mut state = vec![0; 8 * 16];
+      let mut outputs = Vec::new();
+      for op in &ops {
+        let address = [(op.address >> 2) & 1, (op.address >> 1) & 1, op.address & 1].map(|bit| bit as u8);
+        let mut ports: Vec<u8> =
+          [0].into_iter().chain(gates::from_u16(op.input)).chain([op.load]).chain(address).collect();
+        state = clock(&netlist, &ports, &state).1;
+        ports[17] = 0;
+        outputs.push(gates::to_u16(clock(&netlist, &ports, &state).0.try_into().unwrap()));
+      }
+      assert_eq!(outputs, fault::run_ram8(&ops, &[]));
+    }
+  }
+
+  #[test]
+  fn test_cpu_runs_in_step_with_the_computer() {
+    let source = "
+      @10
+      D=A
+      @count
+      M=D
+      @sum
+      M=0
+    (LOOP)
+      @count
+      D=M
+      @END
+      D;JEQ
+      @sum
+      M=D+M
+      @count
+      M=M-1
+      @LOOP
+      0;JMP
+    (END)
+      @END
+      0;JMP
+    ";
+    let program = asm::assemble(source).unwrap().words;
+    let netlist = design(Options::default()).netlist("CPU").unwrap();
+    let names = input_names(&netlist);
+    assert_eq!(names[34], "a_register.bit0.dff.q");
+    assert_eq!(names[34 + 32], "pc_register.bit0.dff.q");
+
+    let mut computer = Computer::new(program.clone());
+    let mut memory: HashMap<u16, u16> = HashMap::new();
+    let mut state = vec![0; 48];
+    for cycle in 0..200 {
+      let a = gates::to_u16(state[..16].try_into().unwrap());
+      let pc = gates::to_u16(state[32..].try_into().unwrap());
+      let in_m = memory.get(&(a & 0x7fff)).copied().unwrap_or(0);
+      let ports: Vec<u8> =
+        [0].into_iter().chain(gates::from_u16(in_m)).chain(program[pc as usize]).chain([0]).collect();
+      let (outputs, next) = clock(&netlist, &ports, &state);
+      if outputs[16] == 1 {
+        memory.insert(a & 0x7fff, gates::to_u16(outputs[..16].try_into().unwrap()));
+      }
+      state = next;
+
+      let step = computer.step();
+      let registers: Vec<u16> = state.chunks(16).map(|bits| gates::to_u16(bits.try_into().unwrap())).collect();
+      assert_eq!(registers, [step.a, step.d, step.next_pc], "cycle {}", cycle);
+    }
+    assert_eq!(memory.get(&17), Some(&55));
+    assert_eq!(computer.peek(17), 55);
+  }
+
+  #[test]
+  fn test_behavioural_memory_is_not_flattened() {
+    let source = verilog::export(Options { behavioural_memory: true });
+    assert!(source.contains("reg [0:15] memory [0:16383];"));
+    let design = verilog::parse(&source).unwrap();
+    assert!(design.module("RAM16K").unwrap().behavioural);
+    assert_eq!(
+      design.netlist("CPU").map(|netlist| netlist.nand_count()),
+      self::design(Options::default()).netlist("CPU").map(|netlist| netlist.nand_count())
+    );
+    assert_eq!(design.netlist("RAM8").err(), Some("behavioural module 'RAM8' cannot be flattened".to_string()));
+  }
+
+  #[test]
+  fn test_parser_reports_errors() {
+    let loop_source = "module Loop (input a, output out);
+  wire x;
+  nand g (x, a, x);
+  assign out = x;
+endmodule
+";
+    let design = verilog::parse(loop_source).unwrap();
+    assert_eq!(design.netlist("Loop").err(), Some("combinational loop through 'x'".to_string()));
+    assert_eq!(
+      verilog::parse("module M (input a);\n  assign = a;\nendmodule").err(),
+      Some("line 2: expected a name".to_string())
+    );
+    let undriven = verilog::parse("module M (input a, output b); endmodule").unwrap();
+    assert_eq!(undriven.netlist("M").err(), Some("'b' is not driven".to_string()));
+    let doubled = verilog::parse("module M (input a, output b); assign b = a; assign b = 1'b0; endmodule").unwrap();
+    assert_eq!(doubled.netlist("M").err(), Some("'b' has more than one driver".to_string()));
+  }
+}